@@ -0,0 +1,123 @@
+//! Templates bundled in archives.
+//!
+//! Only available with the feature `archive`. Supported are `.zip`, `.tar`,
+//! `.tar.gz` and `.tgz` files. The regular files of an archive are read with
+//! their paths inside the archive, directories and links are skipped. An
+//! entry with an absolute path or a `..` component is an error.
+
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::read::MultiGzDecoder;
+
+/// A regular file of an archive.
+#[derive(Debug)]
+pub struct Entry {
+    /// The path inside the archive, without a leading `./`.
+    pub path: PathBuf,
+    pub content: Vec<u8>,
+}
+
+/// Read the regular files of an archive whose paths are accepted by
+/// `filter`, sorted by path. Other files are not decompressed.
+pub fn read(path: &Path, filter: impl Fn(&Path) -> bool) -> Result<Vec<Entry>, ArchiveError> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    let file = BufReader::new(File::open(path)?);
+
+    let mut entries = if name.ends_with(".zip") {
+        read_zip(file, filter)?
+    } else if name.ends_with(".tar") {
+        read_tar(file, filter)?
+    } else {
+        read_tar(MultiGzDecoder::new(file), filter)?
+    };
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+fn read_zip(
+    file: BufReader<File>,
+    filter: impl Fn(&Path) -> bool,
+) -> Result<Vec<Entry>, ArchiveError> {
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut entries = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if !file.is_file() {
+            continue;
+        }
+        let path = entry_path(Path::new(file.name()))?;
+        if !filter(&path) {
+            continue;
+        }
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        entries.push(Entry { path, content });
+    }
+    Ok(entries)
+}
+
+fn read_tar(reader: impl Read, filter: impl Fn(&Path) -> bool) -> Result<Vec<Entry>, ArchiveError> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry_path(&entry.path()?)?;
+        if !filter(&path) {
+            continue;
+        }
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        entries.push(Entry { path, content });
+    }
+    Ok(entries)
+}
+
+/// The path of an entry without `.` components. Absolute paths and `..`
+/// components are refused.
+fn entry_path(path: &Path) -> Result<PathBuf, ArchiveError> {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .map(|component| match component {
+            Component::Normal(name) => Ok(name),
+            _ => Err(ArchiveError::UnsafePath(path.to_owned())),
+        })
+        .collect()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ArchiveError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Entry leaves the archive: {0:?}")]
+    UnsafePath(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_paths() {
+        assert_eq!(
+            entry_path(Path::new("./partials/footer.md")).unwrap(),
+            PathBuf::from("partials/footer.md")
+        );
+        assert!(entry_path(Path::new("../outside.md")).is_err());
+        assert!(entry_path(Path::new("/etc/passwd")).is_err());
+    }
+}
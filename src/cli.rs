@@ -1,40 +1,241 @@
-use std::path::PathBuf;
+use std::{num::NonZeroUsize, path::PathBuf};
 
 use clap::{command, value_parser, Arg, ArgAction, Command};
 
 /// Get the CLI definition as a [`clap::Command`].
 pub fn get_cli() -> Command {
     let command = command!("docfmt")
+        .subcommand_negates_reqs(true)
+        .subcommand(
+            Command::new("validate")
+                .about("Check that the main template and all includes parse, without rendering.")
+                .arg(
+                    Arg::new("template")
+                        .value_parser(value_parser!(PathBuf))
+                        .help(concat!(
+                            "Path to the main file defining the document structure. ",
+                            "May be omitted if a configuration defines it."
+                        )),
+                ),
+        )
+        .subcommand(
+            Command::new("lint")
+                .about(concat!(
+                    "Report templates not reachable from the main template, references ",
+                    "to missing partials and variables missing in the data, without rendering."
+                ))
+                .arg(
+                    Arg::new("template")
+                        .value_parser(value_parser!(PathBuf))
+                        .help(concat!(
+                            "Path to the main file defining the document structure. ",
+                            "May be omitted if a configuration defines it."
+                        )),
+                )
+                .arg(
+                    Arg::new("schema")
+                        .long("schema")
+                        .value_parser(value_parser!(PathBuf))
+                        .help(concat!(
+                            "JSON Schema of the data. Variables declared in it are not ",
+                            "reported as missing."
+                        )),
+                ),
+        )
+        .subcommand(
+            Command::new("graph")
+                .about(concat!(
+                    "Print the graph of the templates included by the main template, ",
+                    "without rendering."
+                ))
+                .arg(
+                    Arg::new("template")
+                        .value_parser(value_parser!(PathBuf))
+                        .help(concat!(
+                            "Path to the main file defining the document structure. ",
+                            "May be omitted if a configuration defines it."
+                        )),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["dot", "json"])
+                        .default_value("dot")
+                        .help("Format of the graph, `dot` for Graphviz or `json`."),
+                ),
+        )
+        .subcommand(Command::new("init").about(concat!(
+            "Write a starter configuration, main template and data file ",
+            "into the current directory."
+        )))
         .arg(
             Arg::new("template")
                 .value_parser(value_parser!(PathBuf))
-                .required_unless_present("config")
                 .help(concat!(
                     "Path to the main file defining the document structure. ",
-                    "May be omitted if a config file is given."
+                    "May be omitted if a configuration defines it."
                 )),
         )
         .arg(
             Arg::new("output")
                 .value_parser(value_parser!(PathBuf))
-                .required_unless_present("config")
                 .help(concat!(
-                    "Path to the output file. ",
-                    "May be omitted if a config file is given."
+                    "Path to the output file, `-` writes to stdout. ",
+                    "May be omitted if a configuration defines it or an output template is given."
+                )),
+        )
+        .arg(
+            Arg::new("output_template")
+                .long("output-template")
+                .value_parser(value_parser!(String))
+                .conflicts_with("output")
+                .help(concat!(
+                    "Template of the output path, rendered against the data. ",
+                    "Replaces the output file."
                 )),
         )
+        .arg(
+            Arg::new("each")
+                .long("each")
+                .value_parser(value_parser!(String))
+                .requires("output_template")
+                .help(concat!(
+                    "JSON pointer to an array in the data. ",
+                    "Renders one output per item, the path is given by the output template."
+                )),
+        )
+        .arg(
+            Arg::new("concat")
+                .long("concat")
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Append)
+                .conflicts_with_all(["each", "output_dir"])
+                .help(concat!(
+                    "Name of a template to render into the output, in the given order. ",
+                    "Can be used multiple times, the outputs are joined by the separator."
+                )),
+        )
+        .arg(
+            Arg::new("separator")
+                .long("separator")
+                .value_parser(value_parser!(String))
+                .allow_hyphen_values(true)
+                .help("Text between the outputs of the concatenated templates, e.g. `---`."),
+        )
+        .arg(
+            Arg::new("output_dir")
+                .long("output-dir")
+                .value_parser(value_parser!(PathBuf))
+                .conflicts_with_all(["output", "output_template", "each"])
+                .help(concat!(
+                    "Render each registered template matching the output pattern into this directory ",
+                    "as `<name>.<ext>`. Replaces the output file."
+                )),
+        )
+        .arg(
+            Arg::new("output_pattern")
+                .long("output-pattern")
+                .value_parser(value_parser!(String))
+                .requires("output_dir")
+                .help("Glob pattern of the template names rendered into the output directory, defaults to all."),
+        )
+        .arg(
+            Arg::new("output_ext")
+                .long("output-ext")
+                .value_parser(value_parser!(String))
+                .requires("output_dir")
+                .help("Extension of the files written to the output directory, defaults to `md`."),
+        )
+        .arg(
+            Arg::new("fail_fast")
+                .long("fail-fast")
+                .action(ArgAction::SetTrue)
+                .help("Stop at the first render target that fails instead of rendering the others."),
+        )
+        .arg(
+            Arg::new("output_format")
+                .long("output-format")
+                .value_parser(["text", "json", "toml"])
+                .help("Validate the rendered output as JSON or TOML before writing it, defaults to `text`."),
+        )
+        .arg(
+            Arg::new("pretty")
+                .long("pretty")
+                .action(ArgAction::SetTrue)
+                .help("Pretty-print the rendered output in the output format before writing it."),
+        )
+        .arg(
+            Arg::new("line_ending")
+                .long("line-ending")
+                .value_parser(["lf", "crlf", "native"])
+                .help("Normalize the line endings of the output, by default they are kept as rendered."),
+        )
+        .arg(
+            Arg::new("collapse_blank_lines")
+                .long("collapse-blank-lines")
+                .action(ArgAction::SetTrue)
+                .help("Collapse consecutive blank lines of the output into a single blank line."),
+        )
+        .arg(
+            Arg::new("safe")
+                .long("safe")
+                .action(ArgAction::SetTrue)
+                .help("Refuse output paths rendered from the output template that are absolute or contain `..`."),
+        )
+        .arg(
+            Arg::new("allow_overwrite_source")
+                .long("allow-overwrite-source")
+                .action(ArgAction::SetTrue)
+                .help("Allow the output to overwrite the main template, an included file or a data file."),
+        )
+        .arg(
+            Arg::new("append")
+                .long("append")
+                .action(ArgAction::SetTrue)
+                .help("Append to the output file instead of replacing it."),
+        )
+        .arg(
+            Arg::new("check_links")
+                .long("check-links")
+                .action(ArgAction::SetTrue)
+                .help("Exit with an error if a local link of the written output points to a missing file."),
+        )
+        .arg(
+            Arg::new("report_unused")
+                .long("report-unused")
+                .action(ArgAction::SetTrue)
+                .help("Warn about the templates never included while rendering."),
+        )
+        .arg(
+            Arg::new("report_usage")
+                .long("report-usage")
+                .action(ArgAction::SetTrue)
+                .help("Warn about the top-level keys of the data no rendered template can use, found by scanning the templates."),
+        )
         .arg(
             Arg::new("config")
                 .short('c')
                 .long("config")
+                .global(true)
                 .value_parser(value_parser!(PathBuf))
                 .action(ArgAction::Append)
-                .help("Path to a TOML file containing the configuration."),
+                .help(concat!(
+                    "Path to a TOML, JSON or JSON5 file containing the configuration. ",
+                    "If defined multiple times, later files take precedence."
+                )),
+        )
+        .arg(
+            Arg::new("entry")
+                .long("entry")
+                .global(true)
+                .value_parser(value_parser!(String))
+                .help("Name of the template to render, defaults to `main`."),
         )
         .arg(
             Arg::new("include")
                 .short('i')
                 .long("include")
+                .global(true)
                 .value_parser(value_parser!(PathBuf))
                 .action(ArgAction::Append)
                 .help(concat!(
@@ -48,28 +249,200 @@ pub fn get_cli() -> Command {
             Arg::new("extension")
                 .short('e')
                 .long("ext")
+                .global(true)
                 .value_parser(value_parser!(String))
                 .action(ArgAction::Append)
                 .default_values(vec!["md", "markdown"])
                 .value_delimiter(',')
                 .help("Comma-separated list of file extensions to include in directories."),
         )
+        .arg(
+            Arg::new("no_default_extensions")
+                .long("no-default-extensions")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Only include the extensions given by `--ext` or the configuration, not `md` and `markdown`."),
+        )
+        .arg(
+            Arg::new("partial_extension")
+                .long("partial-ext")
+                .global(true)
+                .value_parser(value_parser!(String))
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("Comma-separated list of file extensions of partials to include in directories in addition to `--ext`."),
+        )
         .arg(
             Arg::new("data")
                 .short('d')
                 .long("data")
+                .global(true)
                 .value_parser(value_parser!(PathBuf))
                 .action(ArgAction::Append)
                 .help(concat!(
                     "File containing data to be used in the document. ",
-                    "May be a JSON or TOML file. The type is determined by the file extension. ",
+                    "May be a JSON, JSON5, TOML or YAML file. The type is determined by the file extension. ",
+                    "Use `-` to read from stdin with the format given by `--data-format`. ",
                     "If defined multiple times, the data is merged.",
                 )),
         )
+        .arg(
+            Arg::new("secrets")
+                .long("secrets")
+                .global(true)
+                .value_parser(value_parser!(PathBuf))
+                .action(ArgAction::Append)
+                .help("Data file like `--data` whose values are masked in the log and in `--dump-data`, e.g. a `.env` file."),
+        )
+        .arg(
+            Arg::new("sensitive")
+                .long("sensitive")
+                .global(true)
+                .action(ArgAction::Append)
+                .help("JSON pointer of a data key whose values are masked in the log and in `--dump-data`, e.g. `/db/password`."),
+        )
+        .arg(
+            Arg::new("data_format")
+                .long("data-format")
+                .global(true)
+                .value_parser(["json", "json5", "toml", "yaml", "ini", "env"])
+                .help("Format of the data read from stdin with `-d -`."),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .global(true)
+                .value_name("NAME")
+                .help("Merge the overlay `<name>.<profile>.<ext>` of each data file after it, if it exists."),
+        )
+        .arg(
+            Arg::new("data_cmd")
+                .long("data-cmd")
+                .global(true)
+                .value_name("COMMAND:FORMAT")
+                .action(ArgAction::Append)
+                .help(concat!(
+                    "Run a command and read its standard output as data in the format given by the suffix, ",
+                    "e.g. `./gen-data.sh:json`. The output is merged after the data files.",
+                )),
+        )
+        .arg(
+            Arg::new("raw_dir")
+                .long("raw-dir")
+                .global(true)
+                .value_parser(value_parser!(PathBuf))
+                .help("Base directory of the files inserted with `include_raw` and hashed with `file_sha256`, defaults to the current directory."),
+        )
+        .arg(
+            Arg::new("root")
+                .long("root")
+                .global(true)
+                .value_parser(value_parser!(PathBuf))
+                .help("Base directory of the names of templates in included directories, defaults to the parent of each directory."),
+        )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .global(true)
+                .value_parser(value_parser!(NonZeroUsize))
+                .help("Number of threads reading data files, defaults to the number of logical CPUs."),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .global(true)
+                .value_parser(value_parser!(std::num::NonZeroU64))
+                .help("Seconds rendering may take before the process is terminated."),
+        )
+        .arg(
+            Arg::new("max_depth")
+                .long("max-depth")
+                .global(true)
+                .value_parser(value_parser!(NonZeroUsize))
+                .help("Maximum depth of the files registered from included directories, 1 registers only their direct files."),
+        )
+        .arg(
+            Arg::new("max_templates")
+                .long("max-templates")
+                .global(true)
+                .value_parser(value_parser!(NonZeroUsize))
+                .help("Maximum number of registered templates, exceeding it is an error."),
+        )
+        .arg(
+            Arg::new("ignore_file")
+                .long("ignore-file")
+                .global(true)
+                .value_parser(value_parser!(PathBuf))
+                .help(concat!(
+                    "File with gitignore-style patterns of files to skip in included directories. ",
+                    "Applied in addition to a .docfmtignore in each included directory."
+                )),
+        )
+        .arg(
+            Arg::new("merge_arrays")
+                .long("merge-arrays")
+                .global(true)
+                .value_parser(["replace", "append"])
+                .help("How arrays defined by several data sources are merged, defaults to `replace`."),
+        )
+        .arg(
+            Arg::new("no_builtins")
+                .long("no-builtins")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Do not add the built-in values `docfmt.now` and `docfmt.date` to the data."),
+        )
+        .arg(
+            Arg::new("no_default_data")
+                .long("no-default-data")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help(concat!(
+                    "Do not read docfmt.data.toml or docfmt.data.json ",
+                    "from the directory of the configuration file."
+                )),
+        )
+        .arg(
+            Arg::new("env")
+                .long("env")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Add the environment variables to the data under the key `env`."),
+        )
+        .arg(
+            Arg::new("no_escape")
+                .long("no-escape")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Do not HTML-escape the values of `{{ }}` expressions."),
+        )
+        .arg(
+            Arg::new("date_format")
+                .long("date-format")
+                .global(true)
+                .value_parser(value_parser!(String))
+                .help("Format of the built-in value `docfmt.date`. Defaults to `%Y-%m-%d`."),
+        )
+        .arg(
+            Arg::new("locale")
+                .long("locale")
+                .global(true)
+                .value_parser(value_parser!(String))
+                .help("Locale of the translations looked up by the helper `t`, e.g. `de-AT`."),
+        )
+        .arg(
+            Arg::new("fallback_locale")
+                .long("fallback-locale")
+                .global(true)
+                .value_parser(value_parser!(String))
+                .help("Locale whose translations are used if the locale has none, e.g. `en`."),
+        )
         .arg(
             Arg::new("force")
                 .short('f')
                 .long("force")
+                .global(true)
                 .action(ArgAction::SetTrue)
                 .help("Force overwriting of the output file."),
         )
@@ -77,24 +450,136 @@ pub fn get_cli() -> Command {
             Arg::new("strict")
                 .short('s')
                 .long("strict")
+                .global(true)
                 .action(ArgAction::SetTrue)
                 .help("Restrict accessing non-existing fields or indices in templates."),
         )
+        .arg(
+            Arg::new("print_templates")
+                .long("print-templates")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print the sorted names of the registered templates to stderr and exit without rendering."),
+        )
+        .arg(
+            Arg::new("dump_data")
+                .long("dump-data")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print the data as JSON to stdout with secrets masked and exit without rendering."),
+        )
+        .arg(
+            Arg::new("strict_data")
+                .long("strict-data")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Exit with an error if a top-level data key can't be used by any rendered template, found by scanning the templates."),
+        )
+        .arg(
+            Arg::new("require_data")
+                .long("require-data")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Exit with an error if the merged data is empty."),
+        )
+        .arg(
+            Arg::new("sidecar_data")
+                .long("sidecar-data")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help(concat!(
+                    "Render an included template with the data file of the same stem next to it, ",
+                    "e.g. foo.json next to foo.hbs, merged over its context."
+                )),
+        )
+        .arg(
+            Arg::new("strict_undefined_partials")
+                .long("strict-undefined-partials")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Exit with an error if a template references a partial that is not registered."),
+        )
+        .arg(
+            Arg::new("fail_on_warn")
+                .long("fail-on-warn")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Exit with an error if a warning was logged, before writing any output."),
+        )
         .arg(
             Arg::new("verbose")
                 .short('v')
                 .long("verbose")
+                .global(true)
+                .action(ArgAction::Count)
+                .help("Print verbose output. Repeat for trace output."),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .global(true)
                 .action(ArgAction::SetTrue)
-                .help("Print verbose output."),
+                .conflicts_with("verbose")
+                .help("Only print errors."),
+        )
+        .arg(
+            Arg::new("log_format")
+                .long("log-format")
+                .global(true)
+                .value_parser(["text", "json"])
+                .help("Format of the log written to stderr, `text` or one JSON object per line."),
+        )
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .global(true)
+                .value_parser(value_parser!(PathBuf))
+                .help(concat!(
+                    "Write a JSON report of the registered templates, data files, outputs, ",
+                    "timings, errors and warnings to this file."
+                )),
         );
 
     #[cfg(unix)]
     let command = command.arg(
         Arg::new("follow")
             .long("follow")
+            .global(true)
             .action(ArgAction::SetTrue)
             .help("Follow symlinks when traversing directories."),
     );
 
+    #[cfg(feature = "http")]
+    let command = command.arg(
+        Arg::new("http_timeout")
+            .long("http-timeout")
+            .global(true)
+            .value_parser(value_parser!(std::num::NonZeroU64))
+            .help("Seconds to wait for a data URL, defaults to 30."),
+    );
+
+    #[cfg(feature = "http")]
+    let command = command.arg(
+        Arg::new("check_remote_links")
+            .long("check-remote-links")
+            .action(ArgAction::SetTrue)
+            .help("Check the `http` and `https` links of the written output too, implies `--check-links`."),
+    );
+
+    #[cfg(feature = "scripting")]
+    let command = command.arg(
+        Arg::new("helpers")
+            .long("helpers")
+            .global(true)
+            .value_parser(value_parser!(PathBuf))
+            .action(ArgAction::Append)
+            .help(concat!(
+                "Rhai script defining custom helpers. ",
+                "Every public function is registered as a helper. ",
+                "Can be used multiple times."
+            )),
+    );
+
     command
 }
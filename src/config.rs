@@ -1,487 +1,6092 @@
 use std::{
-    fs::File,
-    io::read_to_string,
-    os::unix::prelude::OsStrExt,
-    path::{Path, PathBuf},
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::{read_to_string, ErrorKind, Read, Seek, SeekFrom, Write},
+    num::{NonZeroU64, NonZeroUsize},
+    path::{Component, Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
 };
 
-use clap::ArgMatches;
-use handlebars::{Handlebars, TemplateError};
-use log::{info, warn, error};
+use clap::{parser::ValueSource, ArgMatches};
+use flate2::read::MultiGzDecoder;
+use handlebars::{
+    template::TemplateElement, Context, Handlebars, RenderContext, RenderError, RenderErrorReason,
+    Renderable, StringOutput, Template, TemplateError,
+};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use log::{error, info, warn};
 use serde::Deserialize;
 use walkdir::WalkDir;
 
+#[cfg(feature = "archive")]
+use crate::archive;
+#[cfg(feature = "http")]
+use crate::http;
+#[cfg(feature = "scripting")]
+use crate::scripting;
+use crate::{
+    data::{self, MergeStrategy},
+    dotenv,
+    front_matter::{self, FrontMatterError},
+    graph::GraphFormat,
+    helpers,
+    links::{self, Target},
+    logger::LogFormat,
+    references, secrets,
+};
+
+/// The context and message of an error reading a data file.
+type DataFileError = (&'static str, String);
+
+/// Name of the ignore file read from included directories.
+const IGNORE_FILE: &str = ".docfmtignore";
+/// Extensions of the sidecar data file of a template, the first one found is
+/// read.
+const SIDECAR_EXTENSIONS: [&str; 4] = ["json", "toml", "yaml", "yml"];
+/// Key of the built-in values in the data.
+const BUILTINS_KEY: &str = "docfmt";
+/// Key of the environment variables in the data.
+const ENV_KEY: &str = "env";
+/// Path standing for stdin.
+const STDIN: &str = "-";
+/// Environment variable with the path of the configuration file.
+const CONFIG_ENV: &str = "DOCFMT_CONFIG";
+/// Configuration file read from the current directory if none is given.
+const DEFAULT_CONFIG: &str = "docfmt.toml";
+/// Data files read from the directory of the configuration file, the first
+/// one found is merged below all other data.
+const DEFAULT_DATA: [&str; 2] = ["docfmt.data.toml", "docfmt.data.json"];
+/// Default name of the entry template.
+const DEFAULT_ENTRY: &str = "main";
+/// Default format of the built-in `date` value.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+/// Default extension of the files written to the output directory.
+const DEFAULT_OUTPUT_EXT: &str = "md";
+/// Seconds to wait for a data URL or a remote link, unless configured
+/// otherwise.
+#[cfg(feature = "http")]
+const DEFAULT_HTTP_TIMEOUT: u64 = 30;
+
 #[derive(Debug, Default, Deserialize)]
 #[serde(rename = "Config")]
 struct ConfigRead {
+    extends: Option<PathBuf>,
+    #[serde(default)]
+    relative_to: RelativeTo,
     template: Option<PathBuf>,
+    entry: Option<String>,
     output: Option<PathBuf>,
+    output_template: Option<String>,
+    each: Option<String>,
+    output_dir: Option<PathBuf>,
+    output_pattern: Option<String>,
+    output_ext: Option<String>,
+    #[serde(default)]
+    render: Vec<RenderTarget>,
+    #[serde(default)]
+    concat: Vec<String>,
+    separator: Option<String>,
+    #[serde(default)]
+    fail_fast: bool,
+    output_format: Option<OutputFormat>,
+    #[serde(default)]
+    pretty: bool,
+    line_ending: Option<LineEnding>,
+    #[serde(default)]
+    collapse_blank_lines: bool,
+    #[serde(default)]
+    safe: bool,
+    #[serde(default)]
+    allow_overwrite_source: bool,
+    #[serde(default)]
+    append: bool,
+    #[serde(default)]
+    report_unused: bool,
+    #[serde(default)]
+    report_usage: bool,
+    #[serde(default)]
+    check_links: bool,
+    #[serde(default)]
+    check_remote_links: bool,
     #[serde(default)]
     force: bool,
     #[serde(default)]
     follow: bool,
     #[serde(default)]
+    follow_links: BTreeMap<PathBuf, bool>,
+    #[serde(default)]
+    include_when: BTreeMap<PathBuf, String>,
+    #[serde(default)]
     strict: bool,
     #[serde(default)]
+    strict_data: bool,
+    #[serde(default)]
+    require_data: bool,
+    #[serde(default)]
+    sidecar_data: bool,
+    #[serde(default)]
+    strict_undefined_partials: bool,
+    #[serde(default)]
+    fail_on_warn: bool,
+    #[serde(default)]
     verbose: bool,
     #[serde(default)]
+    quiet: bool,
+    /// Number of verbose flags given on the command line.
+    #[serde(skip)]
+    verbosity: u8,
+    log_format: Option<LogFormat>,
+    #[serde(default)]
     include: Vec<PathBuf>,
     #[serde(default)]
     extensions: Vec<String>,
+    /// Whether the default extensions are skipped when no extension is given
+    /// on the command line.
+    #[serde(default)]
+    no_default_extensions: bool,
+    #[serde(default)]
+    partial_extensions: Vec<String>,
     #[serde(default)]
     datafiles: Vec<PathBuf>,
+    #[serde(default)]
+    secrets: Vec<PathBuf>,
+    #[serde(default)]
+    sensitive: Vec<String>,
+    data_format: Option<DataFormat>,
+    #[serde(default)]
+    data_formats: BTreeMap<String, DataFormat>,
+    profile: Option<String>,
+    #[serde(default)]
+    data_commands: Vec<String>,
+    #[serde(default)]
+    helpers: Vec<PathBuf>,
+    raw_dir: Option<PathBuf>,
+    root: Option<PathBuf>,
+    jobs: Option<NonZeroUsize>,
+    timeout: Option<NonZeroU64>,
+    http_timeout: Option<NonZeroU64>,
+    max_depth: Option<NonZeroUsize>,
+    max_templates: Option<NonZeroUsize>,
+    merge_arrays: Option<MergeStrategy>,
+    ignore_file: Option<PathBuf>,
+    report: Option<PathBuf>,
+    schema: Option<PathBuf>,
+    builtins: Option<bool>,
+    default_data: Option<bool>,
+    #[serde(default)]
+    env: bool,
+    #[serde(default)]
+    env_vars: Vec<String>,
+    html_escape: Option<bool>,
+    date_format: Option<String>,
+    locale: Option<String>,
+    fallback_locale: Option<String>,
     data: Option<toml::Value>,
+    #[serde(default)]
+    computed: serde_json::Map<String, serde_json::Value>,
+    #[serde(skip)]
+    mode: Mode,
+    /// Only print the names of the registered templates, a command line flag.
+    #[serde(skip)]
+    print_templates: bool,
+    /// Only print the data, a command line flag.
+    #[serde(skip)]
+    dump_data: bool,
+    /// The directory searched for the default data file, the one of the
+    /// configuration file or else the current directory.
+    #[serde(skip)]
+    default_data_dir: Option<PathBuf>,
+    /// The directory the rendered output template is relative to, the one of
+    /// the configuration file defining it.
+    #[serde(skip)]
+    output_template_dir: Option<PathBuf>,
 }
 
-#[derive(Debug, Default)]
-pub struct Config {
-    template: PathBuf,
-    output: PathBuf,
-    force: bool,
-    follow: bool,
-    strict: bool,
-    verbose: bool,
-    include: Vec<PathBuf>,
-    extensions: Vec<String>,
-    datafiles: Vec<PathBuf>,
-    data: serde_json::Value,
-}
+impl ConfigRead {
+    /// Read the configuration from a file. The format is determined by the
+    /// file extension, supported are `toml`, `json` and `json5` or `jsonc`.
+    /// If the file `extends` another configuration, that one is read first
+    /// and the file is laid over it.
+    fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        Self::from_file_extending(path, &mut Vec::new())
+    }
 
-impl Config {
-    #[inline]
-    pub fn log_level(&self) -> log::Level {
-        if self.verbose {
-            #[cfg(debug_assertions)]
-            return log::Level::Debug;
-            #[cfg(not(debug_assertions))]
-            return log::Level::Info;
+    /// Read the configuration from a file, `chain` holds the files already
+    /// being read to detect cycles.
+    fn from_file_extending(path: &Path, chain: &mut Vec<PathBuf>) -> Result<Self, ConfigError> {
+        let content = read_to_string(File::open(path).map_err(ConfigError::ConfigFileReadError)?)
+            .map_err(ConfigError::ConfigFileReadError)?;
+        let content = strip_bom(content, path);
+        let mut config: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content).map_err(ConfigError::InvalidConfig)?,
+            Some("json") => {
+                serde_json::from_str(&content).map_err(ConfigError::InvalidJsonConfig)?
+            }
+            Some("json5" | "jsonc") => {
+                json5::from_str(&content).map_err(ConfigError::InvalidJson5Config)?
+            }
+            _ => return Err(ConfigError::UnsupportedConfigFormat(path.to_owned())),
+        };
+        config.resolve_paths(path.parent().unwrap_or(Path::new("")));
+
+        let Some(extends) = config.extends.take() else {
+            return Ok(config);
+        };
+        let canonical = path
+            .canonicalize()
+            .map_err(ConfigError::ConfigFileReadError)?;
+        chain.push(canonical);
+
+        let parent = path.parent().unwrap_or(Path::new("")).join(extends);
+        let canonical = parent
+            .canonicalize()
+            .map_err(ConfigError::ConfigFileReadError)?;
+        if chain.contains(&canonical) {
+            return Err(ConfigError::ExtendsCycle(parent));
         }
-        log::Level::Warn
+        let parent = Self::from_file_extending(&parent, chain)?;
+
+        chain.pop();
+        Ok(config.extend(parent))
     }
 
-    #[allow(clippy::result_large_err)]
-    pub fn new_registry(&self) -> Option<Handlebars> {
-        let mut failed = false;
-        let mut registry = Handlebars::new();
+    /// Complete a configuration that is not read from a file. Its relative
+    /// paths and the file it `extends` are relative to the current directory.
+    #[cfg(test)]
+    fn from_str_extending(mut config: Self) -> Result<Self, ConfigError> {
+        match config.extends.take() {
+            Some(extends) => Ok(config.extend(Self::from_file(&extends)?)),
+            None => Ok(config),
+        }
+    }
 
-        if self.strict {
-            registry.set_strict_mode(true);
-            info!("Enabled strict mode");
+    /// Lay this configuration over `base`. Values defined here take
+    /// precedence, flags set in either are set, lists extend the lists of the
+    /// base and data is merged.
+    fn extend(self, base: ConfigRead) -> ConfigRead {
+        let (output, output_template, output_template_dir) =
+            if self.output.is_some() || self.output_template.is_some() {
+                (self.output, self.output_template, self.output_template_dir)
+            } else {
+                (base.output, base.output_template, base.output_template_dir)
+            };
+        let data = match (base.data, self.data) {
+            (Some(mut base), Some(data)) => {
+                Self::merge_data(&mut base, data);
+                Some(base)
+            }
+            (base, data) => data.or(base),
+        };
+
+        ConfigRead {
+            extends: None,
+            relative_to: self.relative_to,
+            template: self.template.or(base.template),
+            entry: self.entry.or(base.entry),
+            output,
+            output_template,
+            each: self.each.or(base.each),
+            output_dir: self.output_dir.or(base.output_dir),
+            output_pattern: self.output_pattern.or(base.output_pattern),
+            output_ext: self.output_ext.or(base.output_ext),
+            render: [base.render, self.render].concat(),
+            concat: [base.concat, self.concat].concat(),
+            separator: self.separator.or(base.separator),
+            fail_fast: self.fail_fast || base.fail_fast,
+            output_format: self.output_format.or(base.output_format),
+            pretty: self.pretty || base.pretty,
+            line_ending: self.line_ending.or(base.line_ending),
+            collapse_blank_lines: self.collapse_blank_lines || base.collapse_blank_lines,
+            safe: self.safe || base.safe,
+            allow_overwrite_source: self.allow_overwrite_source || base.allow_overwrite_source,
+            append: self.append || base.append,
+            report_unused: self.report_unused || base.report_unused,
+            report_usage: self.report_usage || base.report_usage,
+            check_links: self.check_links || base.check_links,
+            check_remote_links: self.check_remote_links || base.check_remote_links,
+            force: self.force || base.force,
+            follow: self.follow || base.follow,
+            follow_links: base
+                .follow_links
+                .into_iter()
+                .chain(self.follow_links)
+                .collect(),
+            include_when: base
+                .include_when
+                .into_iter()
+                .chain(self.include_when)
+                .collect(),
+            strict: self.strict || base.strict,
+            strict_data: self.strict_data || base.strict_data,
+            require_data: self.require_data || base.require_data,
+            sidecar_data: self.sidecar_data || base.sidecar_data,
+            strict_undefined_partials: self.strict_undefined_partials
+                || base.strict_undefined_partials,
+            fail_on_warn: self.fail_on_warn || base.fail_on_warn,
+            verbose: self.verbose || base.verbose,
+            quiet: self.quiet || base.quiet,
+            verbosity: self.verbosity.max(base.verbosity),
+            log_format: self.log_format.or(base.log_format),
+            include: [base.include, self.include].concat(),
+            extensions: [base.extensions, self.extensions].concat(),
+            no_default_extensions: self.no_default_extensions || base.no_default_extensions,
+            partial_extensions: [base.partial_extensions, self.partial_extensions].concat(),
+            datafiles: [base.datafiles, self.datafiles].concat(),
+            secrets: [base.secrets, self.secrets].concat(),
+            sensitive: [base.sensitive, self.sensitive].concat(),
+            data_format: self.data_format.or(base.data_format),
+            profile: self.profile.or(base.profile),
+            data_formats: base
+                .data_formats
+                .into_iter()
+                .chain(self.data_formats)
+                .collect(),
+            data_commands: [base.data_commands, self.data_commands].concat(),
+            helpers: [base.helpers, self.helpers].concat(),
+            raw_dir: self.raw_dir.or(base.raw_dir),
+            root: self.root.or(base.root),
+            jobs: self.jobs.or(base.jobs),
+            timeout: self.timeout.or(base.timeout),
+            http_timeout: self.http_timeout.or(base.http_timeout),
+            max_depth: self.max_depth.or(base.max_depth),
+            max_templates: self.max_templates.or(base.max_templates),
+            merge_arrays: self.merge_arrays.or(base.merge_arrays),
+            ignore_file: self.ignore_file.or(base.ignore_file),
+            report: self.report.or(base.report),
+            schema: self.schema.or(base.schema),
+            builtins: self.builtins.or(base.builtins),
+            default_data: self.default_data.or(base.default_data),
+            env: self.env || base.env,
+            env_vars: [base.env_vars, self.env_vars].concat(),
+            html_escape: self.html_escape.or(base.html_escape),
+            date_format: self.date_format.or(base.date_format),
+            locale: self.locale.or(base.locale),
+            fallback_locale: self.fallback_locale.or(base.fallback_locale),
+            data,
+            computed: {
+                let mut computed = base.computed;
+                computed.extend(self.computed);
+                computed
+            },
+            mode: self.mode,
+            print_templates: self.print_templates,
+            dump_data: self.dump_data,
+            default_data_dir: self.default_data_dir.or(base.default_data_dir),
+            output_template_dir,
         }
-        if self.follow {
-            info!("Enabled follow mode");
+    }
+
+    /// Resolve the relative paths of the file against `dir`, the directory of
+    /// the configuration file, unless `relative_to` is `cwd`. Include entries
+    /// keep their name prefix and data files their JSON pointer. Data commands
+    /// are run in the current directory and are kept, as is `-` for stdin or
+    /// stdout.
+    fn resolve_paths(&mut self, dir: &Path) {
+        if self.relative_to == RelativeTo::Cwd
+            || dir.as_os_str().is_empty()
+            || dir == Path::new(".")
+        {
+            return;
         }
-        if let Err(err) = registry.register_template_file("main", &self.template) {
-            error!("Unable to register main template: {:?}", self.template);
-            error!("{}", err);
-            failed = true;
+        for path in [
+            &mut self.template,
+            &mut self.output,
+            &mut self.output_dir,
+            &mut self.raw_dir,
+            &mut self.root,
+            &mut self.ignore_file,
+            &mut self.report,
+            &mut self.schema,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|path| *path != Path::new(STDIN))
+        {
+            *path = dir.join(&*path);
         }
-        info!("Registered main template: {:?}", self.template);
-        for path in &self.include {
-            let path = path.to_owned();
+        if self.output_template.is_some() {
+            self.output_template_dir = Some(dir.to_owned());
+        }
+        for target in &mut self.render {
+            if target.output != Path::new(STDIN) {
+                target.output = dir.join(&target.output);
+            }
+        }
+        for path in &mut self.helpers {
+            *path = dir.join(&*path);
+        }
+        for include in &mut self.include {
+            *include = Self::resolve_include(dir, include);
+        }
+        for datafile in self.datafiles.iter_mut().chain(&mut self.secrets) {
+            if datafile != Path::new(STDIN) {
+                *datafile = Self::resolve_datafile(dir, datafile);
+            }
+        }
+        self.follow_links = std::mem::take(&mut self.follow_links)
+            .into_iter()
+            .map(|(include, follow)| (Self::resolve_include(dir, &include), follow))
+            .collect();
+        self.include_when = std::mem::take(&mut self.include_when)
+            .into_iter()
+            .map(|(include, condition)| (Self::resolve_include(dir, &include), condition))
+            .collect();
+    }
 
-            if path.is_dir() {
-                info!("Walking directory: {:?}", path);
-                info!("Including files with extensions: {:?}", self.extensions);
-                let root = path.parent().unwrap_or(Path::new("")).to_owned();
-                for entry in WalkDir::new(path).follow_links(self.follow) {
-                    let entry = match entry {
-                        Ok(entry) => entry,
-                        Err(err) => {
-                            error!("Unable to read file: {:?}", err.path());
-                            error!("{}", err);
-                            failed = true;
-                            continue;
-                        }
-                    };
+    /// Resolve an include entry against `dir`, keeping its name prefix. An
+    /// entry that exists as given has no prefix, like in `split_prefix`.
+    fn resolve_include(dir: &Path, include: &Path) -> PathBuf {
+        let joined = dir.join(include);
+        if joined.exists() {
+            return joined;
+        }
+        include
+            .to_str()
+            .and_then(|include| include.split_once(':'))
+            .and_then(|(prefix, path)| {
+                let path = dir.join(path);
+                Some(PathBuf::from(format!("{}:{}", prefix, path.to_str()?)))
+            })
+            .unwrap_or(joined)
+    }
 
-                    if let Some(ext) = entry.path().extension() {
-                        let ext = match ext.to_str() {
-                            Some(ext) => ext.to_owned(),
-                            None => {
-                                error!("Unable to read extension of file: {:?}", entry.path());
-                                warn!("File extension is not valid UTF-8");
-                                failed = true;
-                                continue;
-                            }
-                        };
+    /// Resolve a data file against `dir`, keeping its JSON pointer. A data
+    /// file that exists as given has no pointer, like in `split_pointer`. URLs
+    /// are kept as given.
+    fn resolve_datafile(dir: &Path, datafile: &Path) -> PathBuf {
+        if Config::url(datafile).is_some() {
+            return datafile.to_owned();
+        }
+        let joined = dir.join(datafile);
+        if joined.exists() {
+            return joined;
+        }
+        datafile
+            .to_str()
+            .and_then(|datafile| datafile.rsplit_once('@'))
+            .filter(|(_, pointer)| pointer.starts_with('/'))
+            .and_then(|(path, pointer)| {
+                let path = dir.join(path);
+                Some(PathBuf::from(format!("{}@{}", path.to_str()?, pointer)))
+            })
+            .unwrap_or(joined)
+    }
 
-                        if !self.extensions.contains(&ext) {
-                            continue;
+    /// Merge the tables of `b` into `a`, other values of `b` replace those of
+    /// `a`.
+    fn merge_data(a: &mut toml::Value, b: toml::Value) {
+        match (a, b) {
+            (toml::Value::Table(a), toml::Value::Table(b)) => {
+                for (key, value) in b {
+                    match a.get_mut(&key) {
+                        Some(entry) => Self::merge_data(entry, value),
+                        None => {
+                            a.insert(key, value);
                         }
-                    } else {
-                        continue;
                     }
+                }
+            }
+            (a, b) => *a = b,
+        }
+    }
+}
 
-                    let meta = match entry.metadata() {
-                        Ok(meta) => meta,
-                        Err(err) => {
-                            error!("Unable to read metadata of file: {:?}", err.path());
-                            error!("{}", err);
-                            failed = true;
-                            continue;
-                        }
-                    };
-                    if meta.is_file() {
-                        info!("Reading file: {:?}", entry.path());
-                        let name = entry.path();
+/// The format of a data file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    Json,
+    Json5,
+    Toml,
+    Yaml,
+    Ini,
+    Env,
+}
 
-                        if let Some(stem) = name.file_stem() {
-                            if stem.as_bytes().first() == Some(&b'.') {
-                                continue;
-                            }
-                        } else {
-                            error!("Unable to register file: {:?}", entry.path());
-                            warn!("File name is not valid UTF-8");
-                            failed = true;
-                            continue;
-                        }
+impl DataFormat {
+    /// The format of a data file with the given extension.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "json" => Some(DataFormat::Json),
+            "json5" | "jsonc" => Some(DataFormat::Json5),
+            "toml" => Some(DataFormat::Toml),
+            "yaml" | "yml" => Some(DataFormat::Yaml),
+            "ini" => Some(DataFormat::Ini),
+            "env" => Some(DataFormat::Env),
+            _ => None,
+        }
+    }
 
-                        let name = name.strip_prefix(&root).unwrap();
-                        let name = name.with_extension("");
-                        #[cfg(windows)]
-                        let name = name.to_str().unwrap().replace('\\', "/");
-                        #[cfg(unix)]
-                        let name = match name.to_str() {
-                            Some(name) => name,
-                            None => {
-                                error!("Unable to register file: {:?}", entry.path());
-                                warn!("File path is not valid UTF-8");
-                                failed = true;
-                                continue;
-                            }
-                        };
-                        if let Err(err) = registry.register_template_file(name.as_ref(), entry.path()) {
-                            error!("Unable to register file: {:?}", entry.path());
-                            error!("{}", err);
-                            failed = true;
-                            continue;
-                        }
-                        info!("Registered template: {:?}", name);
-                    }
-                }
-            } else if path.is_file() {
-                info!("Reading file: {:?}", &path);
-                let name = path.with_extension("");
-                let name = name.file_name().unwrap();
-                #[cfg(windows)]
-                let name = name.to_str().unwrap().replace('\\', "/");
-                #[cfg(unix)]
-                let name = name.to_str().unwrap();
-                if let Err(err) =  registry.register_template_file(name.as_ref(), &path) {
-                    error!("Unable to register file: {:?}", path);
-                    error!("{}", err);
-                    failed = true;
-                    continue;
-                }
-                info!("Registered template: {:?}", name);
+    /// The format of a response with the given media type, e.g.
+    /// `application/json` or `application/ld+json`.
+    pub fn from_mime_type(mime_type: &str) -> Option<Self> {
+        match mime_type {
+            "application/json" => Some(DataFormat::Json),
+            "application/json5" => Some(DataFormat::Json5),
+            "application/toml" | "text/x-toml" => Some(DataFormat::Toml),
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(DataFormat::Yaml)
             }
+            mime_type if mime_type.ends_with("+json") => Some(DataFormat::Json),
+            mime_type if mime_type.ends_with("+yaml") => Some(DataFormat::Yaml),
+            _ => None,
         }
-        if failed {
-            return None;
-        }
-        Some(registry)
     }
 
-    #[allow(clippy::result_large_err)]
-    pub fn read_data(&self) -> Option<serde_json::Value> {
-        let mut failed = false;
-        let mut data = self.data.clone();
+    /// Parse the content of a data file.
+    pub fn parse(self, content: &str) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        Ok(match self {
+            DataFormat::Json => serde_json::from_str(content)?,
+            DataFormat::Json5 => json5::from_str(content)?,
+            DataFormat::Toml => data::from_toml(toml::from_str(content)?),
+            DataFormat::Yaml => serde_yaml::from_str(content)?,
+            DataFormat::Ini => Self::parse_ini(content)?,
+            DataFormat::Env => dotenv::parse(content)?,
+        })
+    }
 
-        macro_rules! log_error {
-            ($path:expr, $err:expr) => {{
-                error!("Unable to read data file: {:?}", $path);
-                error!("{}", $err);
-                failed = true;
-                continue;
-            }};
-        }
+    /// Parse INI content into an object of strings. Keys outside of a section
+    /// are top-level keys, each section is an object of its keys.
+    fn parse_ini(content: &str) -> Result<serde_json::Value, ini::ParseError> {
+        let ini = ini::Ini::load_from_str(content)?;
+        let mut map = serde_json::Map::new();
 
-        for path in &self.datafiles {
-            info!("Reading data file: {:?}", path);
-            let file = match File::open(path) {
-                Ok(file) => file,
-                Err(err) => {
-                    error!("Unable to open data file: {:?}", path);
-                    error!("{}", err);
-                    failed = true;
-                    continue;
+        for (section, properties) in ini.iter() {
+            let target = match section {
+                Some(section) => {
+                    let entry = map
+                        .entry(section)
+                        .or_insert_with(|| serde_json::Value::Object(Default::default()));
+                    if !entry.is_object() {
+                        *entry = serde_json::Value::Object(Default::default());
+                    }
+                    entry.as_object_mut().unwrap()
                 }
+                None => &mut map,
             };
-            let content = match read_to_string(file) {
-                Ok(content) => content,
-                Err(err) => log_error!(path, err),
-            };
-            let value = if path.extension() == Some("json".as_ref()) {
-                match serde_json::from_str(&content) {
-                    Ok(value) => value,
-                    Err(err) => log_error!(path, err),
-                }
-            } else if path.extension() == Some("toml".as_ref()) {
-                let value = match toml::from_str::<toml::Value>(&content) {
-                    Ok(value) => value,
-                    Err(err) => log_error!(path, err),
-                };
+            for (key, value) in properties.iter() {
+                target.insert(key.to_owned(), value.into());
+            }
+        }
+        Ok(serde_json::Value::Object(map))
+    }
+}
 
-                match serde_json::to_value(value) {
-                    Ok(value) => value,
-                    Err(err) => log_error!(path, err),
-                }
-            } else {
-                error!("Unable to read data file: {:?}", path);
-                error!("Unsupported file extension");
-                failed = true;
-                continue;
-            };
+/// The directory the relative paths of a configuration file are resolved
+/// against.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RelativeTo {
+    /// The directory of the configuration file.
+    #[default]
+    Config,
+    /// The current working directory.
+    Cwd,
+}
+
+/// The format the rendered output is validated against.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Any text, not validated.
+    #[default]
+    Text,
+    Json,
+    Toml,
+}
 
-            Self::merge(&mut data, value);
+impl OutputFormat {
+    /// The output format of the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(OutputFormat::Text),
+            "json" => Some(OutputFormat::Json),
+            "toml" => Some(OutputFormat::Toml),
+            _ => None,
         }
+    }
 
-        if failed {
-            return None;
+    /// Check that the content is valid in this format.
+    pub fn validate(self, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            OutputFormat::Text => {}
+            OutputFormat::Json => {
+                serde_json::from_str::<serde_json::Value>(content)?;
+            }
+            OutputFormat::Toml => {
+                toml::from_str::<toml::Value>(content)?;
+            }
         }
-        Some(data)
+        Ok(())
     }
 
-    #[allow(clippy::result_large_err)]
-    pub fn write_output(&self, content: String) -> bool {
-        info!("Writing output file: {:?}", self.output);
-        if self.output.exists() && !self.force {
-            error!("Output file already exists: {:?}", self.output);
-            return false;
+    /// Pretty-print the content in this format. Text is returned as is.
+    pub fn pretty(self, content: String) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(match self {
+            OutputFormat::Text => content,
+            OutputFormat::Json => {
+                let value = serde_json::from_str::<serde_json::Value>(&content)?;
+                format!("{}\n", serde_json::to_string_pretty(&value)?)
+            }
+            OutputFormat::Toml => {
+                let value = toml::from_str::<toml::Table>(&content)?;
+                toml::to_string_pretty(&value)?
+            }
+        })
+    }
+}
+
+/// The line ending the output is normalized to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    /// The line ending of the platform, `crlf` on Windows and `lf` elsewhere.
+    Native,
+}
+
+impl LineEnding {
+    /// The line ending of the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lf" => Some(LineEnding::Lf),
+            "crlf" => Some(LineEnding::Crlf),
+            "native" => Some(LineEnding::Native),
+            _ => None,
         }
+    }
 
-        if let Err(err) = std::fs::write(&self.output, content) {
-            error!("Unable to write output file: {:?}", self.output);
-            error!("{}", err);
-            return false;
+    /// Convert all line breaks of the content, `\r\n` and `\n`, to this
+    /// line ending.
+    pub fn normalize(self, content: &str) -> String {
+        let content = content.replace("\r\n", "\n");
+
+        match self {
+            LineEnding::Crlf => content.replace('\n', "\r\n"),
+            LineEnding::Native if cfg!(windows) => content.replace('\n', "\r\n"),
+            LineEnding::Lf | LineEnding::Native => content,
         }
-        true
     }
+}
 
-    fn merge(a: &mut serde_json::Value, b: serde_json::Value) {
-        // CREDITS: https://stackoverflow.com/a/54118457
-        if let serde_json::Value::Object(a) = a {
-            if let serde_json::Value::Object(b) = b {
-                for (k, v) in b {
-                    if v.is_null() {
-                        a.remove(&k);
-                    } else {
-                        Self::merge(a.entry(k).or_insert(serde_json::Value::Null), v);
-                    }
-                }
-                return;
+/// Collapse runs of blank lines into a single blank line. A line containing
+/// only whitespace counts as blank. Line endings are kept.
+fn collapse_blank_lines(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut blank = false;
+
+    for line in content.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            if blank {
+                continue;
             }
+            blank = true;
+        } else {
+            blank = false;
         }
+        result.push_str(line);
+    }
+    result
+}
 
-        *a = b;
+/// Remove a leading UTF-8 byte order mark, which editors on Windows like to
+/// add and which the parsers reject.
+fn strip_bom(content: String, path: &Path) -> String {
+    match content.strip_prefix('\u{feff}') {
+        Some(stripped) => {
+            info!("Removed byte order mark: {:?}", path);
+            stripped.to_owned()
+        }
+        None => content,
     }
 }
 
-impl TryFrom<ArgMatches> for Config {
-    type Error = ConfigError;
+/// A template rendered to an output file, defined in the `render` array of
+/// the configuration.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct RenderTarget {
+    /// Name of the template to render.
+    pub entry: String,
+    /// Path to the output file.
+    pub output: PathBuf,
+}
 
-    fn try_from(matches: ArgMatches) -> Result<Self, Self::Error> {
-        let mut config = match matches.get_one::<PathBuf>("config") {
-            Some(path) => {
-                let content =
-                    read_to_string(File::open(path).map_err(ConfigError::ConfigFileReadError)?)
-                        .map_err(ConfigError::ConfigFileReadError)?;
-                toml::from_str::<ConfigRead>(&content).map_err(ConfigError::InvalidConfig)?
-            }
-            None => ConfigRead::default(),
-        };
+/// A command whose standard output is read as data, given as
+/// `<command>:<format>`, e.g. `./gen-data.sh:json`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataCommand {
+    /// The command line, run by the shell.
+    pub command: String,
+    /// The format of the output.
+    pub format: DataFormat,
+}
 
-        config.template = matches
-            .get_one::<PathBuf>("template")
-            .cloned()
-            .or(config.template);
-        config.output = matches
-            .get_one::<PathBuf>("output")
-            .cloned()
-            .or(config.output);
-        config.force = if matches.get_flag("force") {
-            true
-        } else {
-            config.force
+impl DataCommand {
+    /// Parse a command with its format suffix. Returns `None` if the suffix
+    /// is missing or not a supported format.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (command, format) = value.rsplit_once(':')?;
+        let command = command.trim();
+
+        if command.is_empty() {
+            return None;
+        }
+        Some(DataCommand {
+            command: command.to_owned(),
+            format: DataFormat::from_extension(format.trim())?,
+        })
+    }
+
+    /// Run the command and parse its standard output. A command that can not
+    /// be spawned or exits with a non-zero status is an error, its standard
+    /// error is included in the message.
+    pub fn run(&self) -> Result<serde_json::Value, DataFileError> {
+        #[cfg(windows)]
+        let mut command = {
+            let mut command = Command::new("cmd");
+            command.arg("/C").arg(&self.command);
+            command
         };
-        #[cfg(unix)]
-        {
-            config.follow = if matches.get_flag("follow") {
-                true
-            } else {
-                config.follow
-            };
+        #[cfg(not(windows))]
+        let mut command = {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(&self.command);
+            command
+        };
+        let output = command
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|err| ("Unable to run data command", err.to_string()))?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        if !output.status.success() {
+            return Err((
+                "Data command failed",
+                format!("{}\n{}", output.status, stderr.trim_end()),
+            ));
         }
-        #[cfg(not(unix))]
+        if !stderr.trim().is_empty() {
+            info!("Data command wrote to stderr: {}", stderr.trim_end());
+        }
+        let stdout = String::from_utf8(output.stdout)
+            .map_err(|err| ("Unable to read output of data command", err.to_string()))?;
+        self.format
+            .parse(&stdout)
+            .map_err(|err| ("Unable to read output of data command", err.to_string()))
+    }
+}
+
+/// A condition on the data, given as `<path>`, `<path> == <value>` or
+/// `<path> != <value>`. The path is a JSON pointer like `/site/audience` or
+/// a dotted path like `site.audience`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Condition {
+    /// JSON pointer to the tested value.
+    pub pointer: String,
+    /// The comparison, `true` for equality, and the compared value. Without
+    /// a comparison the value is tested for truthiness.
+    pub comparison: Option<(bool, String)>,
+}
+
+impl Condition {
+    /// Parse a condition. Returns `None` if the path is empty.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (path, comparison) = match value.split_once("==") {
+            Some((path, expected)) => (path, Some((true, expected))),
+            None => match value.split_once("!=") {
+                Some((path, expected)) => (path, Some((false, expected))),
+                None => (value, None),
+            },
+        };
+        let path = path.trim();
+
+        if path.is_empty() || path == "/" {
+            return None;
+        }
+        let pointer = match path.strip_prefix('/') {
+            Some(_) => path.to_owned(),
+            None => format!("/{}", path.replace('.', "/")),
+        };
+        let comparison = comparison.map(|(equal, expected)| {
+            let expected = expected.trim();
+            let expected = ['"', '\'']
+                .iter()
+                .find_map(|quote| {
+                    expected
+                        .strip_prefix(*quote)
+                        .and_then(|rest| rest.strip_suffix(*quote))
+                })
+                .unwrap_or(expected);
+            (equal, expected.to_owned())
+        });
+        Some(Condition {
+            pointer,
+            comparison,
+        })
+    }
+
+    /// Evaluate the condition against the data. A missing value is not
+    /// truthy and not equal to any value. `null`, `false`, `0`, empty strings
+    /// and empty arrays are not truthy. Values other than strings are
+    /// compared with the expected value parsed as JSON, e.g. `true` or `2`.
+    pub fn matches(&self, data: &serde_json::Value) -> bool {
+        let value = data.pointer(&self.pointer);
+
+        match &self.comparison {
+            Some((equal, expected)) => {
+                let is_equal = match value {
+                    Some(serde_json::Value::String(value)) => value == expected,
+                    Some(value) => serde_json::from_str::<serde_json::Value>(expected)
+                        .is_ok_and(|expected| expected == *value),
+                    None => false,
+                };
+                is_equal == *equal
+            }
+            None => match value {
+                None | Some(serde_json::Value::Null) | Some(serde_json::Value::Bool(false)) => {
+                    false
+                }
+                Some(serde_json::Value::Number(number)) => number.as_f64() != Some(0.0),
+                Some(serde_json::Value::String(value)) => !value.is_empty(),
+                Some(serde_json::Value::Array(items)) => !items.is_empty(),
+                _ => true,
+            },
+        }
+    }
+}
+
+/// What the registry does not tell about the registered templates and
+/// helpers.
+#[derive(Debug, Default)]
+pub struct Sources {
+    /// Paths of the files the templates were read from by their names.
+    pub files: HashMap<String, PathBuf>,
+    /// Names of the registered helpers.
+    pub helpers: BTreeSet<String>,
+}
+
+/// Summary of a failed registration of the templates.
+#[derive(Debug, Default)]
+pub struct RegistryReport {
+    /// Number of templates registered successfully.
+    pub registered: usize,
+    /// Paths of the files that failed to register.
+    pub failed: Vec<PathBuf>,
+}
+
+impl RegistryReport {
+    /// Log the number of failed files and their paths.
+    pub fn log(&self) {
+        if self.failed.is_empty() {
+            return;
+        }
+        error!(
+            "{} of {} templates failed to register:",
+            self.failed.len(),
+            self.registered + self.failed.len()
+        );
+        for path in &self.failed {
+            error!("  {:?}", path);
+        }
+    }
+}
+
+/// The state of a computed value while ordering them by their dependencies.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    New,
+    Active,
+    Done,
+}
+
+/// The action to perform with the configuration.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Render the main template into the output file.
+    #[default]
+    Render,
+    /// Only register all templates to check that they parse. No output file
+    /// is required.
+    Validate,
+    /// Register all templates and report the unreachable templates and the
+    /// missing partials. No output file is required.
+    Lint,
+    /// Print the graph of the templates included by the entry templates in
+    /// the given format. No output file is required.
+    Graph(GraphFormat),
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    template: Option<PathBuf>,
+    entry: Option<String>,
+    output: PathBuf,
+    output_template: Option<String>,
+    output_template_dir: Option<PathBuf>,
+    each: Option<String>,
+    output_dir: Option<PathBuf>,
+    output_pattern: Option<glob::Pattern>,
+    output_ext: String,
+    render: Vec<RenderTarget>,
+    concat: Vec<String>,
+    separator: String,
+    fail_fast: bool,
+    output_format: OutputFormat,
+    pretty: bool,
+    line_ending: Option<LineEnding>,
+    collapse_blank_lines: bool,
+    safe: bool,
+    allow_overwrite_source: bool,
+    append: bool,
+    report_unused: bool,
+    report_usage: bool,
+    check_links: bool,
+    check_remote_links: bool,
+    force: bool,
+    follow: bool,
+    follow_links: BTreeMap<PathBuf, bool>,
+    include_when: BTreeMap<PathBuf, Condition>,
+    strict: bool,
+    strict_data: bool,
+    require_data: bool,
+    sidecar_data: bool,
+    strict_undefined_partials: bool,
+    fail_on_warn: bool,
+    verbose: u8,
+    quiet: bool,
+    log_format: LogFormat,
+    include: Vec<PathBuf>,
+    extensions: Vec<String>,
+    partial_extensions: Vec<String>,
+    datafiles: Vec<PathBuf>,
+    secrets: Vec<PathBuf>,
+    sensitive: Vec<String>,
+    data_format: Option<DataFormat>,
+    data_formats: BTreeMap<String, DataFormat>,
+    profile: Option<String>,
+    data_commands: Vec<DataCommand>,
+    helpers: Vec<PathBuf>,
+    raw_dir: PathBuf,
+    root: Option<PathBuf>,
+    jobs: Option<NonZeroUsize>,
+    timeout: Option<NonZeroU64>,
+    #[cfg(feature = "http")]
+    http_timeout: Option<NonZeroU64>,
+    max_depth: Option<NonZeroUsize>,
+    max_templates: Option<NonZeroUsize>,
+    merge_arrays: MergeStrategy,
+    ignore_file: Option<PathBuf>,
+    report: Option<PathBuf>,
+    schema: Option<PathBuf>,
+    builtins: bool,
+    default_data: Option<PathBuf>,
+    env: bool,
+    env_vars: Vec<String>,
+    html_escape: bool,
+    date_format: String,
+    locale: Option<String>,
+    fallback_locale: Option<String>,
+    data: serde_json::Value,
+    computed: Vec<(String, String)>,
+    mode: Mode,
+    print_templates: bool,
+    dump_data: bool,
+}
+
+impl Config {
+    /// Build a configuration from the content of a TOML configuration file,
+    /// as if the file was in the current directory. Used by the tests, the
+    /// binary reads its configuration from `ArgMatches`.
+    #[cfg(test)]
+    pub fn from_toml_str(content: &str) -> Result<Self, ConfigError> {
+        let config = toml::from_str(content).map_err(ConfigError::InvalidConfig)?;
+        Self::try_from(ConfigRead::from_str_extending(config)?)
+    }
+
+    /// Build a configuration from the content of a JSON configuration file,
+    /// see [`Config::from_toml_str`].
+    #[cfg(test)]
+    pub fn from_json_str(content: &str) -> Result<Self, ConfigError> {
+        let config = serde_json::from_str(content).map_err(ConfigError::InvalidJsonConfig)?;
+        Self::try_from(ConfigRead::from_str_extending(config)?)
+    }
+
+    #[inline]
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// The name of the template to render, defaults to `main`.
+    #[inline]
+    pub fn entry(&self) -> &str {
+        self.entry.as_deref().unwrap_or(DEFAULT_ENTRY)
+    }
+
+    #[inline]
+    pub fn output(&self) -> &Path {
+        &self.output
+    }
+
+    /// The default data file, the data files and secrets files with glob
+    /// patterns expanded. Patterns that are invalid or match nothing are
+    /// skipped.
+    pub fn datafiles(&self) -> Vec<PathBuf> {
+        self.default_data
+            .iter()
+            .chain(&self.datafiles)
+            .chain(&self.secrets)
+            .map(|path| Self::split_pointer(path).0)
+            .flat_map(|path| match Self::expand_glob(path) {
+                Some(Ok(paths)) => self.with_overlays(paths),
+                Some(Err(_)) => vec![],
+                None => self.with_overlays(vec![path.to_owned()]),
+            })
+            .collect()
+    }
+
+    /// Add the overlay of the profile after each data file that has one.
+    /// Overlays among `paths` are only merged after their base file.
+    fn with_overlays(&self, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        let Some(profile) = &self.profile else {
+            return paths;
+        };
+        let overlays = paths
+            .iter()
+            .filter_map(|path| Self::overlay_path(path, profile))
+            .collect::<Vec<_>>();
+
+        paths
+            .iter()
+            .filter(|path| !overlays.contains(path))
+            .flat_map(|path| {
+                let overlay = Self::overlay_path(path, profile).filter(|path| path.is_file());
+                std::iter::once(path.to_owned()).chain(overlay)
+            })
+            .collect()
+    }
+
+    /// The overlay of a data file for a profile, the profile inserted before
+    /// the extension, e.g. `data.prod.toml` for `data.toml` or
+    /// `data.prod.json.gz` for `data.json.gz`. Files without an extension,
+    /// like `.env`, have no overlay.
+    fn overlay_path(path: &Path, profile: &str) -> Option<PathBuf> {
+        let name = path.file_name()?.to_str()?;
+        let extension = |name: &str| name.rfind('.').filter(|index| *index > 0);
+        let mut index = extension(name)?;
+
+        if &name[index..] == ".gz" {
+            index = extension(&name[..index])?;
+        }
+        let (stem, extension) = name.split_at(index);
+        Some(path.with_file_name(format!("{}.{}{}", stem, profile, extension)))
+    }
+
+    /// The number of threads reading data files, defaults to the number of
+    /// logical CPUs.
+    pub fn jobs(&self) -> usize {
+        self.jobs
+            .or_else(|| std::thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get)
+    }
+
+    /// The time rendering may take before the process is terminated.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+            .map(|timeout| Duration::from_secs(timeout.get()))
+    }
+
+    #[inline]
+    pub fn strict_data(&self) -> bool {
+        self.strict_data
+    }
+
+    #[inline]
+    pub fn fail_on_warn(&self) -> bool {
+        self.fail_on_warn
+    }
+
+    #[inline]
+    pub fn report_unused(&self) -> bool {
+        self.report_unused
+    }
+
+    #[inline]
+    pub fn report_usage(&self) -> bool {
+        self.report_usage
+    }
+
+    /// Whether the links of the outputs are checked, which checking remote
+    /// links implies.
+    #[inline]
+    pub fn check_links(&self) -> bool {
+        self.check_links || self.check_remote_links
+    }
+
+    #[inline]
+    pub fn each(&self) -> Option<&str> {
+        self.each.as_deref()
+    }
+
+    #[inline]
+    pub fn render(&self) -> &[RenderTarget] {
+        &self.render
+    }
+
+    #[inline]
+    pub fn concat(&self) -> &[String] {
+        &self.concat
+    }
+
+    /// Join the outputs of the concatenated entries with the separator.
+    /// Outputs that are empty or only hold whitespace are skipped. A line
+    /// break is added to an output followed by the separator and to a
+    /// separator that does not end with one, so the separator stands on its
+    /// own lines. Nothing follows the last output.
+    pub fn join_outputs(&self, outputs: Vec<String>) -> String {
+        let mut content = String::new();
+
+        for output in outputs
+            .into_iter()
+            .filter(|output| !output.trim().is_empty())
         {
-            config.follow = false;
+            if !content.is_empty() {
+                if !content.ends_with('\n') {
+                    content.push('\n');
+                }
+                if !self.separator.is_empty() {
+                    content.push_str(&self.separator);
+                    if !self.separator.ends_with('\n') {
+                        content.push('\n');
+                    }
+                }
+            }
+            content.push_str(&output);
         }
-        config.strict = if matches.get_flag("strict") {
-            true
+        content
+    }
+
+    #[inline]
+    pub fn fail_fast(&self) -> bool {
+        self.fail_fast
+    }
+
+    #[inline]
+    pub fn print_templates(&self) -> bool {
+        self.print_templates
+    }
+
+    #[inline]
+    pub fn dump_data(&self) -> bool {
+        self.dump_data
+    }
+
+    #[inline]
+    pub fn output_dir(&self) -> Option<&Path> {
+        self.output_dir.as_deref()
+    }
+
+    /// The render targets of the output directory: each registered template
+    /// whose name matches the output pattern, written to
+    /// `<output-dir>/<name>.<ext>`, in the order of the names.
+    pub fn output_dir_targets(&self, registry: &Handlebars) -> Vec<RenderTarget> {
+        let Some(dir) = &self.output_dir else {
+            return Vec::new();
+        };
+        let mut names = registry
+            .get_templates()
+            .keys()
+            .filter(|name| {
+                self.output_pattern
+                    .as_ref()
+                    .is_none_or(|pattern| pattern.matches(name))
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+
+        names
+            .into_iter()
+            .map(|name| RenderTarget {
+                entry: name.to_owned(),
+                output: dir.join(format!("{}.{}", name, self.output_ext)),
+            })
+            .collect()
+    }
+
+    /// The names of the templates to render: those of the render targets or
+    /// the concatenated templates if any are defined, otherwise the entry. With an output directory the
+    /// templates are only known after registration, and when printing the
+    /// template names nothing is rendered, so none are returned.
+    pub fn entries(&self) -> Vec<&str> {
+        if self.output_dir.is_some() || self.print_templates || self.dump_data {
+            Vec::new()
+        } else if !self.concat.is_empty() {
+            self.concat.iter().map(String::as_str).collect()
+        } else if self.render.is_empty() {
+            vec![self.entry()]
         } else {
-            config.strict
+            self.render
+                .iter()
+                .map(|target| target.entry.as_str())
+                .collect()
+        }
+    }
+
+    /// The path of the JSON report, if one is written.
+    #[inline]
+    pub fn report(&self) -> Option<&Path> {
+        self.report.as_deref()
+    }
+
+    #[inline]
+    pub fn log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    /// The log level derived from the `quiet` and `verbose` options. Quiet
+    /// only logs errors, a single verbose flag logs debug messages in debug
+    /// builds and info messages in release builds, a repeated verbose flag
+    /// logs everything. Defaults to warnings.
+    #[inline]
+    pub fn log_level(&self) -> log::Level {
+        if self.quiet {
+            return log::Level::Error;
+        }
+        match self.verbose {
+            0 => log::Level::Warn,
+            1 => {
+                #[cfg(debug_assertions)]
+                return log::Level::Debug;
+                #[cfg(not(debug_assertions))]
+                return log::Level::Info;
+            }
+            _ => log::Level::Trace,
+        }
+    }
+
+    /// Create the registry with the helpers, the main template and all
+    /// included templates. All files are registered even if some fail, the
+    /// failures are returned as a report. The conditions of the includes are
+    /// not evaluated, every include is registered.
+    #[allow(clippy::result_large_err)]
+    pub fn new_registry(&self) -> Result<Handlebars<'static>, RegistryReport> {
+        self.build_registry(None).map(|(registry, _)| registry)
+    }
+
+    /// Create the registry like `new_registry`, or like `new_registry_for` if
+    /// there is data, and return the paths of the files the templates were
+    /// read from and the names of the registered helpers.
+    #[allow(clippy::result_large_err)]
+    pub fn new_registry_with_sources(
+        &self,
+        data: Option<&serde_json::Value>,
+    ) -> Result<(Handlebars<'static>, Sources), RegistryReport> {
+        self.build_registry(data)
+    }
+
+    /// Create the registry like `new_registry`, but skip the includes whose
+    /// condition does not match the data.
+    #[allow(clippy::result_large_err)]
+    pub fn new_registry_for(
+        &self,
+        data: &serde_json::Value,
+    ) -> Result<Handlebars<'static>, RegistryReport> {
+        self.build_registry(Some(data))
+            .map(|(registry, _)| registry)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn build_registry(
+        &self,
+        data: Option<&serde_json::Value>,
+    ) -> Result<(Handlebars<'static>, Sources), RegistryReport> {
+        let mut failed = Vec::new();
+        let mut registry = Handlebars::new();
+        let mut sources = HashMap::new();
+        let mut helpers = helpers::register(&mut registry)
+            .into_iter()
+            .chain(["include_raw", "file_sha256", "t"])
+            .map(str::to_owned)
+            .collect::<BTreeSet<_>>();
+
+        registry.register_helper(
+            "include_raw",
+            Box::new(helpers::IncludeRaw::new(&self.raw_dir)),
+        );
+        registry.register_helper(
+            "file_sha256",
+            Box::new(helpers::FileSha256::new(&self.raw_dir)),
+        );
+        registry.register_helper(
+            "t",
+            Box::new(helpers::Translate::new(
+                self.locale.as_deref(),
+                self.fallback_locale.as_deref(),
+            )),
+        );
+        if !self.html_escape {
+            registry.register_escape_fn(handlebars::no_escape);
+            info!("Disabled HTML escaping");
+        }
+        if self.strict {
+            registry.set_strict_mode(true);
+            info!("Enabled strict mode");
+        }
+        if self.follow {
+            info!("Enabled follow mode");
+        }
+        for path in &self.helpers {
+            #[cfg(feature = "scripting")]
+            match scripting::register_file(&mut registry, path) {
+                Ok(names) => {
+                    info!("Registered script helpers from {:?}: {:?}", path, names);
+                    helpers.extend(names);
+                }
+                Err(err) => {
+                    error!("Unable to register script helpers: {:?}", path);
+                    error!("{}", err);
+                    failed.push(path.to_owned());
+                }
+            }
+            #[cfg(not(feature = "scripting"))]
+            {
+                error!("Unable to register script helpers: {:?}", path);
+                error!("Scripting support is not enabled, rebuild with feature `scripting`");
+                failed.push(path.to_owned());
+            }
+        }
+        if let Some(template) = &self.template {
+            match Self::read_file(template) {
+                Ok(source) => match front_matter::split(&source) {
+                    Ok((_, body)) => {
+                        if let Err(err) = registry.register_template_string(self.entry(), body) {
+                            error!("Unable to register main template: {:?}", template);
+                            error!("{}", err);
+                            failed.push(template.to_owned());
+                        }
+                    }
+                    Err(err) => {
+                        error!("Unable to register main template: {:?}", template);
+                        error!("{}", err);
+                        failed.push(template.to_owned());
+                    }
+                },
+                Err(err) => {
+                    error!("Unable to register main template: {:?}", template);
+                    error!("{}", err);
+                    failed.push(template.to_owned());
+                }
+            }
+            info!("Registered main template: {:?}", template);
+            sources.insert(self.entry().to_owned(), template.to_owned());
+        }
+        let ignore_file = match &self.ignore_file {
+            Some(path) => match Self::read_ignore_file(path) {
+                Ok(ignore) => Some(ignore),
+                Err(err) => {
+                    error!("Unable to read ignore file: {:?}", path);
+                    error!("{}", err);
+                    failed.push(path.to_owned());
+                    None
+                }
+            },
+            None => None,
+        };
+        'includes: for include in &self.include {
+            let condition = Self::include_setting(&self.include_when, include);
+            if let (Some(condition), Some(data)) = (condition, data) {
+                if !condition.matches(data) {
+                    info!("Skipping include, condition not met: {:?}", include);
+                    continue;
+                }
+            }
+            let (prefix, path) = Self::split_prefix(include);
+            let path = path.to_owned();
+
+            if Self::is_archive(&path) && path.is_file() {
+                if !self.register_archive(&mut registry, &mut sources, prefix, &path, &mut failed) {
+                    break;
+                }
+            } else if path.is_dir() {
+                info!("Walking directory: {:?}", path);
+                info!("Including files with extensions: {:?}", self.extensions);
+                if !self.partial_extensions.is_empty() {
+                    info!(
+                        "Including partials with extensions: {:?}",
+                        self.partial_extensions
+                    );
+                }
+                let root = match (prefix, &self.root) {
+                    (Some(_), _) => path.to_owned(),
+                    (None, Some(root)) => root.to_owned(),
+                    (None, None) => path.parent().unwrap_or(Path::new("")).to_owned(),
+                };
+                let root = Self::normalize(&root);
+                if !Self::normalize(&path).starts_with(&root) {
+                    error!("Unable to include directory: {:?}", path);
+                    error!("Directory is outside of root {:?}", root);
+                    failed.push(path.to_owned());
+                    continue;
+                }
+                let mut ignores = ignore_file.iter().cloned().collect::<Vec<_>>();
+                let local = path.join(IGNORE_FILE);
+                if local.is_file() {
+                    match Self::read_ignore_file(&local) {
+                        Ok(ignore) => ignores.push(ignore),
+                        Err(err) => {
+                            error!("Unable to read ignore file: {:?}", local);
+                            error!("{}", err);
+                            failed.push(local.to_owned());
+                            continue;
+                        }
+                    }
+                }
+                let follow = self.follow_links(include);
+                if follow != self.follow {
+                    info!("Following symbolic links: {}", follow);
+                }
+                let walk = WalkDir::new(path)
+                    .follow_links(follow)
+                    .max_depth(self.max_depth.map_or(usize::MAX, NonZeroUsize::get))
+                    .sort_by_file_name();
+                for entry in walk {
+                    let entry = match entry {
+                        Ok(entry) => entry,
+                        Err(err) if err.loop_ancestor().is_some() => {
+                            error!("Symlink loop detected at {:?}", err.path());
+                            error!(
+                                "Link points to its ancestor {:?}",
+                                err.loop_ancestor().unwrap()
+                            );
+                            failed.push(err.path().map(Path::to_owned).unwrap_or_default());
+                            continue;
+                        }
+                        Err(err) => {
+                            error!("Unable to read file: {:?}", err.path());
+                            error!("{}", err);
+                            failed.push(err.path().map(Path::to_owned).unwrap_or_default());
+                            continue;
+                        }
+                    };
+
+                    if let Some(ext) = entry.path().extension() {
+                        let ext = match ext.to_str() {
+                            Some(ext) => ext.to_owned(),
+                            None => {
+                                error!("Unable to read extension of file: {:?}", entry.path());
+                                error!("File extension is not valid UTF-8");
+                                failed.push(entry.path().to_owned());
+                                continue;
+                            }
+                        };
+
+                        if !self.extensions.contains(&ext)
+                            && !self.partial_extensions.contains(&ext)
+                        {
+                            continue;
+                        }
+                    } else {
+                        continue;
+                    }
+
+                    let meta = match entry.metadata() {
+                        Ok(meta) => meta,
+                        Err(err) => {
+                            error!("Unable to read metadata of file: {:?}", err.path());
+                            error!("{}", err);
+                            failed.push(err.path().map(Path::to_owned).unwrap_or_default());
+                            continue;
+                        }
+                    };
+                    if meta.is_file() {
+                        info!("Reading file: {:?}", entry.path());
+                        let name = entry.path();
+
+                        if let Some(stem) = name.file_stem() {
+                            if stem.as_encoded_bytes().first() == Some(&b'.') {
+                                continue;
+                            }
+                        } else {
+                            error!("Unable to register file: {:?}", entry.path());
+                            error!("File name is not valid UTF-8");
+                            failed.push(entry.path().to_owned());
+                            continue;
+                        }
+                        if ignores
+                            .iter()
+                            .any(|ignore| Self::is_ignored(ignore, entry.path()))
+                        {
+                            info!("Ignoring file: {:?}", entry.path());
+                            continue;
+                        }
+
+                        let Some(name) = Self::template_name(
+                            prefix,
+                            Self::normalize(name).strip_prefix(&root).unwrap(),
+                        ) else {
+                            error!("Unable to register file: {:?}", entry.path());
+                            error!("File path is not valid UTF-8");
+                            failed.push(entry.path().to_owned());
+                            continue;
+                        };
+                        if !self.check_name(&mut sources, &name, entry.path()) {
+                            failed.push(entry.path().to_owned());
+                            continue;
+                        }
+                        if self.template_limit_reached(&registry) {
+                            failed.push(entry.path().to_owned());
+                            break 'includes;
+                        }
+                        let registered = Self::read_file(entry.path())
+                            .map_err(|err| err.to_string())
+                            .and_then(|source| {
+                                registry
+                                    .register_template_string(&name, source)
+                                    .map_err(|err| err.to_string())
+                            });
+                        if let Err(err) = registered {
+                            error!("Unable to register file: {:?}", entry.path());
+                            error!("{}", err);
+                            failed.push(entry.path().to_owned());
+                            continue;
+                        }
+                        info!("Registered template: {:?}", name);
+                    }
+                }
+            } else if path.is_file() {
+                info!("Reading file: {:?}", &path);
+                let Some(name) = path
+                    .file_name()
+                    .and_then(|name| Self::template_name(prefix, name.as_ref()))
+                else {
+                    error!("Unable to register file: {:?}", path);
+                    error!("File path is not valid UTF-8");
+                    failed.push(path.to_owned());
+                    continue;
+                };
+                if !self.check_name(&mut sources, &name, &path) {
+                    failed.push(path.to_owned());
+                    continue;
+                }
+                if self.template_limit_reached(&registry) {
+                    failed.push(path.to_owned());
+                    break;
+                }
+                let registered = Self::read_file(&path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|source| {
+                        registry
+                            .register_template_string(&name, source)
+                            .map_err(|err| err.to_string())
+                    });
+                if let Err(err) = registered {
+                    error!("Unable to register file: {:?}", path);
+                    error!("{}", err);
+                    failed.push(path.to_owned());
+                    continue;
+                }
+                info!("Registered template: {:?}", name);
+            }
+        }
+        if self.sidecar_data {
+            self.register_sidecars(&mut registry, &sources, &mut failed);
+            helpers.insert("sidecar".to_owned());
+        }
+        let report = RegistryReport {
+            registered: registry.get_templates().len(),
+            failed,
+        };
+        if !report.failed.is_empty() {
+            return Err(report);
+        }
+        let mut missing = false;
+        for entry in self.entries() {
+            if registry.get_template(entry).is_none() {
+                error!("Unable to find entry template: {:?}", entry);
+                missing = true;
+            }
+        }
+        if self.strict_undefined_partials {
+            for (template, partial) in references::missing_partials(&registry) {
+                error!(
+                    "Unable to find partial {:?} referenced by {:?}",
+                    partial, template
+                );
+                missing = true;
+            }
+        }
+        if missing {
+            return Err(report);
+        }
+        let sources = Sources {
+            files: sources,
+            helpers,
+        };
+        Ok((registry, sources))
+    }
+
+    /// Wrap the included templates that have a data file of the same stem next
+    /// to them, e.g. `foo.json` next to `foo.hbs`, in the helper `sidecar`,
+    /// which renders them with that data merged over their context. The main
+    /// template and the templates of archives have no sidecar data.
+    fn register_sidecars(
+        &self,
+        registry: &mut Handlebars,
+        sources: &HashMap<String, PathBuf>,
+        failed: &mut Vec<PathBuf>,
+    ) {
+        let mut templates = sources
+            .iter()
+            .filter(|(_, path)| self.template.as_ref() != Some(*path))
+            .collect::<Vec<_>>();
+        templates.sort();
+
+        let mut data = HashMap::new();
+        for (name, path) in templates {
+            let Some(sidecar) = SIDECAR_EXTENSIONS
+                .iter()
+                .map(|ext| path.with_extension(ext))
+                .find(|sidecar| sidecar != path && sidecar.is_file())
+            else {
+                continue;
+            };
+            let value = match self.read_datafile(&sidecar) {
+                Ok(value) => value,
+                Err((context, err)) => {
+                    error!("{}: {:?}", context, sidecar);
+                    error!("{}", err);
+                    failed.push(sidecar);
+                    continue;
+                }
+            };
+            let registered = Self::read_file(path)
+                .map_err(|err| err.to_string())
+                .and_then(|source| {
+                    let source = format!("{{{{#sidecar {:?}}}}}{}{{{{/sidecar}}}}", name, source);
+                    registry
+                        .register_template_string(name, source)
+                        .map_err(|err| err.to_string())
+                });
+            if let Err(err) = registered {
+                error!("Unable to register file: {:?}", path);
+                error!("{}", err);
+                failed.push(path.to_owned());
+                continue;
+            }
+            info!("Registered sidecar data of {:?}: {:?}", name, sidecar);
+            data.insert(name.to_owned(), value);
+        }
+        registry.register_helper(
+            "sidecar",
+            Box::new(helpers::Sidecar::new(data, self.merge_arrays)),
+        );
+    }
+
+    /// Whether an include is a `.zip`, `.tar`, `.tar.gz` or `.tgz` archive by
+    /// its file name.
+    fn is_archive(path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .map(str::to_ascii_lowercase)
+            .is_some_and(|name| {
+                [".zip", ".tar", ".tar.gz", ".tgz"]
+                    .iter()
+                    .any(|ext| name.ends_with(ext))
+            })
+    }
+
+    /// Register the templates of an archive included like a directory. The
+    /// names are derived from the paths inside the archive. Extensions, dot
+    /// files and `max_depth` apply as for directories, ignore files do not.
+    /// Returns `false` if the template limit is reached.
+    #[cfg(feature = "archive")]
+    fn register_archive(
+        &self,
+        registry: &mut Handlebars,
+        sources: &mut HashMap<String, PathBuf>,
+        prefix: Option<&str>,
+        path: &Path,
+        failed: &mut Vec<PathBuf>,
+    ) -> bool {
+        info!("Reading archive: {:?}", path);
+        let max_depth = self.max_depth.map_or(usize::MAX, NonZeroUsize::get);
+        let entries = archive::read(path, |entry| {
+            let ext = entry.extension().and_then(|ext| ext.to_str());
+            let dotfile = entry
+                .file_stem()
+                .is_some_and(|stem| stem.as_encoded_bytes().first() == Some(&b'.'));
+            entry.components().count() <= max_depth
+                && ext.is_some_and(|ext| {
+                    self.extensions.iter().any(|e| e == ext)
+                        || self.partial_extensions.iter().any(|e| e == ext)
+                })
+                && !dotfile
+        });
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(err) => {
+                error!("Unable to read archive: {:?}", path);
+                error!("{}", err);
+                failed.push(path.to_owned());
+                return true;
+            }
+        };
+
+        for entry in entries {
+            let source = path.join(&entry.path);
+            info!("Reading file: {:?}", source);
+            let Some(name) = Self::template_name(prefix, &entry.path) else {
+                error!("Unable to register file: {:?}", source);
+                error!("File path is not valid UTF-8");
+                failed.push(source);
+                continue;
+            };
+            if !self.check_name(sources, &name, &source) {
+                failed.push(source);
+                continue;
+            }
+            if self.template_limit_reached(registry) {
+                failed.push(source);
+                return false;
+            }
+            let registered = String::from_utf8(entry.content)
+                .map_err(|err| err.to_string())
+                .and_then(|content| {
+                    registry
+                        .register_template_string(&name, strip_bom(content, &source))
+                        .map_err(|err| err.to_string())
+                });
+            if let Err(err) = registered {
+                error!("Unable to register file: {:?}", source);
+                error!("{}", err);
+                failed.push(source);
+                continue;
+            }
+            info!("Registered template: {:?}", name);
+        }
+        true
+    }
+
+    #[cfg(not(feature = "archive"))]
+    fn register_archive(
+        &self,
+        _: &mut Handlebars,
+        _: &mut HashMap<String, PathBuf>,
+        _: Option<&str>,
+        path: &Path,
+        failed: &mut Vec<PathBuf>,
+    ) -> bool {
+        error!("Unable to read archive: {:?}", path);
+        error!("Archive support is not enabled, rebuild with feature `archive`");
+        failed.push(path.to_owned());
+        true
+    }
+
+    /// Check whether the registry holds the maximum number of templates, so
+    /// no more can be registered.
+    fn template_limit_reached(&self, registry: &Handlebars) -> bool {
+        let Some(max) = self.max_templates else {
+            return false;
+        };
+        if registry.get_templates().len() < max.get() {
+            return false;
+        }
+        error!("Too many templates, the maximum is {}", max);
+        error!("Check the include entries or raise the limit with --max-templates");
+        true
+    }
+
+    /// Split a data file of the form `path@/pointer` into the path and the
+    /// JSON pointer its data is merged at. A path that exists as given has no
+    /// pointer, so file names containing `@` keep working.
+    fn split_pointer(datafile: &Path) -> (&Path, Option<&str>) {
+        if datafile.exists() {
+            return (datafile, None);
+        }
+        match datafile
+            .to_str()
+            .and_then(|datafile| datafile.rsplit_once('@'))
+        {
+            Some((path, pointer)) if pointer.starts_with('/') => (Path::new(path), Some(pointer)),
+            _ => (datafile, None),
+        }
+    }
+
+    /// Expand a data file path containing glob characters (`*`, `?` or `[`)
+    /// into the matching files in sorted order. Returns `None` if the path is
+    /// not a pattern or is a URL.
+    fn expand_glob(path: &Path) -> Option<Result<Vec<PathBuf>, glob::PatternError>> {
+        let pattern = path.to_str()?;
+
+        if !pattern.contains(['*', '?', '[']) || Self::url(path).is_some() {
+            return None;
+        }
+        let paths = glob::glob(pattern).map(|paths| {
+            let mut paths = paths
+                .filter_map(Result::ok)
+                .filter(|path| path.is_file())
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths
+        });
+        Some(paths)
+    }
+
+    /// Split an include of the form `prefix:path` into the name prefix and the
+    /// path. An include that exists as given has no prefix, so paths
+    /// containing a colon and Windows drive letters keep working.
+    fn split_prefix(include: &Path) -> (Option<&str>, &Path) {
+        if include.exists() {
+            return (None, include);
+        }
+        match include.to_str().and_then(|include| include.split_once(':')) {
+            Some((prefix, path)) => (Some(prefix.trim_matches('/')), Path::new(path)),
+            None => (None, include),
+        }
+    }
+
+    /// Whether symbolic links are followed when walking the include entry.
+    /// The `follow_links` table of the configuration overrides `--follow`
+    /// for the entries it lists.
+    fn follow_links(&self, include: &Path) -> bool {
+        Self::include_setting(&self.follow_links, include)
+            .copied()
+            .unwrap_or(self.follow)
+    }
+
+    /// The setting of an include entry in a table keyed by include entries,
+    /// as given or without the name prefix.
+    fn include_setting<'a, T>(settings: &'a BTreeMap<PathBuf, T>, include: &Path) -> Option<&'a T> {
+        let path = Self::normalize(Self::split_prefix(include).1);
+        let include = Self::normalize(include);
+
+        settings
+            .iter()
+            .find(|(entry, _)| {
+                let entry = Self::normalize(entry);
+                entry == include || entry == path
+            })
+            .map(|(_, setting)| setting)
+    }
+
+    /// Remove `.` components, so `./docs` and `docs` have the same prefixes.
+    fn normalize(path: &Path) -> PathBuf {
+        path.components()
+            .filter(|component| *component != Component::CurDir)
+            .collect()
+    }
+
+    /// Derive the template name from a path relative to the include root. The
+    /// file extension is removed and the components are joined with forward
+    /// slashes on all platforms, the prefix is prepended unless empty. Returns
+    /// `None` if the path is not valid UTF-8.
+    fn template_name(prefix: Option<&str>, path: &Path) -> Option<String> {
+        let path = path.with_extension("");
+        let components = prefix
+            .filter(|prefix| !prefix.is_empty())
+            .map(Some)
+            .into_iter()
+            .chain(
+                path.components()
+                    .map(|component| component.as_os_str().to_str()),
+            )
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(components.join("/"))
+    }
+
+    /// Record the source of a template name. If the name was already taken
+    /// by another file, a warning is logged as the template is overwritten.
+    /// In strict mode the collision is an error and `false` is returned.
+    fn check_name(&self, sources: &mut HashMap<String, PathBuf>, name: &str, path: &Path) -> bool {
+        match sources.insert(name.to_owned(), path.to_owned()) {
+            Some(previous) if self.strict => {
+                error!("Unable to register file: {:?}", path);
+                error!(
+                    "Template name {:?} is already registered from {:?}",
+                    name, previous
+                );
+                false
+            }
+            Some(previous) => {
+                warn!(
+                    "Template {:?} from {:?} overwrites the one from {:?}",
+                    name, path, previous
+                );
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// The number of lines of the front matter of the main template, which
+    /// precede its registered source in the file.
+    fn front_matter_lines(&self) -> usize {
+        let Some(source) = self
+            .template
+            .as_deref()
+            .and_then(|path| Self::read_file(path).ok())
+        else {
+            return 0;
+        };
+        match front_matter::split(&source) {
+            Ok((_, body)) => source[..source.len() - body.len()].matches('\n').count(),
+            Err(_) => 0,
+        }
+    }
+
+    fn read_file(path: &Path) -> std::io::Result<String> {
+        Ok(strip_bom(read_to_string(File::open(path)?)?, path))
+    }
+
+    /// Read the front matter of the main template. Returns `null` if there is
+    /// none. Errors reading the template itself are reported when registering
+    /// it and are ignored here.
+    fn read_front_matter(&self) -> Result<serde_json::Value, FrontMatterError> {
+        let Some(Ok(source)) = self.template.as_deref().map(Self::read_file) else {
+            return Ok(serde_json::Value::Null);
+        };
+        match front_matter::split(&source) {
+            Ok((Some(front_matter), _)) => front_matter.parse(),
+            _ => Ok(serde_json::Value::Null),
+        }
+    }
+
+    /// Read a gitignore-style ignore file. Patterns are relative to the
+    /// directory containing the file.
+    fn read_ignore_file(path: &Path) -> Result<Gitignore, ignore::Error> {
+        let path = std::path::absolute(path)?;
+        let mut builder = GitignoreBuilder::new(path.parent().unwrap_or(Path::new("/")));
+
+        if let Some(err) = builder.add(&path) {
+            return Err(err);
+        }
+        builder.build()
+    }
+
+    fn is_ignored(ignore: &Gitignore, path: &Path) -> bool {
+        match std::path::absolute(path) {
+            Ok(path) if path.starts_with(ignore.path()) => {
+                ignore.matched_path_or_any_parents(&path, false).is_ignore()
+            }
+            _ => false,
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn read_data(&self) -> Option<serde_json::Value> {
+        let mut failed = false;
+        let mut data = match self.read_front_matter() {
+            Ok(serde_json::Value::Null) => self.data.clone(),
+            Ok(mut data) => {
+                data::merge(&mut data, self.data.clone(), self.merge_arrays);
+                data
+            }
+            Err(err) => {
+                error!(
+                    "Unable to read front matter of main template: {:?}",
+                    self.template.as_deref().unwrap_or(Path::new(""))
+                );
+                error!("{}", err);
+                failed = true;
+                self.data.clone()
+            }
+        };
+        if let Some(path) = &self.default_data {
+            match self.read_datafile(path) {
+                Ok(value) if data::replaces_root(&value, &data) => {
+                    error!("Unable to merge default data file: {:?}", path);
+                    error!("Unable to merge an object with an array or value at the data root");
+                    failed = true;
+                }
+                Ok(mut value) => {
+                    data::merge(&mut value, data, self.merge_arrays);
+                    data = value;
+                }
+                Err((context, err)) => {
+                    error!("{}: {:?}", context, path);
+                    error!("{}", err);
+                    failed = true;
+                }
+            }
+        }
+
+        let mut datafiles = Vec::with_capacity(self.datafiles.len());
+        let mut pointers = Vec::with_capacity(self.datafiles.len());
+        let mut sensitive = Vec::with_capacity(self.datafiles.len());
+        let sources = self
+            .datafiles
+            .iter()
+            .map(|path| (path, false))
+            .chain(self.secrets.iter().map(|path| (path, true)));
+        for (path, secret) in sources {
+            let (path, pointer) = Self::split_pointer(path);
+            let paths = match Self::expand_glob(path) {
+                Some(Ok(paths)) if paths.is_empty() => {
+                    warn!("Data file pattern matches no files: {:?}", path);
+                    continue;
+                }
+                Some(Ok(paths)) => self.with_overlays(paths),
+                Some(Err(err)) => {
+                    error!("Invalid data file pattern: {:?}", path);
+                    error!("{}", err);
+                    failed = true;
+                    continue;
+                }
+                None => self.with_overlays(vec![path.to_owned()]),
+            };
+            pointers.extend(std::iter::repeat_n(pointer, paths.len()));
+            sensitive.extend(std::iter::repeat_n(secret, paths.len()));
+            datafiles.extend(paths);
+        }
+
+        let values = self.read_datafiles(&datafiles);
+        let files = datafiles.iter().zip(pointers).zip(sensitive);
+        for (((path, pointer), secret), value) in files.zip(values) {
+            match value {
+                Ok(value) => {
+                    if secret {
+                        secrets::register(&value);
+                    }
+                    let value = match pointer {
+                        Some(pointer) => data::nest(value, pointer),
+                        None => value,
+                    };
+                    if data::replaces_root(&data, &value) {
+                        error!("Unable to merge data file: {:?}", path);
+                        error!("Unable to merge an object with an array or value at the data root");
+                        failed = true;
+                        continue;
+                    }
+                    data::merge(&mut data, value, self.merge_arrays)
+                }
+                Err((context, err)) => {
+                    error!("{}: {:?}", context, path);
+                    error!("{}", err);
+                    failed = true;
+                }
+            }
+        }
+
+        for command in &self.data_commands {
+            info!("Running data command: {:?}", command.command);
+            match command.run() {
+                Ok(value) if data::replaces_root(&data, &value) => {
+                    error!(
+                        "Unable to merge output of data command: {:?}",
+                        command.command
+                    );
+                    error!("Unable to merge an object with an array or value at the data root");
+                    failed = true;
+                }
+                Ok(value) => data::merge(&mut data, value, self.merge_arrays),
+                Err((context, err)) => {
+                    error!("{}: {:?}", context, command.command);
+                    error!("{}", err);
+                    failed = true;
+                }
+            }
+        }
+
+        // Checked before the built-in values and the environment are added,
+        // which are never empty.
+        if self.require_data && Self::is_empty(&data) {
+            if datafiles.is_empty() && self.data_commands.is_empty() {
+                error!("Data is required, but no data file or data command is configured");
+            } else {
+                error!(
+                    "Data is required, but is empty after reading {} data files and {} data commands",
+                    datafiles.len(),
+                    self.data_commands.len()
+                );
+            }
+            failed = true;
+        }
+        if self.builtins {
+            if let Err(err) = self.insert_builtins(&mut data) {
+                error!("Unable to format date with format: {:?}", self.date_format);
+                error!("{}", err);
+                failed = true;
+            }
+        }
+        if self.env {
+            self.insert_env(&mut data);
+        }
+        for pointer in &self.sensitive {
+            match data.pointer(pointer) {
+                Some(value) => secrets::register(value),
+                None => info!("Sensitive data key not found: {:?}", pointer),
+            }
+        }
+
+        if failed {
+            return None;
+        }
+        Some(data)
+    }
+
+    /// Whether data is `null`, an empty object or an empty array.
+    fn is_empty(data: &serde_json::Value) -> bool {
+        match data {
+            serde_json::Value::Null => true,
+            serde_json::Value::Object(map) => map.is_empty(),
+            serde_json::Value::Array(items) => items.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Read and parse the data files using up to `jobs()` threads. The
+    /// results are returned in the order of `paths`, so the merge does not
+    /// depend on the number of threads.
+    fn read_datafiles(&self, paths: &[PathBuf]) -> Vec<Result<serde_json::Value, DataFileError>> {
+        let jobs = self.jobs().min(paths.len());
+
+        if jobs <= 1 {
+            return paths.iter().map(|path| self.read_datafile(path)).collect();
+        }
+        let next = AtomicUsize::new(0);
+        let mut results = std::thread::scope(|scope| {
+            let workers = (0..jobs)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut results = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(path) = paths.get(index) else {
+                                break results;
+                            };
+                            results.push((index, self.read_datafile(path)));
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Read and parse a single data file, `-` reads from stdin. A file ending
+    /// in `.gz` is decompressed, its format is given by the extension before.
+    fn read_datafile(&self, path: &Path) -> Result<serde_json::Value, DataFileError> {
+        const READ: &str = "Unable to read data file";
+
+        if let Some(url) = Self::url(path) {
+            return self.read_url(url);
+        }
+
+        let gzip = path.extension().is_some_and(|ext| ext == "gz");
+        let format = if path == Path::new(STDIN) {
+            info!("Reading data from stdin");
+            self.data_format
+        } else {
+            info!("Reading data file: {:?}", path);
+            let inner = match path.file_stem() {
+                Some(stem) if gzip => Path::new(stem),
+                _ => path,
+            };
+            // A `.env` file has no extension, its name is the extension.
+            inner
+                .extension()
+                .or_else(|| {
+                    inner
+                        .file_name()
+                        .filter(|name| *name == ".env")
+                        .map(|_| "env".as_ref())
+                })
+                .and_then(|ext| ext.to_str())
+                .and_then(|ext| {
+                    self.data_formats
+                        .get(ext)
+                        .copied()
+                        .or_else(|| DataFormat::from_extension(ext))
+                })
+        };
+        let Some(format) = format else {
+            return Err((READ, "Unsupported file extension".to_owned()));
+        };
+        let content = if path == Path::new(STDIN) {
+            read_to_string(std::io::stdin()).map_err(|err| (READ, err.to_string()))?
+        } else {
+            let file =
+                File::open(path).map_err(|err| ("Unable to open data file", err.to_string()))?;
+            if gzip {
+                read_to_string(MultiGzDecoder::new(file))
+                    .map_err(|err| ("Unable to decompress data file", err.to_string()))?
+            } else {
+                read_to_string(file).map_err(|err| (READ, err.to_string()))?
+            }
+        };
+        let content = strip_bom(content, path);
+        format
+            .parse(&content)
+            .map_err(|err| (READ, err.to_string()))
+    }
+
+    /// Fetch and parse a data file from a URL. The format is given by the
+    /// extension of the URL path, or else by the `Content-Type` of the
+    /// response.
+    #[cfg(feature = "http")]
+    fn read_url(&self, url: &str) -> Result<serde_json::Value, DataFileError> {
+        const READ: &str = "Unable to read data URL";
+
+        info!("Fetching data URL: {:?}", url);
+        let response = http::get(url, self.http_timeout())
+            .map_err(|err| ("Unable to fetch data URL", err.to_string()))?;
+        let format = Self::url_extension(url)
+            .and_then(|ext| {
+                self.data_formats
+                    .get(ext)
+                    .copied()
+                    .or_else(|| DataFormat::from_extension(ext))
+            })
+            .or_else(|| {
+                response
+                    .mime_type
+                    .as_deref()
+                    .and_then(DataFormat::from_mime_type)
+            });
+        let Some(format) = format else {
+            return Err((
+                READ,
+                "Unsupported URL extension and content type".to_owned(),
+            ));
+        };
+        let content = strip_bom(response.body, Path::new(url));
+        format
+            .parse(&content)
+            .map_err(|err| (READ, err.to_string()))
+    }
+
+    #[cfg(not(feature = "http"))]
+    fn read_url(&self, _url: &str) -> Result<serde_json::Value, DataFileError> {
+        Err((
+            "Unable to fetch data URL",
+            "HTTP support is not enabled, rebuild with feature `http`".to_owned(),
+        ))
+    }
+
+    /// The time to wait for a response, see [`DEFAULT_HTTP_TIMEOUT`].
+    #[cfg(feature = "http")]
+    fn http_timeout(&self) -> Duration {
+        let timeout = self
+            .http_timeout
+            .map_or(DEFAULT_HTTP_TIMEOUT, NonZeroU64::get);
+        Duration::from_secs(timeout)
+    }
+
+    /// The URL of a data file starting with `http://` or `https://`.
+    fn url(path: &Path) -> Option<&str> {
+        path.to_str()
+            .filter(|path| path.starts_with("http://") || path.starts_with("https://"))
+    }
+
+    /// The extension of the last segment of a URL path, ignoring the query
+    /// and fragment.
+    #[cfg(feature = "http")]
+    fn url_extension(url: &str) -> Option<&str> {
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        let (_, rest) = path.split_once("://")?;
+        let (_, path) = rest.split_once('/')?;
+        let name = path.rsplit('/').next()?;
+        name.rsplit_once('.')
+            .map(|(_, ext)| ext)
+            .filter(|ext| !ext.is_empty())
+    }
+
+    /// Render the output template, if any, against the data and use the
+    /// result as the output path.
+    pub fn resolve_output(&mut self, registry: &Handlebars, data: &serde_json::Value) -> bool {
+        if self.output_template.is_none() {
+            return true;
+        }
+        match self.render_output_path(registry, data) {
+            Some(output) => {
+                self.output = output;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Render the output template against the data, without HTML escaping.
+    /// The rendered path must not be empty. In safe mode it must neither be
+    /// absolute nor contain `..`. It is then resolved against the directory
+    /// of the configuration file defining the output template.
+    pub fn render_output_path(
+        &self,
+        registry: &Handlebars,
+        data: &serde_json::Value,
+    ) -> Option<PathBuf> {
+        let template = self.output_template.as_ref()?;
+        let output = match Self::render_unescaped(registry, template, data) {
+            Ok(output) => PathBuf::from(output.trim()),
+            Err(err) => {
+                error!("Unable to render output template: {:?}", template);
+                error!("{}", err);
+                return None;
+            }
+        };
+
+        if output.as_os_str().is_empty() {
+            error!("Output template rendered an empty path: {:?}", template);
+            return None;
+        }
+        if self.safe
+            && (output.has_root()
+                || output
+                    .components()
+                    .any(|c| c == std::path::Component::ParentDir))
+        {
+            error!("Output path escapes the working directory: {:?}", output);
+            return None;
+        }
+        let output = match &self.output_template_dir {
+            Some(dir) => dir.join(output),
+            None => output,
+        };
+        info!("Rendered output path: {:?}", output);
+        Some(output)
+    }
+
+    /// Render a template string like `Handlebars::render_template`, but
+    /// without HTML escaping, whatever the escape function of the registry.
+    fn render_unescaped(
+        registry: &Handlebars,
+        source: &str,
+        data: &serde_json::Value,
+    ) -> Result<String, RenderError> {
+        let mut template = Template::compile(source).map_err(RenderError::from)?;
+        Self::disable_escape(&mut template);
+        let context = Context::wraps(data)?;
+        let mut output = StringOutput::new();
+        template.render(
+            registry,
+            &context,
+            &mut RenderContext::new(None),
+            &mut output,
+        )?;
+        output.into_string().map_err(RenderError::from)
+    }
+
+    /// Turn the expressions of the template into raw expressions, as if they
+    /// were written with triple braces.
+    fn disable_escape(template: &mut Template) {
+        for element in &mut template.elements {
+            let placeholder = TemplateElement::RawString(String::new());
+            *element = match std::mem::replace(element, placeholder) {
+                TemplateElement::Expression(helper) => TemplateElement::HtmlExpression(helper),
+                TemplateElement::HelperBlock(mut helper) => {
+                    for template in helper.template.iter_mut().chain(helper.inverse.iter_mut()) {
+                        Self::disable_escape(template);
+                    }
+                    TemplateElement::HelperBlock(helper)
+                }
+                element => element,
+            };
+        }
+    }
+
+    /// The render contexts of the items of the array at the `each` pointer.
+    /// Each context is the data with the keys of the item laid over it and
+    /// the item and its index added as `docfmt.item` and `docfmt.index`.
+    /// Returns `None` if no `each` pointer is configured or it does not
+    /// point to an array.
+    pub fn each_contexts(&self, data: &serde_json::Value) -> Option<Vec<serde_json::Value>> {
+        let pointer = self.each.as_ref()?;
+        let items = match data.pointer(pointer) {
+            Some(serde_json::Value::Array(items)) => items,
+            Some(_) => {
+                error!("Data at {:?} is not an array", pointer);
+                return None;
+            }
+            None => {
+                error!("No data found at {:?}", pointer);
+                return None;
+            }
+        };
+
+        let contexts = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let mut context = data.clone();
+                if let serde_json::Value::Object(context) = &mut context {
+                    if let serde_json::Value::Object(item) = item {
+                        for (key, value) in item {
+                            context.insert(key.to_owned(), value.to_owned());
+                        }
+                    }
+                    let builtins = context
+                        .entry(BUILTINS_KEY)
+                        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::default()));
+                    if let serde_json::Value::Object(builtins) = builtins {
+                        builtins.insert("item".to_owned(), item.to_owned());
+                        builtins.insert("index".to_owned(), index.into());
+                    }
+                }
+                context
+            })
+            .collect();
+        Some(contexts)
+    }
+
+    /// Check that the rendered content is valid in the output format.
+    pub fn check_output(&self, content: &str) -> bool {
+        if let Err(err) = self.output_format.validate(content) {
+            error!("Rendered output is not valid {:?}", self.output_format);
+            error!("{}", err);
+            return false;
+        }
+        true
+    }
+
+    pub fn write_output(&self, content: String) -> bool {
+        self.write_output_to(&self.output, content)
+    }
+
+    /// Write the content to the given output file, honoring the `force`
+    /// and `append` options. The output `-` writes to stdout.
+    pub fn write_output_to(&self, output: &Path, content: String) -> bool {
+        let Some(content) = self.pretty_output(content) else {
+            return false;
+        };
+        if output == Path::new(STDIN) {
+            info!("Writing output to stdout");
+            let content = self.normalize_output(content);
+            if let Err(err) = Self::write_stdout(content.as_bytes()) {
+                error!("Unable to write output to stdout");
+                error!("{}", err);
+                return false;
+            }
+            return true;
+        }
+
+        info!("Writing output file: {:?}", output);
+        if output.exists() && !self.force && !self.append {
+            error!("Output file already exists: {:?}", output);
+            return false;
+        }
+        if !self.allow_overwrite_source {
+            if let Some(source) = self.source_at(output) {
+                error!("Output file is a source file: {:?}", output);
+                error!(
+                    "Refusing to overwrite {:?}, use --allow-overwrite-source",
+                    source
+                );
+                return false;
+            }
+        }
+
+        if let Some(parent) = output.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                info!("Creating output directory: {:?}", parent);
+                if let Err(err) = std::fs::create_dir_all(parent) {
+                    error!("Unable to create output directory: {:?}", parent);
+                    error!("{}", err);
+                    return false;
+                }
+            }
+        }
+
+        let content = if self.append && !Self::ends_with_newline(output) {
+            format!("\n{}", content)
+        } else {
+            content
+        };
+        let content = self.normalize_output(content);
+        let result = if self.append {
+            Self::append(output, content.as_bytes())
+        } else {
+            Self::write_atomic(output, content.as_bytes())
+        };
+        if let Err(err) = result {
+            error!("Unable to write output file: {:?}", output);
+            error!("{}", err);
+            return false;
+        }
+        true
+    }
+
+    /// Pretty-print the content in the output format if `pretty` is set.
+    fn pretty_output(&self, content: String) -> Option<String> {
+        if !self.pretty {
+            return Some(content);
+        }
+        match self.output_format.pretty(content) {
+            Ok(content) => Some(content),
+            Err(err) => {
+                error!("Unable to pretty-print output as {:?}", self.output_format);
+                error!("{}", err);
+                None
+            }
+        }
+    }
+
+    /// Collapse the blank lines and normalize the line endings of the content
+    /// as configured.
+    fn normalize_output(&self, content: String) -> String {
+        let content = if self.collapse_blank_lines {
+            collapse_blank_lines(&content)
+        } else {
+            content
+        };
+        match self.line_ending {
+            Some(ending) => ending.normalize(&content),
+            None => content,
+        }
+    }
+
+    /// Write the content to stdout and flush it.
+    fn write_stdout(content: &[u8]) -> std::io::Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(content)?;
+        stdout.flush()
+    }
+
+    /// Find the main template, included file or data file that is the same
+    /// file as `output`. Paths are compared canonicalized. Files inside an
+    /// included directory count if their extension is included.
+    fn source_at(&self, output: &Path) -> Option<PathBuf> {
+        let output = output.canonicalize().ok()?;
+        let included = output
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                self.extensions.iter().any(|e| e == ext)
+                    || self.partial_extensions.iter().any(|e| e == ext)
+            });
+
+        self.template
+            .iter()
+            .cloned()
+            .chain(
+                self.include
+                    .iter()
+                    .map(|include| Self::split_prefix(include).1.to_owned()),
+            )
+            .chain(self.datafiles())
+            .find(|source| match source.canonicalize() {
+                Ok(source) if source.is_dir() => included && output.starts_with(source),
+                Ok(source) => source == output,
+                Err(_) => false,
+            })
+    }
+
+    /// Write `content` to a temporary file next to `path` and rename it over
+    /// `path`, so an interrupted write never leaves a truncated output file.
+    /// Falls back to copy and remove if the rename crosses devices. An
+    /// existing file keeps its permissions.
+    fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+        let result = File::create(&temp).and_then(|mut file| {
+            file.write_all(content)?;
+            if let Ok(meta) = std::fs::metadata(path) {
+                file.set_permissions(meta.permissions())?;
+            }
+            file.sync_all()
+        });
+        if let Err(err) = result {
+            let _ = std::fs::remove_file(&temp);
+            return Err(err);
+        }
+
+        match std::fs::rename(&temp, path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+                let result = std::fs::copy(&temp, path).map(|_| ());
+                let _ = std::fs::remove_file(&temp);
+                result
+            }
+            Err(err) => {
+                let _ = std::fs::remove_file(&temp);
+                Err(err)
+            }
+        }
+    }
+
+    /// Append the content to the file, creating it if it does not exist.
+    fn append(path: &Path, content: &[u8]) -> std::io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(content)?;
+        file.sync_all()
+    }
+
+    /// Whether the file is missing, empty or ends with a line break.
+    fn ends_with_newline(path: &Path) -> bool {
+        let last = File::open(path).and_then(|mut file| {
+            if file.seek(SeekFrom::End(-1)).is_err() {
+                return Ok(None);
+            }
+            let mut last = [0];
+            file.read_exact(&mut last)?;
+            Ok(Some(last[0]))
+        });
+        matches!(last, Err(_) | Ok(None) | Ok(Some(b'\n')))
+    }
+
+    /// Whether the top-level data key is exempt from the checks for unused
+    /// data: the built-in values, the environment and the translations.
+    fn exempt_key(key: &str) -> bool {
+        key == BUILTINS_KEY || key == ENV_KEY || key == helpers::TRANSLATIONS_KEY
+    }
+
+    /// The top-level keys of the data that no rendered template can use, by a
+    /// static scan of the templates, see `references::used_paths`. The keys of
+    /// `exempt_key` are skipped and the paths used are logged at info level.
+    /// Returns `None` if a template uses the whole data.
+    fn unused_keys<'a>(
+        &self,
+        registry: &Handlebars,
+        sources: &Sources,
+        data: &'a serde_json::Value,
+    ) -> Option<Vec<&'a String>> {
+        let entries = self.rendered_entries(registry);
+        let entries = entries.iter().map(String::as_str).collect::<Vec<_>>();
+        let used = references::used_paths(registry, &sources.helpers, &entries);
+        for path in &used {
+            info!("Data path is used: {:?}", path);
+        }
+        if used.contains("") {
+            return None;
+        }
+        let used = used
+            .iter()
+            .filter_map(|path| references::segments(path).first().copied())
+            .collect::<BTreeSet<_>>();
+        let serde_json::Value::Object(root) = data else {
+            return Some(Vec::new());
+        };
+        Some(
+            root.keys()
+                .filter(|key| !Self::exempt_key(key) && !used.contains(key.as_str()))
+                .collect(),
+        )
+    }
+
+    /// Check that every top-level key of the data can be used by one of the
+    /// rendered templates, see `unused_keys`. Returns `false` if a key is
+    /// unused.
+    pub fn check_data_usage(
+        &self,
+        registry: &Handlebars,
+        sources: &Sources,
+        data: &serde_json::Value,
+    ) -> bool {
+        let Some(unused) = self.unused_keys(registry, sources, data) else {
+            warn!("Unable to check unused data keys, a template uses the whole data");
+            return true;
+        };
+        for key in &unused {
+            error!("Data key is not used by any template: {:?}", key);
+        }
+        unused.is_empty()
+    }
+
+    /// Render the computed values against the data and add them to it as
+    /// top-level keys. Values referencing other computed values are computed
+    /// after them, otherwise in the order of declaration. Returns `false` if a
+    /// template fails or the values reference each other in a cycle.
+    pub fn compute(&self, registry: &Handlebars, data: &mut serde_json::Value) -> bool {
+        if self.computed.is_empty() {
+            return true;
+        }
+        if !data.is_object() {
+            error!("Unable to add computed values, the data is not an object");
+            return false;
+        }
+        let mut dependencies = Vec::with_capacity(self.computed.len());
+        for (key, source) in &self.computed {
+            match Template::compile(source) {
+                Ok(template) => {
+                    let names = references::template_names(&template);
+                    dependencies.push(
+                        self.computed
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, (key, _))| names.contains(key))
+                            .map(|(index, _)| index)
+                            .collect::<BTreeSet<_>>(),
+                    );
+                }
+                Err(err) => {
+                    error!("Unable to compile computed value: {:?}", key);
+                    error!("{}", err);
+                    return false;
+                }
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.computed.len());
+        let mut state = vec![Visit::New; self.computed.len()];
+        for index in 0..self.computed.len() {
+            if let Err(cycle) = Self::visit(index, &dependencies, &mut state, &mut order) {
+                let keys = cycle
+                    .iter()
+                    .map(|index| self.computed[*index].0.as_str())
+                    .collect::<Vec<_>>();
+                error!("Computed values reference each other: {}", keys.join(" → "));
+                return false;
+            }
+        }
+
+        for index in order {
+            let (key, source) = &self.computed[index];
+            match registry.render_template(source, data) {
+                Ok(value) => {
+                    info!("Computed value: {:?}", key);
+                    if let serde_json::Value::Object(root) = data {
+                        root.insert(key.to_owned(), value.into());
+                    }
+                }
+                Err(err) => {
+                    error!("Unable to render computed value: {:?}", key);
+                    error!("{}", Self::computed_error(key, &err));
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Locate the error of the computed value `key` in the partial that
+    /// failed, or else in the template of the value as `computed.<key>`.
+    fn computed_error(key: &str, err: &RenderError) -> ConfigError {
+        let template = match &err.template_name {
+            Some(name) => name.to_owned(),
+            None => format!("computed.{}", key),
+        };
+        let location = match (err.line_no, err.column_no) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", template, line, column),
+            _ => template,
+        };
+
+        ConfigError::RenderError {
+            location,
+            reason: render_reason(err),
+        }
+    }
+
+    /// Append the computed value at `index` to `order` after its
+    /// dependencies. Returns the indices forming a cycle if one is found.
+    fn visit(
+        index: usize,
+        dependencies: &[BTreeSet<usize>],
+        state: &mut [Visit],
+        order: &mut Vec<usize>,
+    ) -> Result<(), Vec<usize>> {
+        match state[index] {
+            Visit::Done => return Ok(()),
+            Visit::Active => return Err(vec![index]),
+            Visit::New => {}
+        }
+        state[index] = Visit::Active;
+        for dependency in &dependencies[index] {
+            if let Err(mut cycle) = Self::visit(*dependency, dependencies, state, order) {
+                if cycle.len() == 1 || cycle.first() != cycle.last() {
+                    cycle.insert(0, index);
+                }
+                return Err(cycle);
+            }
+        }
+        state[index] = Visit::Done;
+        order.push(index);
+        Ok(())
+    }
+
+    /// Warn about each variable referenced by the entry templates that is
+    /// missing in the data, if strict mode is enabled. Rendering would fail
+    /// on the first of them, the warnings list them all at once. Returns the
+    /// number of missing variables.
+    pub fn check_references(
+        &self,
+        registry: &Handlebars,
+        sources: &Sources,
+        data: &serde_json::Value,
+    ) -> usize {
+        if !self.strict {
+            return 0;
+        }
+        let mut missing = 0;
+
+        for path in references::root_paths(registry, &sources.helpers, &self.entries()) {
+            if Self::data_value(data, &path).is_none() {
+                warn!("Template references a missing data field: {:?}", path);
+                missing += 1;
+            }
+        }
+        missing
+    }
+
+    /// The value at a path like `person.name` or `items.0` of the data.
+    fn data_value<'a>(data: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+        references::segments(path)
+            .into_iter()
+            .try_fold(data, |value, segment| match value {
+                serde_json::Value::Object(map) => map.get(segment),
+                serde_json::Value::Array(items) => {
+                    segment.parse::<usize>().ok().and_then(|i| items.get(i))
+                }
+                _ => None,
+            })
+    }
+
+    /// Whether a JSON Schema declares a path like `person.name`, following
+    /// `properties` and `additionalProperties` for keys and `items` for
+    /// indices.
+    fn schema_declares(schema: &serde_json::Value, path: &str) -> bool {
+        references::segments(path)
+            .into_iter()
+            .try_fold(schema, |schema, segment| {
+                schema
+                    .get("properties")
+                    .and_then(|properties| properties.get(segment))
+                    .or_else(|| {
+                        segment
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|_| schema.get("items"))
+                    })
+                    .or_else(|| {
+                        schema
+                            .get("additionalProperties")
+                            .filter(|schema| schema.is_object())
+                    })
+            })
+            .is_some()
+    }
+
+    /// Check the links of the written outputs. Local targets must exist
+    /// relative to the output, remote targets are requested if
+    /// `check_remote_links` is set, each URL once. Returns whether all links
+    /// are valid.
+    pub fn check_output_links(&self, outputs: &[PathBuf]) -> bool {
+        let mut broken = 0;
+        let mut remote = BTreeMap::<String, Vec<(&Path, usize)>>::new();
+
+        for output in outputs {
+            if output == Path::new(STDIN) {
+                info!("Skipping links of output written to stdout");
+                continue;
+            }
+            let content = match std::fs::read_to_string(output) {
+                Ok(content) => content,
+                Err(err) => {
+                    error!("Unable to read output file: {:?}", output);
+                    error!("{}", err);
+                    broken += 1;
+                    continue;
+                }
+            };
+            for link in links::extract(&content) {
+                match links::classify(link.target) {
+                    Target::Local(path) if !links::resolve(output, &path).exists() => {
+                        error!(
+                            "Broken link in {:?} line {}: {:?}",
+                            output, link.line, link.target
+                        );
+                        broken += 1;
+                    }
+                    Target::Remote(url) if self.check_remote_links => {
+                        remote.entry(url).or_default().push((output, link.line));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        broken += self.check_remote_links(remote);
+        if broken > 0 {
+            error!("Found {} broken links", broken);
+        }
+        broken == 0
+    }
+
+    /// Request the remote link targets, returns the number of broken links.
+    #[cfg(feature = "http")]
+    fn check_remote_links(&self, remote: BTreeMap<String, Vec<(&Path, usize)>>) -> usize {
+        let mut broken = 0;
+
+        for (url, links) in remote {
+            info!("Checking link: {:?}", url);
+            if let Err(err) = http::check(&url, self.http_timeout()) {
+                for (output, line) in &links {
+                    error!("Broken link in {:?} line {}: {:?}", output, line, url);
+                }
+                error!("{}", err);
+                broken += links.len();
+            }
+        }
+        broken
+    }
+
+    #[cfg(not(feature = "http"))]
+    fn check_remote_links(&self, remote: BTreeMap<String, Vec<(&Path, usize)>>) -> usize {
+        if !self.check_remote_links {
+            return 0;
+        }
+        error!("Unable to check remote links");
+        error!("HTTP support is not enabled, rebuild with feature `http`");
+        remote.values().map(Vec::len).sum::<usize>().max(1)
+    }
+
+    /// Warn about the registered templates not included, directly or through
+    /// other partials, by the rendered templates. Returns the number of unused
+    /// templates.
+    pub fn check_unused(&self, registry: &Handlebars) -> usize {
+        let unused = self.unused_templates(registry);
+        for name in &unused {
+            warn!("Template is never used: {:?}", name);
+        }
+        unused.len()
+    }
+
+    /// Log the registered templates that are not reachable from the entry
+    /// templates and the references to partials that are neither registered
+    /// nor defined inline as errors. Returns the number of problems found.
+    pub fn lint(&self, registry: &Handlebars) -> usize {
+        let unused = self.unused_templates(registry);
+        for name in &unused {
+            error!(
+                "Template is not reachable from the entry templates: {:?}",
+                name
+            );
+        }
+        let missing = references::missing_partials(registry);
+        for (template, partial) in &missing {
+            error!(
+                "Unable to find partial {:?} referenced by {:?}",
+                partial, template
+            );
+        }
+        unused.len() + missing.len()
+    }
+
+    /// Warn about each top-level key of the data that no rendered template can
+    /// use, see `unused_keys`. If a template uses the whole data no key is
+    /// reported, which is logged as a warning. Returns the number of unused
+    /// keys.
+    pub fn check_usage(
+        &self,
+        registry: &Handlebars,
+        sources: &Sources,
+        data: &serde_json::Value,
+    ) -> usize {
+        let Some(unused) = self.unused_keys(registry, sources, data) else {
+            warn!("Unable to report unused data keys, a template uses the whole data");
+            return 0;
+        };
+        for key in &unused {
+            warn!("Data key is never used: {:?}", key);
+        }
+        unused.len()
+    }
+
+    /// Convert a render error into an error located in the file of the
+    /// innermost template that failed, with the line in the file, followed by
+    /// the chain of partials including it from the rendered entries, the
+    /// innermost first. The `sources` map the names of the templates to their
+    /// files.
+    pub fn render_error(
+        &self,
+        registry: &Handlebars,
+        sources: &HashMap<String, PathBuf>,
+        err: RenderError,
+    ) -> ConfigError {
+        let offset = self.front_matter_lines();
+        let locate = |name: &str| match sources.get(name) {
+            Some(file) if self.template.as_ref() == Some(file) => {
+                (file.display().to_string(), offset)
+            }
+            Some(file) => (file.display().to_string(), 0),
+            None => (name.to_owned(), 0),
+        };
+        let template = err.template_name.as_deref().unwrap_or(self.entry());
+        let (file, offset) = locate(template);
+        let mut location = match (err.line_no, err.column_no) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", file, line + offset, column),
+            _ => file,
+        };
+        let entries = self.rendered_entries(registry);
+        let entries = entries.iter().map(String::as_str).collect::<Vec<_>>();
+        for (name, line) in references::include_chain(registry, &entries, template)
+            .into_iter()
+            .rev()
+        {
+            let (file, offset) = locate(&name);
+            location += &format!(", included from {}:{}", file, line + offset);
+        }
+
+        ConfigError::RenderError {
+            location,
+            reason: render_reason(&err),
+        }
+    }
+
+    /// Log each variable evaluated against the root of the data by the entry
+    /// templates that is neither in the data nor declared by the schema as an
+    /// error, once per file and line referencing it. The `sources` map the
+    /// names of the templates to their files and name the registered helpers,
+    /// which are not variables. Returns the number of problems found.
+    pub fn lint_variables(
+        &self,
+        registry: &Handlebars,
+        sources: &Sources,
+        data: &serde_json::Value,
+    ) -> usize {
+        let schema = match &self.schema {
+            Some(path) => match self.read_datafile(path) {
+                Ok(schema) => Some(schema),
+                Err((_, err)) => {
+                    error!("Unable to read schema: {:?}", path);
+                    error!("{}", err);
+                    return 1;
+                }
+            },
+            None => None,
+        };
+        let entries = self.rendered_entries(registry);
+        let entries = entries.iter().map(String::as_str).collect::<Vec<_>>();
+        let offset = self.front_matter_lines();
+        let mut missing = 0;
+
+        for (path, locations) in
+            references::root_path_locations(registry, &sources.helpers, &entries)
+        {
+            if Self::data_value(data, &path).is_some()
+                || schema
+                    .as_ref()
+                    .is_some_and(|schema| Self::schema_declares(schema, &path))
+            {
+                continue;
+            }
+            for (name, line) in locations {
+                let (file, line) = match sources.files.get(&name) {
+                    Some(file) if self.template.as_ref() == Some(file) => {
+                        (file.display().to_string(), line + offset)
+                    }
+                    Some(file) => (file.display().to_string(), line),
+                    None => (name, line),
+                };
+                error!(
+                    "Variable is missing in the data: {:?} at {}:{}",
+                    path, file, line
+                );
+                missing += 1;
+            }
+        }
+        missing
+    }
+
+    /// The names of the templates rendered: the templates of the output
+    /// directory if there is one, the entry templates otherwise.
+    pub fn rendered_entries(&self, registry: &Handlebars) -> Vec<String> {
+        if self.output_dir.is_some() {
+            self.output_dir_targets(registry)
+                .into_iter()
+                .map(|target| target.entry)
+                .collect()
+        } else {
+            self.entries().into_iter().map(str::to_owned).collect()
+        }
+    }
+
+    /// The sorted names of the registered templates that are not reachable
+    /// from the entry templates through partials with a literal name.
+    fn unused_templates<'a>(&self, registry: &'a Handlebars) -> Vec<&'a String> {
+        let entries = self.rendered_entries(registry);
+        let entries = entries.iter().map(String::as_str).collect::<Vec<_>>();
+        let used = references::used_templates(registry, &entries);
+        let mut unused = registry
+            .get_templates()
+            .keys()
+            .filter(|name| !used.contains(name.as_str()))
+            .collect::<Vec<_>>();
+        unused.sort();
+        unused
+    }
+
+    /// Insert the built-in values under the key `docfmt` unless they are
+    /// already defined by the data.
+    fn insert_builtins(&self, data: &mut serde_json::Value) -> std::fmt::Result {
+        let serde_json::Value::Object(root) = data else {
+            return Ok(());
+        };
+        let builtins = root
+            .entry(BUILTINS_KEY)
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::default()));
+        let serde_json::Value::Object(builtins) = builtins else {
+            return Ok(());
+        };
+        let now = chrono::Local::now();
+        let mut date = String::new();
+
+        write!(date, "{}", now.format(&self.date_format))?;
+        builtins
+            .entry("now")
+            .or_insert_with(|| now.to_rfc3339().into());
+        builtins.entry("date").or_insert_with(|| date.into());
+        Ok(())
+    }
+
+    /// Insert the environment variables under the key `env` unless they are
+    /// already defined by the data. If `env_vars` is not empty, only the listed
+    /// variables are inserted. Variables that are not valid UTF-8 are skipped.
+    fn insert_env(&self, data: &mut serde_json::Value) {
+        let serde_json::Value::Object(root) = data else {
+            return;
+        };
+        let env = root
+            .entry(ENV_KEY)
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::default()));
+        let serde_json::Value::Object(env) = env else {
+            return;
+        };
+        let vars = std::env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+            .filter(|(name, _)| self.env_vars.is_empty() || self.env_vars.contains(name))
+            .collect::<BTreeMap<_, _>>();
+
+        for (name, value) in vars {
+            env.entry(name).or_insert_with(|| value.into());
+        }
+    }
+
+    /// The configuration file used if `--config` is not given: the path in
+    /// `env`, the value of `DOCFMT_CONFIG`, if set and not empty, otherwise
+    /// `docfmt.toml` in `dir` if it exists.
+    fn default_config(env: Option<std::ffi::OsString>, dir: &Path) -> Option<PathBuf> {
+        match env {
+            Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+            _ => Some(dir.join(DEFAULT_CONFIG)).filter(|path| path.is_file()),
+        }
+    }
+
+    /// The first default data file present in `dir`.
+    fn default_data(dir: &Path) -> Option<PathBuf> {
+        DEFAULT_DATA
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    }
+}
+
+impl TryFrom<ArgMatches> for Config {
+    type Error = ConfigError;
+
+    fn try_from(matches: ArgMatches) -> Result<Self, Self::Error> {
+        let (mode, matches) = match matches.subcommand() {
+            Some(("validate", matches)) => (Mode::Validate, matches.to_owned()),
+            Some(("lint", matches)) => (Mode::Lint, matches.to_owned()),
+            Some(("graph", matches)) => {
+                let format = matches
+                    .get_one::<String>("format")
+                    .and_then(|name| GraphFormat::from_name(name))
+                    .unwrap_or_default();
+                (Mode::Graph(format), matches.to_owned())
+            }
+            _ => (Mode::Render, matches),
+        };
+        let paths = match matches.get_many::<PathBuf>("config") {
+            Some(paths) => paths.cloned().collect(),
+            None => Self::default_config(std::env::var_os(CONFIG_ENV), Path::new("."))
+                .into_iter()
+                .collect::<Vec<_>>(),
+        };
+        let mut config = ConfigRead::default();
+        for path in &paths {
+            config = ConfigRead::from_file(path)?.extend(config);
+        }
+        config.default_data_dir = paths
+            .last()
+            .map(|path| path.parent().unwrap_or(Path::new("")).to_owned());
+
+        config.mode = mode;
+        config.template = matches
+            .get_one::<PathBuf>("template")
+            .cloned()
+            .or(config.template);
+        config.entry = matches.get_one::<String>("entry").cloned().or(config.entry);
+        if mode == Mode::Lint {
+            config.schema = matches
+                .get_one::<PathBuf>("schema")
+                .cloned()
+                .or(config.schema);
+        }
+        if mode == Mode::Render {
+            if let Some(output) = matches.get_one::<PathBuf>("output") {
+                config.output = Some(output.to_owned());
+                config.output_template = None;
+            }
+            if let Some(template) = matches.get_one::<String>("output_template") {
+                config.output_template = Some(template.to_owned());
+                config.output_template_dir = None;
+                config.output = None;
+            }
+            config.each = matches.get_one::<String>("each").cloned().or(config.each);
+            config.concat.extend(
+                matches
+                    .get_many::<String>("concat")
+                    .unwrap_or_default()
+                    .cloned(),
+            );
+            config.separator = matches
+                .get_one::<String>("separator")
+                .cloned()
+                .or(config.separator);
+            if let Some(dir) = matches.get_one::<PathBuf>("output_dir") {
+                config.output_dir = Some(dir.to_owned());
+                config.output = None;
+                config.output_template = None;
+            }
+            config.output_pattern = matches
+                .get_one::<String>("output_pattern")
+                .cloned()
+                .or(config.output_pattern);
+            config.output_ext = matches
+                .get_one::<String>("output_ext")
+                .cloned()
+                .or(config.output_ext);
+            config.fail_fast = if matches.get_flag("fail_fast") {
+                true
+            } else {
+                config.fail_fast
+            };
+            if let Some(format) = matches.get_one::<String>("output_format") {
+                config.output_format = OutputFormat::from_name(format);
+            }
+            config.pretty = if matches.get_flag("pretty") {
+                true
+            } else {
+                config.pretty
+            };
+            if let Some(ending) = matches.get_one::<String>("line_ending") {
+                config.line_ending = LineEnding::from_name(ending);
+            }
+            config.collapse_blank_lines = if matches.get_flag("collapse_blank_lines") {
+                true
+            } else {
+                config.collapse_blank_lines
+            };
+            config.safe = if matches.get_flag("safe") {
+                true
+            } else {
+                config.safe
+            };
+            config.allow_overwrite_source = if matches.get_flag("allow_overwrite_source") {
+                true
+            } else {
+                config.allow_overwrite_source
+            };
+            config.append = if matches.get_flag("append") {
+                true
+            } else {
+                config.append
+            };
+            config.report_unused = if matches.get_flag("report_unused") {
+                true
+            } else {
+                config.report_unused
+            };
+            config.report_usage = if matches.get_flag("report_usage") {
+                true
+            } else {
+                config.report_usage
+            };
+            config.check_links = if matches.get_flag("check_links") {
+                true
+            } else {
+                config.check_links
+            };
+            #[cfg(feature = "http")]
+            {
+                config.check_remote_links = if matches.get_flag("check_remote_links") {
+                    true
+                } else {
+                    config.check_remote_links
+                };
+            }
+        }
+        config.force = if matches.get_flag("force") {
+            true
+        } else {
+            config.force
+        };
+        #[cfg(unix)]
+        {
+            config.follow = if matches.get_flag("follow") {
+                true
+            } else {
+                config.follow
+            };
+        }
+        #[cfg(not(unix))]
+        {
+            config.follow = false;
+            config.follow_links.clear();
+        }
+        config.strict = if matches.get_flag("strict") {
+            true
+        } else {
+            config.strict
+        };
+        config.print_templates = matches.get_flag("print_templates");
+        config.dump_data = matches.get_flag("dump_data");
+        config.strict_data = if matches.get_flag("strict_data") {
+            true
+        } else {
+            config.strict_data
+        };
+        config.require_data = if matches.get_flag("require_data") {
+            true
+        } else {
+            config.require_data
+        };
+        config.sidecar_data = if matches.get_flag("sidecar_data") {
+            true
+        } else {
+            config.sidecar_data
+        };
+        config.strict_undefined_partials = if matches.get_flag("strict_undefined_partials") {
+            true
+        } else {
+            config.strict_undefined_partials
+        };
+        config.fail_on_warn = if matches.get_flag("fail_on_warn") {
+            true
+        } else {
+            config.fail_on_warn
+        };
+        let verbose = matches.get_count("verbose");
+        let quiet = matches.get_flag("quiet");
+        if verbose > 0 || quiet {
+            config.verbose = false;
+            config.verbosity = verbose;
+            config.quiet = quiet;
+        }
+        if let Some(format) = matches.get_one::<String>("log_format") {
+            config.log_format = LogFormat::from_name(format);
+        }
+        config.include.extend(
+            matches
+                .get_many::<PathBuf>("include")
+                .unwrap_or_default()
+                .map(PathBuf::from),
+        );
+        config.no_default_extensions = if matches.get_flag("no_default_extensions") {
+            true
+        } else {
+            config.no_default_extensions
+        };
+        let is_default = matches.value_source("extension") == Some(ValueSource::DefaultValue);
+        if !(is_default && config.no_default_extensions) {
+            config.extensions.extend(
+                matches
+                    .get_many::<String>("extension")
+                    .unwrap_or_default()
+                    .map(String::from),
+            );
+        }
+        config.partial_extensions.extend(
+            matches
+                .get_many::<String>("partial_extension")
+                .unwrap_or_default()
+                .map(String::from),
+        );
+        config.datafiles.extend(
+            matches
+                .get_many::<PathBuf>("data")
+                .unwrap_or_default()
+                .map(PathBuf::from),
+        );
+        config.secrets.extend(
+            matches
+                .get_many::<PathBuf>("secrets")
+                .unwrap_or_default()
+                .map(PathBuf::from),
+        );
+        config.sensitive.extend(
+            matches
+                .get_many::<String>("sensitive")
+                .unwrap_or_default()
+                .cloned(),
+        );
+        if let Some(format) = matches.get_one::<String>("data_format") {
+            config.data_format = DataFormat::from_extension(format);
+        }
+        config.profile = matches
+            .get_one::<String>("profile")
+            .cloned()
+            .or(config.profile);
+        config.data_commands.extend(
+            matches
+                .get_many::<String>("data_cmd")
+                .unwrap_or_default()
+                .cloned(),
+        );
+        config.raw_dir = matches
+            .get_one::<PathBuf>("raw_dir")
+            .cloned()
+            .or(config.raw_dir);
+        config.root = matches.get_one::<PathBuf>("root").cloned().or(config.root);
+        config.jobs = matches
+            .get_one::<NonZeroUsize>("jobs")
+            .copied()
+            .or(config.jobs);
+        config.timeout = matches
+            .get_one::<NonZeroU64>("timeout")
+            .copied()
+            .or(config.timeout);
+        #[cfg(feature = "http")]
+        {
+            config.http_timeout = matches
+                .get_one::<NonZeroU64>("http_timeout")
+                .copied()
+                .or(config.http_timeout);
+        }
+        config.max_depth = matches
+            .get_one::<NonZeroUsize>("max_depth")
+            .copied()
+            .or(config.max_depth);
+        config.max_templates = matches
+            .get_one::<NonZeroUsize>("max_templates")
+            .copied()
+            .or(config.max_templates);
+        if let Some(strategy) = matches.get_one::<String>("merge_arrays") {
+            config.merge_arrays = MergeStrategy::from_name(strategy);
+        }
+        config.ignore_file = matches
+            .get_one::<PathBuf>("ignore_file")
+            .cloned()
+            .or(config.ignore_file);
+        config.report = matches
+            .get_one::<PathBuf>("report")
+            .cloned()
+            .or(config.report);
+        if matches.get_flag("no_builtins") {
+            config.builtins = Some(false);
+        }
+        if matches.get_flag("no_default_data") {
+            config.default_data = Some(false);
+        }
+        config.env = if matches.get_flag("env") {
+            true
+        } else {
+            config.env
+        };
+        if matches.get_flag("no_escape") {
+            config.html_escape = Some(false);
+        }
+        config.date_format = matches
+            .get_one::<String>("date_format")
+            .cloned()
+            .or(config.date_format);
+        config.locale = matches
+            .get_one::<String>("locale")
+            .cloned()
+            .or(config.locale);
+        config.fallback_locale = matches
+            .get_one::<String>("fallback_locale")
+            .cloned()
+            .or(config.fallback_locale);
+        #[cfg(feature = "scripting")]
+        config.helpers.extend(
+            matches
+                .get_many::<PathBuf>("helpers")
+                .unwrap_or_default()
+                .map(PathBuf::from),
+        );
+
+        Self::try_from(config)
+    }
+}
+
+impl TryFrom<ConfigRead> for Config {
+    type Error = ConfigError;
+
+    fn try_from(config: ConfigRead) -> Result<Self, Self::Error> {
+        let stdin = Path::new(STDIN);
+        let stdin_data = config
+            .datafiles
+            .iter()
+            .chain(&config.secrets)
+            .filter(|path| *path == stdin)
+            .count();
+
+        if stdin_data > 1 || (stdin_data == 1 && config.template.as_deref() == Some(stdin)) {
+            return Err(ConfigError::StdinConflict);
+        }
+        if stdin_data == 1 && config.data_format.is_none() {
+            return Err(ConfigError::MissingDataFormat);
+        }
+        if !config.render.is_empty()
+            && (config.output.is_some() || config.output_template.is_some())
+        {
+            return Err(ConfigError::RenderConflict);
+        }
+        if !config.concat.is_empty()
+            && (!config.render.is_empty() || config.output_dir.is_some() || config.each.is_some())
+        {
+            return Err(ConfigError::ConcatConflict);
+        }
+        if config.output_dir.is_some()
+            && (config.output.is_some()
+                || config.output_template.is_some()
+                || config.each.is_some()
+                || !config.render.is_empty())
+        {
+            return Err(ConfigError::OutputDirConflict);
+        }
+        if let Some(key) = config.sensitive.iter().find(|key| !key.starts_with('/')) {
+            return Err(ConfigError::InvalidSensitive(key.to_owned()));
+        }
+        // Templates rendered in batches and printing the template names or
+        // the data need neither a main template nor an output file.
+        let batch = !config.render.is_empty()
+            || config.output_dir.is_some()
+            || config.print_templates
+            || config.dump_data;
+        Ok(Config {
+            template: match (config.template, &config.entry) {
+                (None, None) if !batch && config.concat.is_empty() => {
+                    return Err(ConfigError::MissingTemplate)
+                }
+                (template, _) => template,
+            },
+            entry: config.entry,
+            output: match (config.mode, &config.output_template) {
+                (Mode::Render, None) if !batch => {
+                    config.output.ok_or(ConfigError::MissingOutput)?
+                }
+                _ => config.output.unwrap_or_default(),
+            },
+            each: match (&config.each, &config.output_template) {
+                (Some(_), None) if config.mode == Mode::Render => {
+                    return Err(ConfigError::MissingOutputTemplate)
+                }
+                _ => config.each,
+            },
+            output_template: config.output_template,
+            output_template_dir: config.output_template_dir,
+            output_dir: config.output_dir,
+            output_pattern: config
+                .output_pattern
+                .map(|pattern| {
+                    glob::Pattern::new(&pattern).map_err(|_| ConfigError::InvalidPattern(pattern))
+                })
+                .transpose()?,
+            output_ext: config
+                .output_ext
+                .unwrap_or_else(|| DEFAULT_OUTPUT_EXT.to_owned()),
+            render: config.render,
+            concat: config.concat,
+            separator: config.separator.unwrap_or_default(),
+            fail_fast: config.fail_fast,
+            output_format: config.output_format.unwrap_or_default(),
+            pretty: config.pretty,
+            line_ending: config.line_ending,
+            collapse_blank_lines: config.collapse_blank_lines,
+            safe: config.safe,
+            allow_overwrite_source: config.allow_overwrite_source,
+            append: config.append,
+            report_unused: config.report_unused,
+            report_usage: config.report_usage,
+            check_links: config.check_links,
+            check_remote_links: config.check_remote_links,
+            force: config.force,
+            follow: config.follow,
+            follow_links: config.follow_links,
+            include_when: config
+                .include_when
+                .into_iter()
+                .map(|(include, condition)| match Condition::parse(&condition) {
+                    Some(condition) => Ok((include, condition)),
+                    None => Err(ConfigError::InvalidCondition(condition)),
+                })
+                .collect::<Result<_, _>>()?,
+            strict: config.strict,
+            strict_data: config.strict_data,
+            require_data: config.require_data,
+            sidecar_data: config.sidecar_data,
+            strict_undefined_partials: config.strict_undefined_partials,
+            fail_on_warn: config.fail_on_warn,
+            verbose: config.verbosity.max(config.verbose as u8),
+            quiet: config.quiet,
+            log_format: config.log_format.unwrap_or_default(),
+            include: config.include,
+            extensions: config.extensions,
+            partial_extensions: config.partial_extensions,
+            datafiles: config.datafiles,
+            secrets: config.secrets,
+            sensitive: config.sensitive,
+            data_format: config.data_format,
+            profile: config.profile,
+            data_formats: config.data_formats,
+            data_commands: config
+                .data_commands
+                .iter()
+                .map(|value| {
+                    DataCommand::parse(value)
+                        .ok_or_else(|| ConfigError::InvalidDataCommand(value.to_owned()))
+                })
+                .collect::<Result<_, _>>()?,
+            helpers: config.helpers,
+            raw_dir: config.raw_dir.unwrap_or_else(|| PathBuf::from(".")),
+            root: config.root,
+            jobs: config.jobs,
+            timeout: config.timeout,
+            #[cfg(feature = "http")]
+            http_timeout: config.http_timeout,
+            max_depth: config.max_depth,
+            max_templates: config.max_templates,
+            merge_arrays: config.merge_arrays.unwrap_or_default(),
+            ignore_file: config.ignore_file,
+            report: config.report,
+            schema: config.schema,
+            builtins: config.builtins.unwrap_or(true),
+            default_data: match config.default_data {
+                Some(false) => None,
+                _ => {
+                    Self::default_data(config.default_data_dir.as_deref().unwrap_or(Path::new("")))
+                }
+            },
+            env: config.env,
+            env_vars: config.env_vars,
+            html_escape: config.html_escape.unwrap_or(true),
+            date_format: config
+                .date_format
+                .unwrap_or_else(|| DEFAULT_DATE_FORMAT.to_owned()),
+            locale: config.locale,
+            fallback_locale: config.fallback_locale,
+            data: config.data.map_or(
+                serde_json::Value::Object(serde_json::Map::default()),
+                data::from_toml,
+            ),
+            computed: config
+                .computed
+                .into_iter()
+                .map(|(key, value)| match value {
+                    serde_json::Value::String(template) => Ok((key, template)),
+                    _ => Err(ConfigError::InvalidComputed(key)),
+                })
+                .collect::<Result<_, _>>()?,
+            mode: config.mode,
+            print_templates: config.print_templates,
+            dump_data: config.dump_data,
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigError {
+    #[error("Unable to read config file: {0}")]
+    ConfigFileReadError(std::io::Error),
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(toml::de::Error),
+    #[error("Invalid configuration: {0}")]
+    InvalidJsonConfig(serde_json::Error),
+    #[error("Invalid configuration: {0}")]
+    InvalidJson5Config(json5::Error),
+    #[error("Configuration extends itself: {0:?}")]
+    ExtendsCycle(PathBuf),
+    #[error("Unsupported config file format: {0:?} (supported are .toml, .json and .json5)")]
+    UnsupportedConfigFormat(PathBuf),
+    #[error("Missing template file")]
+    MissingTemplate,
+    #[error("Missing output file")]
+    MissingOutput,
+    #[error("Missing output template, required to render each item")]
+    MissingOutputTemplate,
+    #[error("Missing data format, required to read data from stdin")]
+    MissingDataFormat,
+    #[error("Only one of the template and the data files can be read from stdin")]
+    StdinConflict,
+    #[error("Render targets can not be combined with an output file or output template")]
+    RenderConflict,
+    #[error("An output directory can not be combined with an output file, output template, each or render targets")]
+    OutputDirConflict,
+    #[error(
+        "Concatenated entries can not be combined with render targets, an output directory or each"
+    )]
+    ConcatConflict,
+    #[error("Invalid template name pattern: {0:?}")]
+    InvalidPattern(String),
+    #[error("Computed value is not a template string: {0:?}")]
+    InvalidComputed(String),
+    #[error("Invalid data command, expected `<command>:<format>`: {0:?}")]
+    InvalidDataCommand(String),
+    #[error("Invalid include condition, expected `<path>`, `<path> == <value>` or `<path> != <value>`: {0:?}")]
+    InvalidCondition(String),
+    #[error("Invalid sensitive data key, expected a JSON pointer like `/db/password`: {0:?}")]
+    InvalidSensitive(String),
+    #[error("Unable to read template: {0}")]
+    TemplateError(#[from] TemplateError),
+    #[error("Render failed in {location}: {reason}")]
+    RenderError { location: String, reason: String },
+}
+
+impl ConfigError {
+    /// A stable code of the error for tools parsing the log, independent of
+    /// the message, e.g. `MISSING_TEMPLATE`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConfigError::ConfigFileReadError(_) => "CONFIG_FILE_UNREADABLE",
+            ConfigError::InvalidConfig(_)
+            | ConfigError::InvalidJsonConfig(_)
+            | ConfigError::InvalidJson5Config(_) => "INVALID_CONFIG",
+            ConfigError::ExtendsCycle(_) => "EXTENDS_CYCLE",
+            ConfigError::UnsupportedConfigFormat(_) => "UNSUPPORTED_CONFIG_FORMAT",
+            ConfigError::MissingTemplate => "MISSING_TEMPLATE",
+            ConfigError::MissingOutput => "MISSING_OUTPUT",
+            ConfigError::MissingOutputTemplate => "MISSING_OUTPUT_TEMPLATE",
+            ConfigError::MissingDataFormat => "MISSING_DATA_FORMAT",
+            ConfigError::StdinConflict => "STDIN_CONFLICT",
+            ConfigError::RenderConflict => "RENDER_CONFLICT",
+            ConfigError::OutputDirConflict => "OUTPUT_DIR_CONFLICT",
+            ConfigError::ConcatConflict => "CONCAT_CONFLICT",
+            ConfigError::InvalidPattern(_) => "INVALID_PATTERN",
+            ConfigError::InvalidComputed(_) => "INVALID_COMPUTED",
+            ConfigError::InvalidDataCommand(_) => "INVALID_DATA_COMMAND",
+            ConfigError::InvalidCondition(_) => "INVALID_CONDITION",
+            ConfigError::InvalidSensitive(_) => "INVALID_SENSITIVE",
+            ConfigError::TemplateError(_) => "TEMPLATE_ERROR",
+            ConfigError::RenderError { .. } => "RENDER_ERROR",
+        }
+    }
+}
+
+/// The reason of a render error, naming the missing variable in strict mode.
+fn render_reason(err: &RenderError) -> String {
+    match err.reason() {
+        RenderErrorReason::MissingVariable(Some(path)) => {
+            format!("Variable {:?} not found in strict mode", path)
+        }
+        reason => reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn create_registry() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            output: PathBuf::from("tests/output/main.md"),
+            force: false,
+            follow: false,
+            strict: false,
+            verbose: 0,
+            include: vec![
+                PathBuf::from("tests/templates/input1"),
+                PathBuf::from("tests/templates/input2"),
+                PathBuf::from("tests/templates/file.hbs"),
+            ],
+            extensions: vec!["hbs".into(), "md".into()],
+            datafiles: vec![],
+            data: serde_json::Value::Object(serde_json::Map::default()),
+            ..Default::default()
+        };
+
+        let registry = config.new_registry();
+        assert!(registry.is_ok());
+        let registry = registry.unwrap();
+
+        assert!(registry.get_template("main").is_some());
+        assert!(registry.get_template("input1/file").is_some());
+        assert!(registry.get_template("input1/.hidden").is_none());
+        assert!(registry.get_template("input1/subdir/file").is_some());
+        assert!(registry.get_template("input2/file").is_some());
+        assert!(registry.get_template("input2/.hidden").is_none());
+        assert!(registry.get_template("file").is_some());
+
+        let data = config.read_data();
+        assert!(data.is_some());
+        let data = data.unwrap();
+
+        let content = registry.render("main", &data);
+        assert!(content.is_ok());
+        let content = content.unwrap();
+
+        assert_eq!(content, "Hello World!\nGoodbye!\nFor now!");
+    }
+
+    #[test]
+    fn layout() {
+        let config = Config::from_toml_str(concat!(
+            "template = \"tests/templates/layout/page.hbs\"\n",
+            "output = \"out.md\"\n",
+            "include = [\"tests/templates/layout/layouts\"]\n",
+            "extensions = [\"hbs\"]\n",
+            "strict = true\n",
+        ))
+        .unwrap();
+        let registry = config.new_registry().unwrap();
+        let data = json!({"title": "Docs", "author": "Jane"});
+        let content = registry.render(config.entry(), &data).unwrap();
+        assert!(content.starts_with("# Docs\n"));
+        assert!(content.contains("Welcome to Docs."));
+        assert!(!content.contains("No content."));
+        assert!(content.contains("Written by Jane."));
+
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/layout/bare.hbs")),
+            ..config
+        };
+        let registry = config.new_registry().unwrap();
+        let content = registry.render(config.entry(), &data).unwrap();
+        assert_eq!(content, "# Docs\n\nNo content.\n\nBye.\n");
+    }
+
+    #[test]
+    fn name_collision() {
+        let config = Config::from_json_str(
+            r#"{
+                "template": "tests/templates/main.hbs",
+                "output": "out.md",
+                "include": ["tests/templates/collision"],
+                "extensions": ["hbs", "md"]
+            }"#,
+        )
+        .unwrap();
+        let registry = config.new_registry().unwrap();
+        let content = registry.render("collision/page", &json!({}));
+        assert_eq!(content.unwrap(), "Page md");
+
+        let config = Config {
+            strict: true,
+            ..config
+        };
+        assert!(config.new_registry().is_err());
+
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            strict: true,
+            include: vec![
+                PathBuf::from("tests/templates/file.hbs"),
+                PathBuf::from("tests/templates/input1/file.hbs"),
+            ],
+            ..Default::default()
+        };
+        assert!(config.new_registry().is_err());
+    }
+
+    #[test]
+    fn template_name() {
+        let name = Config::template_name(None, Path::new("input1/subdir/file.hbs"));
+        assert_eq!(name.as_deref(), Some("input1/subdir/file"));
+        let name = Config::template_name(None, Path::new("file.tar.hbs"));
+        assert_eq!(name.as_deref(), Some("file.tar"));
+        let name = Config::template_name(Some("partials"), Path::new("sub/file.hbs"));
+        assert_eq!(name.as_deref(), Some("partials/sub/file"));
+        let name = Config::template_name(Some(""), Path::new("file.hbs"));
+        assert_eq!(name.as_deref(), Some("file"));
+
+        #[cfg(windows)]
+        {
+            let name = Config::template_name(None, Path::new(r"input1\subdir\file.hbs"));
+            assert_eq!(name.as_deref(), Some("input1/subdir/file"));
+        }
+        #[cfg(unix)]
+        {
+            use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+            let path = Path::new(OsStr::from_bytes(b"input1/\xff.hbs"));
+            assert_eq!(Config::template_name(None, path), None);
+        }
+    }
+
+    #[test]
+    fn entry() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            entry: Some("index".to_owned()),
+            include: vec![PathBuf::from("tests/templates/input1")],
+            extensions: vec!["hbs".into()],
+            ..Default::default()
+        };
+        let registry = config.new_registry().unwrap();
+        assert!(registry.get_template("index").is_some());
+        assert!(registry.get_template("main").is_none());
+
+        let config = Config {
+            template: None,
+            entry: Some("input1/file".to_owned()),
+            ..config
+        };
+        let registry = config.new_registry().unwrap();
+        let content = registry.render(config.entry(), &json!({}));
+        assert_eq!(content.unwrap(), "World!");
+
+        let config = Config {
+            entry: Some("missing".to_owned()),
+            ..config
+        };
+        assert!(config.new_registry().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loop() {
+        let root = std::env::temp_dir().join(format!("docfmt-loop-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("dir")).unwrap();
+        std::fs::write(root.join("dir/page.hbs"), "Page").unwrap();
+        std::os::unix::fs::symlink(root.join("dir"), root.join("dir/loop")).unwrap();
+
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            include: vec![root.join("dir")],
+            extensions: vec!["hbs".into()],
+            ..Default::default()
+        };
+        assert!(config.new_registry().is_ok());
+
+        let config = Config {
+            follow: true,
+            ..config
+        };
+        assert!(config.new_registry().is_err());
+
+        let config = Config {
+            follow_links: BTreeMap::from([(root.join("dir"), false)]),
+            ..config
+        };
+        assert!(config.new_registry().is_ok());
+
+        let config = Config {
+            follow: false,
+            follow_links: BTreeMap::from([(
+                PathBuf::from(format!("loop:{}", root.join("dir").display())),
+                true,
+            )]),
+            include: vec![PathBuf::from(format!(
+                "loop:{}",
+                root.join("dir").display()
+            ))],
+            ..config
+        };
+        assert!(config.new_registry().is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn name_prefix() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            include: vec![
+                PathBuf::from("partials:tests/templates/input1"),
+                PathBuf::from(":tests/templates/input2"),
+                PathBuf::from("single:tests/templates/file.hbs"),
+            ],
+            extensions: vec!["hbs".into(), "md".into()],
+            ..Default::default()
+        };
+        let registry = config.new_registry().unwrap();
+        assert!(registry.get_template("partials/file").is_some());
+        assert!(registry.get_template("partials/subdir/file").is_some());
+        assert!(registry.get_template("file").is_some());
+        assert!(registry.get_template("single/file").is_some());
+        assert!(registry.get_template("input1/file").is_none());
+    }
+
+    #[test]
+    fn name_root() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            include: vec![PathBuf::from("./tests/templates/input1")],
+            extensions: vec!["hbs".into()],
+            root: Some(PathBuf::from("tests/templates/input1")),
+            ..Default::default()
+        };
+        let registry = config.new_registry().unwrap();
+        assert!(registry.get_template("file").is_some());
+        assert!(registry.get_template("subdir/file").is_some());
+
+        let config = Config {
+            root: Some(PathBuf::from(".")),
+            ..config
+        };
+        let registry = config.new_registry().unwrap();
+        assert!(registry
+            .get_template("tests/templates/input1/file")
+            .is_some());
+
+        let config = Config {
+            root: Some(PathBuf::from("tests/data")),
+            ..config
+        };
+        assert!(config.new_registry().is_err());
+    }
+
+    #[test]
+    fn partial_extensions() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            include: vec![
+                PathBuf::from("tests/templates/input1"),
+                PathBuf::from("tests/templates/input2"),
+            ],
+            extensions: vec!["md".into()],
+            ..Default::default()
+        };
+        let registry = config.new_registry().unwrap();
+        assert!(registry.get_template("input1/file").is_none());
+        assert!(registry.get_template("input2/file").is_some());
+
+        let config = Config {
+            partial_extensions: vec!["hbs".into()],
+            ..config
+        };
+        let registry = config.new_registry().unwrap();
+        assert!(registry.get_template("input1/file").is_some());
+        assert!(registry.get_template("input1/subdir/file").is_some());
+        assert!(registry.get_template("input2/file").is_some());
+    }
+
+    #[test]
+    fn registry_report() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            include: vec![
+                PathBuf::from("tests/templates/input1"),
+                PathBuf::from("tests/templates/missing.hbs"),
+            ],
+            ignore_file: Some(PathBuf::from("tests/templates/missing.ignore")),
+            extensions: vec!["hbs".into()],
+            ..Default::default()
+        };
+        let report = config.new_registry().unwrap_err();
+        assert_eq!(report.registered, 3);
+        assert_eq!(
+            report.failed,
+            vec![PathBuf::from("tests/templates/missing.ignore")]
+        );
+    }
+
+    #[test]
+    fn ignore_file() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            include: vec![PathBuf::from("tests/templates/ignored")],
+            extensions: vec!["hbs".into()],
+            ..Default::default()
+        };
+
+        let registry = config.new_registry();
+        assert!(registry.is_ok());
+        let registry = registry.unwrap();
+
+        assert!(registry.get_template("ignored/page").is_some());
+        assert!(registry.get_template("ignored/draft").is_none());
+        assert!(registry.get_template("ignored/fixtures/data").is_none());
+        assert!(registry.get_template("ignored/fixtures/keep").is_some());
+
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            include: vec![PathBuf::from("tests/templates/ignored")],
+            extensions: vec!["hbs".into()],
+            ignore_file: Some(PathBuf::from("tests/templates/page.ignore")),
+            ..Default::default()
+        };
+
+        let registry = config.new_registry().unwrap();
+        assert!(registry.get_template("ignored/page").is_none());
+        assert!(registry.get_template("ignored/fixtures/keep").is_some());
+    }
+
+    #[test]
+    fn read_data() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            output: PathBuf::from("tests/output/main.md"),
+            force: false,
+            follow: false,
+            strict: false,
+            verbose: 0,
+            include: vec![],
+            extensions: vec![],
+            datafiles: vec![
+                PathBuf::from("tests/data/data1.toml"),
+                PathBuf::from("tests/data/data2.json"),
+            ],
+            data: serde_json::Value::Object(serde_json::Map::default()),
+            ..Default::default()
+        };
+
+        let data = config.read_data();
+        assert!(data.is_some());
+        let data = data.unwrap();
+        let expected = json!({
+            "cities": [
+              "colombo"
+            ],
+            "person": {
+              "firstName": "Jane"
+            },
+            "title": "This is another title"
+        });
+
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn toml_datetimes() {
+        let matches =
+            crate::cli::get_cli().get_matches_from(["docfmt", "-c", "tests/config/dates.toml"]);
+        let config = Config::try_from(matches).unwrap();
+        let data = config.read_data().unwrap();
+        let registry = config.new_registry_for(&data).unwrap();
+        assert_eq!(
+            registry.render(config.entry(), &data).unwrap(),
+            concat!(
+                "Released 2024-04-04T12:00:00+02:00 (2024-04-04), ",
+                "updated 2024-05-01T08:30:00, reviewed 07:45:00\n"
+            )
+        );
+    }
+
+    #[test]
+    fn config_file_formats() {
+        let toml = ConfigRead::from_file(Path::new("tests/templates/config.toml"));
+        assert!(toml.is_ok());
+        let json = ConfigRead::from_file(Path::new("tests/templates/config.json"));
+        assert!(json.is_ok());
+        let json = json.unwrap();
+        assert_eq!(
+            json.template,
+            Some(PathBuf::from("tests/templates/<path to template>"))
+        );
+        assert_eq!(
+            json.extensions,
+            vec!["md".to_owned(), "markdown".to_owned()]
+        );
+
+        let json5 = ConfigRead::from_file(Path::new("tests/templates/config.json5"));
+        let json5 = json5.unwrap();
+        assert_eq!(
+            json5.template,
+            Some(PathBuf::from("tests/templates/<path to template>"))
+        );
+        assert_eq!(
+            json5.extensions,
+            vec!["md".to_owned(), "markdown".to_owned()]
+        );
+
+        let other = ConfigRead::from_file(Path::new("tests/data/data1.toml"));
+        assert!(other.is_ok());
+        let other = ConfigRead::from_file(Path::new("tests/templates/main.hbs"));
+        assert!(matches!(
+            other,
+            Err(ConfigError::UnsupportedConfigFormat(_))
+        ));
+    }
+
+    #[test]
+    fn config_extends() {
+        let config = ConfigRead::from_file(Path::new("tests/config/child.toml")).unwrap();
+        assert_eq!(
+            config.template,
+            Some(PathBuf::from("tests/config/child.hbs"))
+        );
+        assert_eq!(config.output, Some(PathBuf::from("tests/config/base.md")));
+        assert!(config.strict);
+        assert_eq!(
+            config.include,
+            vec![
+                PathBuf::from("tests/config/base"),
+                PathBuf::from("tests/config/child")
+            ]
+        );
+        let data = serde_json::to_value(config.data.unwrap()).unwrap();
+        assert_eq!(
+            data,
+            json!({"title": "Child", "author": {"name": "Jane", "mail": "jane@example.com"}})
+        );
+
+        let config = ConfigRead::from_file(Path::new("tests/config/cycle.toml"));
+        assert!(matches!(config, Err(ConfigError::ExtendsCycle(_))));
+    }
+
+    #[test]
+    fn config_from_str() {
+        let config = Config::from_toml_str(
+            "template = \"tests/templates/main.hbs\"\noutput = \"out.md\"\nstrict = true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.template,
+            Some(PathBuf::from("tests/templates/main.hbs"))
+        );
+        assert_eq!(config.output, PathBuf::from("out.md"));
+        assert!(config.strict);
+
+        let config = Config::from_json_str(
+            r#"{"extends": "tests/config/base.toml", "template": "main.hbs"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.template, Some(PathBuf::from("main.hbs")));
+        assert_eq!(config.output, PathBuf::from("tests/config/base.md"));
+
+        assert!(matches!(
+            Config::from_toml_str("output = \"out.md\""),
+            Err(ConfigError::MissingTemplate)
+        ));
+        assert!(matches!(
+            Config::from_toml_str("template = "),
+            Err(ConfigError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            Config::from_json_str("{"),
+            Err(ConfigError::InvalidJsonConfig(_))
+        ));
+    }
+
+    #[test]
+    fn config_relative_paths() {
+        let root = std::env::temp_dir().join(format!("docfmt-relative-{}", std::process::id()));
+        let site = root.join("site");
+        std::fs::create_dir_all(site.join("parts")).unwrap();
+        std::fs::write(site.join("main.hbs"), "{{> parts/intro}} {{meta.version}}").unwrap();
+        std::fs::write(site.join("parts/intro.hbs"), "{{name}}").unwrap();
+        std::fs::write(site.join("data.toml"), "name = \"Site\"\nversion = 2").unwrap();
+        std::fs::write(
+            site.join("docfmt.toml"),
+            concat!(
+                "template = \"main.hbs\"\n",
+                "output = \"out/README.md\"\n",
+                "include = [\"parts:parts\"]\n",
+                "datafiles = [\"data.toml\", \"data.toml@/meta\"]\n",
+                "extensions = [\"hbs\"]\n",
+                "[follow_links]\n",
+                "\"parts:parts\" = true\n",
+            ),
+        )
+        .unwrap();
+
+        let config = Config::try_from(crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "-c",
+            site.join("docfmt.toml").to_str().unwrap(),
+            "-d",
+            "tests/data/data1.toml",
+        ]))
+        .unwrap();
+        assert_eq!(config.output, site.join("out/README.md"));
+        assert_eq!(config.datafiles[2], PathBuf::from("tests/data/data1.toml"));
+        assert_eq!(
+            config.follow_links.keys().collect::<Vec<_>>(),
+            [&PathBuf::from(format!(
+                "parts:{}",
+                site.join("parts").display()
+            ))]
+        );
+        let data = config.read_data().unwrap();
+        let registry = config.new_registry().unwrap();
+        assert_eq!(registry.render("main", &data).unwrap(), "Site 2");
+
+        std::fs::write(
+            site.join("cwd.toml"),
+            "relative_to = \"cwd\"\ntemplate = \"main.hbs\"\n",
+        )
+        .unwrap();
+        let config = ConfigRead::from_file(&site.join("cwd.toml")).unwrap();
+        assert_eq!(config.template, Some(PathBuf::from("main.hbs")));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn default_config() {
+        let root = std::env::temp_dir().join(format!("docfmt-default-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        assert_eq!(Config::default_config(None, &root), None);
+        assert_eq!(Config::default_config(Some("".into()), &root), None);
+        std::fs::write(root.join(DEFAULT_CONFIG), "").unwrap();
+        assert_eq!(
+            Config::default_config(None, &root),
+            Some(root.join(DEFAULT_CONFIG))
+        );
+        assert_eq!(
+            Config::default_config(Some("ci.toml".into()), &root),
+            Some(PathBuf::from("ci.toml"))
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn default_data() {
+        let root = std::env::temp_dir().join(format!("docfmt-default-data-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("main.hbs"), "").unwrap();
+        std::fs::write(root.join("data.json"), r#"{"title": "Explicit"}"#).unwrap();
+        std::fs::write(
+            root.join(DEFAULT_CONFIG),
+            "template = \"main.hbs\"\noutput = \"out.md\"\ndatafiles = [\"data.json\"]\n\n[data]\nversion = \"1.0\"\n",
+        )
+        .unwrap();
+        let path = root.join(DEFAULT_CONFIG);
+        let config = |flag: &str| {
+            let matches = crate::cli::get_cli().get_matches_from([
+                "docfmt",
+                flag,
+                "-c",
+                path.to_str().unwrap(),
+            ]);
+            Config::try_from(matches).unwrap()
+        };
+
+        assert_eq!(config("--no-builtins").default_data, None);
+        std::fs::write(root.join("docfmt.data.json"), r#"{"title": "JSON"}"#).unwrap();
+        std::fs::write(
+            root.join("docfmt.data.toml"),
+            "title = \"Default\"\nversion = \"0.1\"\nauthor = \"Jane\"\n",
+        )
+        .unwrap();
+        assert_eq!(config("--no-default-data").default_data, None);
+        let config = config("--no-builtins");
+        assert_eq!(config.default_data, Some(root.join("docfmt.data.toml")));
+        assert_eq!(config.datafiles()[0], root.join("docfmt.data.toml"));
+        assert_eq!(
+            config.read_data().unwrap(),
+            json!({"title": "Explicit", "version": "1.0", "author": "Jane"})
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn multiple_configs() {
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "-c",
+            "tests/config/base.toml",
+            "-c",
+            "tests/config/override.toml",
+            "-i",
+            "cli",
+        ]);
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(
+            config.template,
+            Some(PathBuf::from("tests/config/base.hbs"))
+        );
+        assert_eq!(config.output, PathBuf::from("tests/config/override.md"));
+        assert_eq!(
+            config.include,
+            vec![
+                PathBuf::from("tests/config/base"),
+                PathBuf::from("tests/config/override"),
+                PathBuf::from("cli")
+            ]
+        );
+        assert_eq!(config.data["title"], "Override");
+    }
+
+    #[test]
+    fn render_targets() {
+        let matches =
+            crate::cli::get_cli().get_matches_from(["docfmt", "-c", "tests/config/render.toml"]);
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(config.template, None);
+        assert_eq!(config.entries(), ["input1/file", "input1/subdir/file"]);
+        assert_eq!(
+            config.render()[1].output,
+            PathBuf::from("tests/config/subdir.md")
+        );
+        let registry = config.new_registry().unwrap();
+        let content = registry.render(&config.render()[1].entry, &json!({}));
+        assert_eq!(content.unwrap(), "Hello");
+
+        let config = Config {
+            render: vec![RenderTarget {
+                entry: "missing".to_owned(),
+                output: PathBuf::from("missing.md"),
+            }],
+            ..config
+        };
+        assert!(config.new_registry().is_err());
+
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "-c",
+            "tests/config/render.toml",
+            "main.hbs",
+            "out.md",
+        ]);
+        assert!(matches!(
+            Config::try_from(matches),
+            Err(ConfigError::RenderConflict)
+        ));
+    }
+
+    #[test]
+    fn concat_outputs() {
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "--include",
+            "tests/templates/input1",
+            "--ext",
+            "hbs",
+            "--output-template",
+            "out.md",
+            "--concat",
+            "input1/subdir/file",
+            "--concat",
+            "input1/file",
+            "--separator",
+            "---",
+        ]);
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(config.template, None);
+        assert_eq!(config.entries(), ["input1/subdir/file", "input1/file"]);
+        let registry = config.new_registry().unwrap();
+        let outputs = config
+            .entries()
+            .iter()
+            .map(|entry| registry.render(entry, &json!({})).unwrap())
+            .collect();
+        assert_eq!(config.join_outputs(outputs), "Hello\n---\nWorld!");
+
+        let outputs = ["a\n", " \n", "", "b"].map(String::from).to_vec();
+        assert_eq!(config.join_outputs(outputs.clone()), "a\n---\nb");
+        let config = Config {
+            separator: "\n".to_owned(),
+            ..config
+        };
+        assert_eq!(config.join_outputs(outputs.clone()), "a\n\nb");
+        let config = Config {
+            separator: String::new(),
+            ..config
+        };
+        assert_eq!(config.join_outputs(outputs), "a\nb");
+
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "-c",
+            "tests/config/render.toml",
+            "--concat",
+            "input1/file",
+        ]);
+        assert!(matches!(
+            Config::try_from(matches),
+            Err(ConfigError::ConcatConflict)
+        ));
+    }
+
+    #[test]
+    fn no_default_extensions() {
+        let args = [
+            "docfmt",
+            "tests/templates/main.hbs",
+            "out.md",
+            "--include",
+            "tests/templates/collision",
+        ];
+        let matches = crate::cli::get_cli().get_matches_from(args);
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(config.extensions, ["md", "markdown"]);
+
+        let matches = crate::cli::get_cli().get_matches_from(args.into_iter().chain([
+            "--no-default-extensions",
+            "--ext",
+            "hbs",
+        ]));
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(config.extensions, ["hbs"]);
+        let registry = config.new_registry().unwrap();
+        let mut names = registry.get_templates().keys().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["collision/page", "main"]);
+        let content = registry.render("collision/page", &json!({})).unwrap();
+        assert_eq!(content.trim_end(), "Page hbs");
+
+        let matches = crate::cli::get_cli()
+            .get_matches_from(args.into_iter().chain(["--no-default-extensions"]));
+        let config = Config::try_from(matches).unwrap();
+        assert!(config.extensions.is_empty());
+    }
+
+    #[test]
+    fn validate_mode() {
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "validate",
+            "tests/templates/main.hbs",
+            "--include",
+            "tests/templates/input1",
+            "--ext",
+            "hbs",
+        ]);
+        let config = Config::try_from(matches);
+        assert!(config.is_ok());
+        let config = config.unwrap();
+        assert_eq!(config.mode(), Mode::Validate);
+        assert_eq!(config.output, PathBuf::new());
+        assert_eq!(
+            config.include,
+            vec![PathBuf::from("tests/templates/input1")]
+        );
+
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "tests/templates/main.hbs",
+            "out.md",
+        ]);
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(config.mode(), Mode::Render);
+        assert_eq!(config.output, PathBuf::from("out.md"));
+
+        let matches =
+            crate::cli::get_cli().get_matches_from(["docfmt", "lint", "tests/templates/main.hbs"]);
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(config.mode(), Mode::Lint);
+        assert_eq!(config.output, PathBuf::new());
+
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "graph",
+            "tests/templates/main.hbs",
+            "--format",
+            "json",
+        ]);
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(config.mode(), Mode::Graph(GraphFormat::Json));
+    }
+
+    #[test]
+    fn preserve_order() {
+        let config = Config {
+            datafiles: vec![
+                PathBuf::from("tests/data/order.toml"),
+                PathBuf::from("tests/data/order.json"),
+            ],
+            data: serde_json::Value::Object(serde_json::Map::default()),
+            ..Default::default()
+        };
+
+        let data = config.read_data().unwrap();
+        let registry = Handlebars::new();
+        let content = registry.render_template("{{#each this}}{{@key}}={{this}} {{/each}}", &data);
+        assert_eq!(content.unwrap(), "zebra=1 apple=20 mango=3 banana=4 ");
+    }
+
+    #[test]
+    fn json5_data() {
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/comments.json5")],
+            data: json!({}),
+            ..Default::default()
+        };
+        let data = config.read_data().unwrap();
+        assert_eq!(data, json!({"title": "Hello", "tags": ["a", "b"]}));
+
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/malformed.json5")],
+            data: json!({}),
+            ..Default::default()
+        };
+        assert!(config.read_data().is_none());
+    }
+
+    #[test]
+    fn data_formats() {
+        let value = DataFormat::Yaml.parse("title: Hello\ntags: [a, b]\n");
+        assert_eq!(
+            value.unwrap(),
+            json!({"title": "Hello", "tags": ["a", "b"]})
+        );
+        assert!(DataFormat::Toml.parse("title = ").is_err());
+        assert_eq!(DataFormat::from_extension("yml"), Some(DataFormat::Yaml));
+        assert_eq!(DataFormat::from_extension("hbs"), None);
+    }
+
+    #[test]
+    fn ini_env_data() {
+        let config = Config {
+            datafiles: vec![
+                PathBuf::from("tests/data/legacy.ini"),
+                PathBuf::from("tests/data/dotenv/.env"),
+            ],
+            data: json!({}),
+            ..Default::default()
+        };
+        let data = config.read_data().unwrap();
+        assert_eq!(
+            data,
+            json!({
+                "name": "docfmt",
+                "server": {"host": "localhost", "port": "8080"},
+                "STAGE": "production",
+                "GREETING": "Hello, World!",
+            })
+        );
+        assert_eq!(DataFormat::from_extension("ini"), Some(DataFormat::Ini));
+        assert_eq!(DataFormat::from_extension("env"), Some(DataFormat::Env));
+        assert!(DataFormat::Ini.parse("[server\nhost = localhost").is_err());
+    }
+
+    #[test]
+    fn require_data() {
+        let config = Config {
+            require_data: true,
+            data: json!({}),
+            ..Default::default()
+        };
+        assert_eq!(config.read_data(), None);
+
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/empty.json")],
+            builtins: true,
+            ..config
+        };
+        assert_eq!(config.read_data(), None);
+
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/*.missing")],
+            data_commands: vec![DataCommand::parse("echo {}:json").unwrap()],
+            ..config
+        };
+        assert_eq!(config.read_data(), None);
+
+        let config = Config {
+            data: json!({"title": "Inline"}),
+            ..config
+        };
+        assert_eq!(config.read_data().unwrap()["title"], "Inline");
+
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/data1.toml")],
+            data: json!({}),
+            data_commands: vec![],
+            ..config
+        };
+        assert!(config.read_data().is_some());
+    }
+
+    #[test]
+    fn secrets_masking() {
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/data1.toml")],
+            secrets: vec![PathBuf::from("tests/data/secrets/.env")],
+            sensitive: vec!["/title".to_owned(), "/missing".to_owned()],
+            data: json!({}),
+            ..Default::default()
+        };
+        let data = config.read_data().unwrap();
+        assert_eq!(data["DB_PASSWORD"], "hunter2-fixture");
+        assert_eq!(data["title"], "This is a title");
+        assert_eq!(
+            secrets::redact(&data),
+            json!({
+                "title": "***",
+                "person": {"firstName": "John", "lastName": "Doe"},
+                "cities": ["london", "paris"],
+                "DB_PASSWORD": "***",
+                "API_TOKEN": "***",
+            })
+        );
+        assert_eq!(
+            secrets::mask("Token tok-fixture-123 for hunter2-fixture"),
+            "Token *** for ***"
+        );
+
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "--dump-data",
+            "--secrets",
+            "tests/data/secrets/.env",
+            "--sensitive",
+            "db.password",
+        ]);
+        let err = Config::try_from(matches).unwrap_err();
+        assert_eq!(err.code(), "INVALID_SENSITIVE");
+    }
+
+    #[test]
+    fn data_format_map() {
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/pipeline.data")],
+            data: json!({}),
+            ..Default::default()
+        };
+        assert!(config.read_data().is_none());
+
+        let config = Config {
+            data_formats: BTreeMap::from([("data".to_owned(), DataFormat::Json)]),
+            ..config
+        };
+        let data = config.read_data().unwrap();
+        assert_eq!(data, json!({"title": "Pipeline"}));
+    }
+
+    #[test]
+    fn data_glob() {
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/order.*")],
+            data: json!({}),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.datafiles(),
+            vec![
+                PathBuf::from("tests/data/order.json"),
+                PathBuf::from("tests/data/order.toml")
+            ]
+        );
+        let data = config.read_data().unwrap();
+        assert_eq!(data["banana"], json!(4));
+        assert_eq!(data["apple"], json!(2));
+
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/*.missing")],
+            ..config
+        };
+        assert_eq!(config.read_data().unwrap(), json!({}));
+
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/[")],
+            ..config
+        };
+        assert!(config.read_data().is_none());
+    }
+
+    #[test]
+    fn data_pointer() {
+        let config = Config {
+            datafiles: vec![
+                PathBuf::from("tests/data/data1.toml@/site/meta"),
+                PathBuf::from("tests/data/order.*@/orders/a~1b"),
+            ],
+            data: json!({"site": {"name": "docfmt"}}),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.datafiles(),
+            vec![
+                PathBuf::from("tests/data/data1.toml"),
+                PathBuf::from("tests/data/order.json"),
+                PathBuf::from("tests/data/order.toml"),
+            ]
+        );
+        let data = config.read_data().unwrap();
+        assert_eq!(data["site"]["name"], "docfmt");
+        assert_eq!(data["site"]["meta"]["title"], "This is a title");
+        assert_eq!(data["orders"]["a/b"]["apple"], 2);
+        assert!(data.get("title").is_none());
+        assert_eq!(
+            Config::split_pointer(Path::new("tests/data/data1.toml")),
+            (Path::new("tests/data/data1.toml"), None)
+        );
+        assert_eq!(
+            Config::split_pointer(Path::new("user@host.json")),
+            (Path::new("user@host.json"), None)
+        );
+    }
+
+    #[test]
+    fn data_jobs() {
+        let config = Config {
+            datafiles: vec![
+                PathBuf::from("tests/data/data1.toml"),
+                PathBuf::from("tests/data/order.json"),
+                PathBuf::from("tests/data/data2.json"),
+                PathBuf::from("tests/data/order.toml"),
+                PathBuf::from("tests/data/legacy.ini"),
+            ],
+            data: json!({}),
+            jobs: NonZeroUsize::new(1),
+            ..Default::default()
+        };
+        let sequential = config.read_data().unwrap();
+        let config = Config {
+            jobs: NonZeroUsize::new(4),
+            ..config
+        };
+        let parallel = config.read_data().unwrap();
+        assert_eq!(
+            serde_json::to_string(&sequential).unwrap(),
+            serde_json::to_string(&parallel).unwrap()
+        );
+
+        let matches = crate::cli::get_cli().try_get_matches_from(["docfmt", "-j", "0", "main.hbs"]);
+        assert!(matches.is_err());
+    }
+
+    #[test]
+    fn stdin_data() {
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "-d",
+            "-",
+            "--data-format",
+            "yaml",
+            "main.hbs",
+            "out.md",
+        ]);
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(config.data_format, Some(DataFormat::Yaml));
+
+        let matches =
+            crate::cli::get_cli().get_matches_from(["docfmt", "-d", "-", "main.hbs", "out.md"]);
+        assert!(matches!(
+            Config::try_from(matches),
+            Err(ConfigError::MissingDataFormat)
+        ));
+
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "-d",
+            "-",
+            "-d",
+            "-",
+            "--data-format",
+            "json",
+            "main.hbs",
+            "out.md",
+        ]);
+        assert!(matches!(
+            Config::try_from(matches),
+            Err(ConfigError::StdinConflict)
+        ));
+    }
+
+    #[test]
+    fn builtins() {
+        let config = Config {
+            builtins: true,
+            date_format: "%Y".to_owned(),
+            data: json!({"docfmt": {"date": "yesterday"}}),
+            ..Default::default()
+        };
+
+        let data = config.read_data().unwrap();
+        assert_eq!(data["docfmt"]["date"], "yesterday");
+        assert!(data["docfmt"]["now"].is_string());
+
+        let config = Config {
+            builtins: true,
+            date_format: "%Y".to_owned(),
+            data: json!({}),
+            ..Default::default()
+        };
+
+        let data = config.read_data().unwrap();
+        assert_eq!(
+            data["docfmt"]["date"],
+            chrono::Local::now().format("%Y").to_string()
+        );
+
+        let config = Config {
+            builtins: true,
+            date_format: "%Q".to_owned(),
+            data: json!({}),
+            ..Default::default()
+        };
+        assert!(config.read_data().is_none());
+    }
+
+    #[test]
+    fn html_escape() {
+        let data = json!({"value": "a & b"});
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            html_escape: true,
+            ..Default::default()
+        };
+        let registry = config.new_registry().unwrap();
+        let content = registry.render_template("{{value}}", &data);
+        assert_eq!(content.unwrap(), "a &amp; b");
+
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            html_escape: false,
+            ..Default::default()
+        };
+        let registry = config.new_registry().unwrap();
+        let content = registry.render_template("{{value}}", &data);
+        assert_eq!(content.unwrap(), "a & b");
+    }
+
+    #[test]
+    fn env() {
+        std::env::set_var("DOCFMT_TEST_ENV", "value");
+        std::env::set_var("DOCFMT_TEST_SECRET", "secret");
+        let config = Config {
+            env: true,
+            env_vars: vec!["DOCFMT_TEST_ENV".to_owned()],
+            data: json!({}),
+            ..Default::default()
+        };
+
+        let data = config.read_data().unwrap();
+        let registry = Handlebars::new();
+        let content = registry.render_template("{{env.DOCFMT_TEST_ENV}}", &data);
+        assert_eq!(content.unwrap(), "value");
+        assert!(data["env"].get("DOCFMT_TEST_SECRET").is_none());
+
+        let config = Config {
+            env: true,
+            data: json!({"env": {"DOCFMT_TEST_ENV": "data"}}),
+            ..Default::default()
+        };
+        let data = config.read_data().unwrap();
+        assert_eq!(data["env"]["DOCFMT_TEST_ENV"], "data");
+        assert_eq!(data["env"]["DOCFMT_TEST_SECRET"], "secret");
+
+        let config = Config {
+            env: false,
+            data: json!({}),
+            ..Default::default()
+        };
+        assert!(config.read_data().unwrap().get("env").is_none());
+    }
+
+    #[test]
+    fn render_error() {
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        registry
+            .register_template_string("main", "Hello\n{{> part}}")
+            .unwrap();
+        registry
+            .register_template_string("part", "Part\n  {{missing}}")
+            .unwrap();
+
+        let err = registry.render("main", &json!({})).unwrap_err();
+        let err = Config::computed_error("title", &err);
+        assert!(matches!(
+            &err,
+            ConfigError::RenderError { location, .. } if location == "part:2:3"
+        ));
+        assert!(err.to_string().starts_with("Render failed in part:2:3: "));
+
+        let err = registry.render_template("{{> part}}\n{{title}}", &json!({}));
+        let err = Config::computed_error("title", &err.unwrap_err());
+        assert!(err.to_string().starts_with("Render failed in part:2:3: "));
+        let err = registry.render_template("Hello\n {{title}}", &json!({}));
+        let err = Config::computed_error("title", &err.unwrap_err());
+        assert!(matches!(
+            &err,
+            ConfigError::RenderError { location, reason }
+                if location == "computed.title:2:2"
+                    && reason == "Variable \"title\" not found in strict mode"
+        ));
+    }
+
+    #[test]
+    fn render_error_location() {
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        let mut sources = HashMap::new();
+        for (name, source) in [
+            ("main", "Hello\n{{> section}}"),
+            ("section", "Section\n\n{{#if title}}{{/if}}\n{{> part}}"),
+            ("part", "Part\n  {{missing}}"),
+        ] {
+            registry.register_template_string(name, source).unwrap();
+            sources.insert(name.to_owned(), PathBuf::from(format!("{}.hbs", name)));
+        }
+        sources.remove("section");
+
+        let config = Config::default();
+        let err = registry.render("main", &json!({})).unwrap_err();
+        let err = config.render_error(&registry, &sources, err);
+        assert!(matches!(
+            &err,
+            ConfigError::RenderError { location, .. }
+                if location == "part.hbs:2:3, included from section:4, included from main.hbs:2"
+        ));
+
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/front_matter.hbs")),
+            ..Default::default()
+        };
+        let sources = HashMap::from([(
+            "main".to_owned(),
+            PathBuf::from("tests/templates/front_matter.hbs"),
+        )]);
+        let err = registry.render("main", &json!({})).unwrap_err();
+        let err = config.render_error(&registry, &sources, err);
+        assert_eq!(
+            err.to_string(),
+            concat!(
+                "Render failed in part:2:3, included from section:4, included from ",
+                "tests/templates/front_matter.hbs:7: Variable \"missing\" not found in strict mode"
+            )
+        );
+    }
+
+    #[test]
+    fn check_data_usage() {
+        let config = Config::default();
+        let sources = Sources::default();
+        let mut registry = Handlebars::new();
+        registry
+            .register_template_string(
+                DEFAULT_ENTRY,
+                "{{title}} {{#each cities}}{{this}} {{name}}{{/each}}",
+            )
+            .unwrap();
+        registry
+            .register_template_string("unused", "{{stale}}")
+            .unwrap();
+
+        let data = json!({"title": "Hello", "cities": [], "docfmt": {}, "translations": {}});
+        assert!(config.check_data_usage(&registry, &sources, &data));
+        let data = json!({"title": "Hello", "cities": [], "stale": true});
+        assert!(!config.check_data_usage(&registry, &sources, &data));
+        let data = json!({"title": "Hello", "cities": [], "name": "A"});
+        assert!(!config.check_data_usage(&registry, &sources, &data));
+    }
+
+    #[test]
+    fn check_usage() {
+        let mut registry = Handlebars::new();
+        registry
+            .register_template_string(
+                "main",
+                "{{title}} {{#each cities}}{{name}} {{../country}}{{/each}}",
+            )
+            .unwrap();
+        let config = Config {
+            entry: Some("main".to_owned()),
+            ..Default::default()
+        };
+
+        let data = json!({
+            "title": "A",
+            "cities": [],
+            "country": "B",
+            "name": "C",
+            "docfmt": {},
+            "translations": {}
+        });
+        assert_eq!(config.check_usage(&registry, &Sources::default(), &data), 1);
+        let data = json!({"title": "A", "cities": [], "country": "B"});
+        assert_eq!(config.check_usage(&registry, &Sources::default(), &data), 0);
+
+        registry
+            .register_template_string("main", "{{json this}}")
+            .unwrap();
+        let data = json!({"title": "A", "legacy": "B"});
+        assert_eq!(config.check_usage(&registry, &Sources::default(), &data), 0);
+    }
+
+    #[test]
+    fn check_references() {
+        let mut registry = Handlebars::new();
+        let sources = Sources {
+            helpers: helpers::register(&mut registry)
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            ..Default::default()
+        };
+        registry
+            .register_template_string(
+                "main",
+                "{{title}} {{person.name}} {{items.[1]}} {{#if note}}{{note}}{{/if}} {{missing}} {{debug}}",
+            )
+            .unwrap();
+        let data = json!({"title": "A", "person": {"name": "B"}, "items": [1, 2]});
+
+        let config = Config {
+            entry: Some("main".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(config.check_references(&registry, &sources, &data), 0);
+        let config = Config {
+            entry: Some("main".to_owned()),
+            strict: true,
+            ..Default::default()
+        };
+        assert_eq!(config.check_references(&registry, &sources, &data), 1);
+    }
+
+    #[test]
+    fn strict_undefined_partials() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/typo.hbs")),
+            ..Default::default()
+        };
+        let registry = config.new_registry().unwrap();
+        assert_eq!(
+            registry.render(config.entry(), &json!({})).unwrap(),
+            "# Title\n\n\n"
+        );
+
+        let config = Config {
+            strict_undefined_partials: true,
+            ..config
+        };
+        assert!(config.new_registry().is_err());
+    }
+
+    #[test]
+    fn check_unused() {
+        let mut registry = Handlebars::new();
+        for (name, source) in [
+            ("main", "{{> header}}"),
+            ("other", "{{> footer}}"),
+            ("header", ""),
+            ("footer", ""),
+        ] {
+            registry.register_template_string(name, source).unwrap();
+        }
+
+        let config = Config {
+            entry: Some("main".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(config.check_unused(&registry), 2);
+        let config = Config {
+            render: ["main", "other"]
+                .map(|entry| RenderTarget {
+                    entry: entry.to_owned(),
+                    output: PathBuf::from(format!("{}.md", entry)),
+                })
+                .to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(config.check_unused(&registry), 0);
+
+        registry
+            .register_template_string("main", "{{> (lookup_file \"status\" state)}}")
+            .unwrap();
+        let config = Config {
+            entry: Some("main".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(config.check_unused(&registry), 0);
+    }
+
+    #[test]
+    fn lint() {
+        let mut registry = Handlebars::new();
+        for (name, source) in [
+            ("main", "{{> header}}{{> missing}}"),
+            ("header", "{{#*inline \"row\"}}{{/inline}}{{> row}}"),
+            ("footer", "{{> other}}"),
+        ] {
+            registry.register_template_string(name, source).unwrap();
+        }
+
+        let config = Config {
+            entry: Some("main".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(config.lint(&registry), 3);
+        registry.unregister_template("footer");
+        assert_eq!(config.lint(&registry), 1);
+        registry.register_template_string("missing", "").unwrap();
+        assert_eq!(config.lint(&registry), 0);
+    }
+
+    #[test]
+    fn lint_variables() {
+        let mut registry = Handlebars::new();
+        let mut sources = Sources::default();
+        for (name, source) in [
+            ("main", "{{title}}\n{{> part}}"),
+            (
+                "part",
+                "{{person.name}}\n\n{{site.url}} {{#each items}}{{name}}{{/each}}",
+            ),
+        ] {
+            registry.register_template_string(name, source).unwrap();
+            sources
+                .files
+                .insert(name.to_owned(), PathBuf::from(format!("{}.hbs", name)));
+        }
+        let data = json!({"title": "A", "person": {}});
+
+        let config = Config {
+            entry: Some("main".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(config.lint_variables(&registry, &sources, &data), 3);
+        let config = Config {
+            schema: Some(PathBuf::from("tests/data/schema.json")),
+            ..config
+        };
+        assert_eq!(config.lint_variables(&registry, &sources, &data), 1);
+        let config = Config {
+            schema: Some(PathBuf::from("tests/data/missing.json")),
+            ..config
+        };
+        assert_eq!(config.lint_variables(&registry, &sources, &data), 1);
+    }
+
+    #[cfg(feature = "scripting")]
+    #[test]
+    fn lint_variables_helpers() {
+        let config = Config::from_toml_str(concat!(
+            "template = \"tests/helpers/main.hbs\"\n",
+            "output = \"out.md\"\n",
+            "helpers = [\"tests/helpers/double.rhai\"]\n",
+        ))
+        .unwrap();
+        let data = json!({"title": "A"});
+        let (registry, sources) = config.new_registry_with_sources(Some(&data)).unwrap();
+        assert!(sources.helpers.contains("debug"));
+        assert!(sources.helpers.contains("double"));
+        assert_eq!(config.lint_variables(&registry, &sources, &data), 1);
+    }
+
+    #[test]
+    fn array_root() {
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/array.json")],
+            builtins: true,
+            env: true,
+            ..Default::default()
+        };
+        let data = config.read_data().unwrap();
+        assert_eq!(data, json!([{"name": "first"}, {"name": "second"}]));
+
+        let registry = Handlebars::new();
+        let rendered = registry.render_template("{{#each this}}{{name}} {{/each}}", &data);
+        assert_eq!(rendered.unwrap(), "first second ");
+
+        let config = Config {
+            data: json!({"title": "Inline"}),
+            ..config
+        };
+        assert!(config.read_data().is_none());
+
+        let config = Config {
+            data: json!({}),
+            datafiles: vec![
+                PathBuf::from("tests/data/array.json"),
+                PathBuf::from("tests/data/data2.json"),
+            ],
+            ..config
+        };
+        assert!(config.read_data().is_none());
+    }
+
+    #[test]
+    fn gzip_data() {
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/compressed.json.gz")],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.read_data().unwrap(),
+            json!({"title": "Compressed", "tags": ["gz"]})
+        );
+
+        let result = config.read_datafile(Path::new("tests/data/corrupt.toml.gz"));
+        assert_eq!(result.unwrap_err().0, "Unable to decompress data file");
+        let result = config.read_datafile(Path::new("tests/data/data2.json.gz"));
+        assert_eq!(result.unwrap_err().0, "Unable to open data file");
+        let result = config.read_datafile(Path::new("tests/data/compressed.gz"));
+        assert_eq!(result.unwrap_err().1, "Unsupported file extension");
+    }
+
+    #[test]
+    fn data_url() {
+        let url = Path::new("https://example.com/data/site.json?ref=main");
+        assert_eq!(Config::url(url), url.to_str());
+        assert_eq!(Config::url(Path::new("data/site.json")), None);
+        assert!(Config::expand_glob(url).is_none());
+        assert_eq!(ConfigRead::resolve_datafile(Path::new("docs"), url), url);
+
+        assert_eq!(
+            DataFormat::from_mime_type("application/json"),
+            Some(DataFormat::Json)
+        );
+        assert_eq!(
+            DataFormat::from_mime_type("application/vnd.api+json"),
+            Some(DataFormat::Json)
+        );
+        assert_eq!(
+            DataFormat::from_mime_type("application/toml"),
+            Some(DataFormat::Toml)
+        );
+        assert_eq!(
+            DataFormat::from_mime_type("text/yaml"),
+            Some(DataFormat::Yaml)
+        );
+        assert_eq!(DataFormat::from_mime_type("text/html"), None);
+
+        #[cfg(not(feature = "http"))]
+        {
+            let result = Config::default().read_datafile(url);
+            assert_eq!(result.unwrap_err().0, "Unable to fetch data URL");
+        }
+    }
+
+    /// Serve one canned response per connection on a local port and return
+    /// its base URL.
+    #[cfg(feature = "http")]
+    fn serve(responses: Vec<String>) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        url
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn check_remote_links() {
+        let url = serve(vec![
+            "HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\n\r\n".to_owned(),
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned(),
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned(),
+        ]);
+        let output =
+            std::env::temp_dir().join(format!("docfmt-remote-links-{}.md", std::process::id()));
+        let config = Config {
+            check_remote_links: true,
+            ..Default::default()
+        };
+
+        let content = format!("[a]({url}/a) [again]({url}/a)\n", url = url);
+        std::fs::write(&output, content).unwrap();
+        assert!(config.check_output_links(std::slice::from_ref(&output)));
+        std::fs::write(&output, format!("[b]({}/b)\n", url)).unwrap();
+        assert!(!config.check_output_links(std::slice::from_ref(&output)));
+
+        std::fs::remove_file(output).unwrap();
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn fetch_data_url() {
+        let response = |status: &str, content_type: &str, body: &str| {
+            format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            )
+        };
+        let url = serve(vec![
+            response("200 OK", "text/plain", "title = \"Remote\""),
+            response(
+                "200 OK",
+                "application/json; charset=utf-8",
+                "{\"tags\": [\"http\"]}",
+            ),
+            response("404 Not Found", "text/plain", "missing"),
+            response("200 OK", "text/html", "<html></html>"),
+        ]);
+
+        let config = Config {
+            datafiles: vec![
+                PathBuf::from(format!("{}/site.toml?ref=main", url)),
+                PathBuf::from(format!("{}/api/site", url)),
+            ],
+            jobs: NonZeroUsize::new(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.read_data().unwrap(),
+            json!({"title": "Remote", "tags": ["http"]})
+        );
+        let result = config.read_datafile(Path::new(&format!("{}/missing.json", url)));
+        assert_eq!(
+            result.unwrap_err(),
+            (
+                "Unable to fetch data URL",
+                "Server responded with HTTP status 404".to_owned()
+            )
+        );
+        let result = config.read_datafile(Path::new(&format!("{}/page", url)));
+        assert_eq!(
+            result.unwrap_err().1,
+            "Unsupported URL extension and content type"
+        );
+    }
+
+    #[test]
+    fn profile_overlays() {
+        assert_eq!(
+            Config::overlay_path(Path::new("data/site.toml"), "prod"),
+            Some(PathBuf::from("data/site.prod.toml"))
+        );
+        assert_eq!(
+            Config::overlay_path(Path::new("site.json.gz"), "dev"),
+            Some(PathBuf::from("site.dev.json.gz"))
+        );
+        assert_eq!(Config::overlay_path(Path::new(".env"), "dev"), None);
+        assert_eq!(Config::overlay_path(Path::new("data.gz"), "dev"), None);
+
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/profile/site.toml")],
+            ..Default::default()
+        };
+        let base = json!({
+            "title": "Site",
+            "url": "http://localhost",
+            "features": {"search": false},
+        });
+        assert_eq!(config.read_data().unwrap(), base);
+
+        let config = Config {
+            profile: Some("staging".to_owned()),
+            ..config
+        };
+        assert_eq!(config.read_data().unwrap(), base);
+
+        let prod = json!({
+            "title": "Site",
+            "url": "https://example.com",
+            "features": {"search": true},
+        });
+        let config = Config {
+            profile: Some("prod".to_owned()),
+            ..config
+        };
+        assert_eq!(config.read_data().unwrap(), prod);
+        assert_eq!(
+            config.datafiles(),
+            [
+                PathBuf::from("tests/data/profile/site.toml"),
+                PathBuf::from("tests/data/profile/site.prod.toml"),
+            ]
+        );
+
+        let config = Config {
+            datafiles: vec![PathBuf::from("tests/data/profile/*.toml")],
+            ..config
+        };
+        assert_eq!(config.read_data().unwrap(), prod);
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/bom.hbs")),
+            include: vec![PathBuf::from("tests/templates/bom.hbs")],
+            datafiles: vec![PathBuf::from("tests/data/bom.json")],
+            builtins: false,
+            ..Default::default()
+        };
+        let data = config.read_data().unwrap();
+        assert_eq!(data, json!({"title": "BOM"}));
+
+        let registry = config.new_registry().unwrap();
+        assert_eq!(registry.render("main", &data).unwrap(), "BOM\n");
+        assert_eq!(registry.render("bom", &data).unwrap(), "BOM\n");
+    }
+
+    #[test]
+    fn data_commands() {
+        assert_eq!(
+            DataCommand::parse("./gen-data.sh --url http://host:json"),
+            Some(DataCommand {
+                command: "./gen-data.sh --url http://host".to_owned(),
+                format: DataFormat::Json,
+            })
+        );
+        assert_eq!(DataCommand::parse("./gen-data.sh"), None);
+        assert_eq!(DataCommand::parse("./gen-data.sh:xml"), None);
+        assert_eq!(DataCommand::parse(":json"), None);
+
+        let config = Config {
+            data_commands: vec![
+                DataCommand::parse("echo 'title = \"Command\"':toml").unwrap(),
+                DataCommand::parse("echo '{\"count\": 2}':json").unwrap(),
+            ],
+            builtins: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.read_data().unwrap(),
+            json!({"title": "Command", "count": 2})
+        );
+
+        let config = Config {
+            data_commands: vec![DataCommand::parse("echo oops >&2; exit 3:json").unwrap()],
+            ..Default::default()
+        };
+        assert!(config.read_data().is_none());
+
+        let cli = crate::cli::get_cli;
+        let err = Config::try_from(cli().get_matches_from([
+            "docfmt",
+            "--data-cmd",
+            "./gen-data.sh",
+            "a",
+            "b",
+        ]))
+        .unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidDataCommand(_)));
+    }
+
+    #[test]
+    fn include_conditions() {
+        let data = json!({"audience": "internal", "draft": false, "level": 2, "tags": []});
+        let matches = |condition: &str| Condition::parse(condition).unwrap().matches(&data);
+
+        assert!(matches("audience == internal"));
+        assert!(matches("/audience == \"internal\""));
+        assert!(!matches("audience != internal"));
+        assert!(matches("level == 2"));
+        assert!(matches("audience"));
+        assert!(!matches("draft"));
+        assert!(!matches("tags"));
+        assert!(!matches("missing"));
+        assert!(matches("missing != x"));
+        assert_eq!(Condition::parse(" == x"), None);
+
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            include: vec![
+                PathBuf::from("tests/templates/input1"),
+                PathBuf::from("tests/templates/input2"),
+            ],
+            include_when: BTreeMap::from([(
+                PathBuf::from("tests/templates/input2"),
+                Condition::parse("audience == public").unwrap(),
+            )]),
+            extensions: vec!["hbs".into(), "md".into()],
+            ..Default::default()
+        };
+        let all = config.new_registry().unwrap();
+        assert!(all.get_template("input2/file").is_some());
+        let registry = config.new_registry_for(&data).unwrap();
+        assert!(registry.get_template("input2/file").is_none());
+        assert!(registry.get_template("input1/file").is_some());
+
+        let data = json!({"audience": "public"});
+        let registry = config.new_registry_for(&data).unwrap();
+        assert_eq!(registry.get_templates().len(), all.get_templates().len());
+    }
+
+    #[test]
+    fn include_limits() {
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            include: vec![PathBuf::from("tests/templates/input1")],
+            extensions: vec!["hbs".into()],
+            ..Default::default()
+        };
+        let registry = config.new_registry().unwrap();
+        assert!(registry.get_template("input1/subdir/file").is_some());
+
+        let config = Config {
+            max_depth: NonZeroUsize::new(1),
+            ..config
+        };
+        let registry = config.new_registry().unwrap();
+        assert!(registry.get_template("input1/file").is_some());
+        assert!(registry.get_template("input1/subdir/file").is_none());
+
+        let config = Config {
+            max_depth: None,
+            max_templates: NonZeroUsize::new(2),
+            ..config
+        };
+        let report = config.new_registry().unwrap_err();
+        assert_eq!(report.registered, 2);
+        assert_eq!(report.failed.len(), 1);
+
+        let config = Config {
+            max_templates: NonZeroUsize::new(3),
+            ..config
+        };
+        assert!(config.new_registry().is_ok());
+    }
+
+    #[test]
+    fn registration_order() {
+        let root = std::env::temp_dir().join(format!("docfmt-order-{}", std::process::id()));
+        let dir = root.join("docs");
+        std::fs::create_dir_all(dir.join("b")).unwrap();
+        for (file, content) in [
+            ("c.hbs", "C"),
+            ("b/x.hbs", "X"),
+            ("page.md", "Page md"),
+            ("page.hbs", "Page hbs"),
+            ("a.hbs", "A"),
+        ] {
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+        std::fs::write(root.join("2.json"), r#"{"a": 2, "c": 2}"#).unwrap();
+        std::fs::write(root.join("1.json"), r#"{"b": 1, "a": 1}"#).unwrap();
+
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            include: vec![dir.to_owned()],
+            extensions: vec!["hbs".into(), "md".into()],
+            max_templates: NonZeroUsize::new(3),
+            datafiles: vec![root.join("*.json")],
+            ..Default::default()
         };
-        config.verbose = if matches.get_flag("verbose") {
-            true
-        } else {
-            config.verbose
+        let report = config.new_registry().unwrap_err();
+        assert_eq!(report.registered, 3);
+        assert_eq!(report.failed, [dir.join("c.hbs")]);
+
+        let config = Config {
+            max_templates: None,
+            ..config
         };
-        config.include.extend(
-            matches
-                .get_many::<PathBuf>("include")
-                .unwrap_or_default()
-                .map(PathBuf::from),
-        );
-        config.extensions.extend(
-            matches
-                .get_many::<String>("extension")
-                .unwrap_or_default()
-                .map(String::from),
+        let registry = config.new_registry().unwrap();
+        let content = registry.render("docs/page", &json!({}));
+        assert_eq!(content.unwrap(), "Page md");
+
+        let data = config.read_data().unwrap();
+        assert_eq!(data, json!({"b": 1, "a": 2, "c": 2}));
+        let keys = data.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(keys, ["b", "a", "c"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn sidecar_data() {
+        let data = json!({
+            "title": "Doc",
+            "author": {"name": "Jane", "mail": "jane@example.com"},
+            "items": [{"name": "a"}, {"name": "b"}],
+        });
+        let config = Config {
+            include: vec![PathBuf::from("tests/sidecar")],
+            extensions: vec!["hbs".to_owned()],
+            entry: Some("sidecar/main".to_owned()),
+            sidecar_data: true,
+            ..Default::default()
+        };
+        let registry = config.new_registry().unwrap();
+        assert_eq!(
+            registry.render("sidecar/main", &data).unwrap(),
+            "Doc\nCard by Jane <card@example.com> in Doc\na!;b!;\n"
         );
-        config.datafiles.extend(
-            matches
-                .get_many::<PathBuf>("data")
-                .unwrap_or_default()
-                .map(PathBuf::from),
+
+        let config = Config {
+            sidecar_data: false,
+            ..config
+        };
+        let registry = config.new_registry().unwrap();
+        assert_eq!(
+            registry.render("sidecar/main", &data).unwrap(),
+            "Doc\nDoc by Jane <jane@example.com> in Doc\na;b;\n"
         );
+    }
 
-        Self::try_from(config)
+    #[test]
+    fn archive_includes() {
+        assert!(Config::is_archive(Path::new("templates/bundle.zip")));
+        assert!(Config::is_archive(Path::new("bundle.TAR.GZ")));
+        assert!(Config::is_archive(Path::new("bundle.tgz")));
+        assert!(Config::is_archive(Path::new("bundle.tar")));
+        assert!(!Config::is_archive(Path::new("bundle.gz")));
+        assert!(!Config::is_archive(Path::new("tests/archive")));
+
+        let config = Config {
+            include: vec![PathBuf::from("tests/archive/bundle.zip")],
+            extensions: vec!["md".to_owned()],
+            ..Default::default()
+        };
+        #[cfg(not(feature = "archive"))]
+        assert!(config.new_registry().is_err());
+        #[cfg(feature = "archive")]
+        {
+            let registry = config.new_registry().unwrap();
+            let mut names = registry.get_templates().keys().collect::<Vec<_>>();
+            names.sort();
+            assert_eq!(names, ["deep/a/b", "main", "partials/footer"]);
+            assert_eq!(
+                registry.render("main", &json!({"title": "Zip"})).unwrap(),
+                "Bundle Zip\nFooter\n"
+            );
+
+            let config = Config {
+                include: vec![PathBuf::from("bundle:tests/archive/bundle.tar.gz")],
+                entry: Some("bundle/main".to_owned()),
+                max_depth: NonZeroUsize::new(2),
+                ..config
+            };
+            let registry = config.new_registry().unwrap();
+            let mut names = registry.get_templates().keys().collect::<Vec<_>>();
+            names.sort();
+            assert_eq!(names, ["bundle/main", "bundle/partials/footer"]);
+
+            let config = Config {
+                include: vec![PathBuf::from("tests/archive/corrupt.zip")],
+                ..config
+            };
+            let report = config.new_registry().unwrap_err();
+            assert_eq!(report.failed, [PathBuf::from("tests/archive/corrupt.zip")]);
+        }
     }
-}
 
-impl TryFrom<ConfigRead> for Config {
-    type Error = ConfigError;
+    #[test]
+    fn output_dir() {
+        let cli = crate::cli::get_cli;
+        let config = Config::try_from(cli().get_matches_from([
+            "docfmt",
+            "-i",
+            "tests/templates/input1",
+            "-i",
+            "tests/templates/input2",
+            "-e",
+            "hbs",
+            "-e",
+            "md",
+            "--output-dir",
+            "site",
+            "--output-pattern",
+            "input1/**",
+            "--output-ext",
+            "html",
+        ]))
+        .unwrap();
+        assert!(config.entries().is_empty());
+        let registry = config.new_registry().unwrap();
+        let targets = config.output_dir_targets(&registry);
+        assert_eq!(
+            targets,
+            [
+                RenderTarget {
+                    entry: "input1/file".to_owned(),
+                    output: PathBuf::from("site/input1/file.html"),
+                },
+                RenderTarget {
+                    entry: "input1/subdir/file".to_owned(),
+                    output: PathBuf::from("site/input1/subdir/file.html"),
+                },
+            ]
+        );
 
-    fn try_from(config: ConfigRead) -> Result<Self, Self::Error> {
-        Ok(Config {
-            template: config.template.ok_or(ConfigError::MissingTemplate)?,
-            output: config.output.ok_or(ConfigError::MissingOutput)?,
-            force: config.force,
-            follow: config.follow,
-            strict: config.strict,
-            verbose: config.verbose,
-            include: config.include,
-            extensions: config.extensions,
-            datafiles: config.datafiles,
-            data: config
-                .data
-                .map_or(serde_json::Value::Object(serde_json::Map::default()), |v| {
-                    serde_json::to_value(v).unwrap()
-                }),
+        let config = Config {
+            output_pattern: None,
+            output_ext: "md".to_owned(),
+            ..config
+        };
+        let targets = config.output_dir_targets(&registry);
+        assert_eq!(targets.len(), 3);
+        assert_eq!(targets[2].output, PathBuf::from("site/input2/file.md"));
+
+        let err = Config::try_from(ConfigRead {
+            output_dir: Some(PathBuf::from("site")),
+            each: Some("/pages".to_owned()),
+            ..Default::default()
         })
+        .unwrap_err();
+        assert!(matches!(err, ConfigError::OutputDirConflict));
+        let err = Config::try_from(ConfigRead {
+            output_dir: Some(PathBuf::from("site")),
+            output_pattern: Some("[".to_owned()),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidPattern(_)));
     }
-}
 
-#[derive(thiserror::Error, Debug)]
-pub enum ConfigError {
-    #[error("Unable to read config file: {0}")]
-    ConfigFileReadError(std::io::Error),
-    #[error("Invalid configuration: {0}")]
-    InvalidConfig(toml::de::Error),
-    #[error("Missing template file")]
-    MissingTemplate,
-    #[error("Missing output file")]
-    MissingOutput,
-    #[error("Unable to read template: {0}")]
-    TemplateError(#[from] TemplateError),
-}
+    #[test]
+    fn print_templates() {
+        let cli = crate::cli::get_cli;
+        let config = Config::try_from(cli().get_matches_from([
+            "docfmt",
+            "-i",
+            "tests/templates/input1",
+            "-e",
+            "hbs",
+            "--print-templates",
+        ]))
+        .unwrap();
+        assert!(config.print_templates());
+        assert!(config.entries().is_empty());
+        let registry = config.new_registry().unwrap();
+        let mut names = registry.get_templates().keys().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["input1/file", "input1/subdir/file"]);
+    }
 
-#[cfg(test)]
-mod tests {
-    use serde_json::json;
+    #[test]
+    fn computed_values() {
+        let computed = |values: &[(&str, &str)]| Config {
+            computed: values
+                .iter()
+                .map(|(key, template)| (key.to_string(), template.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        let mut data = json!({"major": 1, "minor": 2, "patch": 3});
 
-    use super::*;
+        let config = computed(&[
+            ("title", "docfmt {{full_version}}"),
+            ("full_version", "{{version}}.{{patch}}"),
+            ("version", "{{major}}.{{minor}}"),
+        ]);
+        assert!(config.compute(&registry, &mut data));
+        assert_eq!(data["title"], "docfmt 1.2.3");
+        assert_eq!(data["full_version"], "1.2.3");
+        let keys = data.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "major",
+                "minor",
+                "patch",
+                "version",
+                "full_version",
+                "title"
+            ]
+        );
+
+        let config = computed(&[("a", "{{b}}"), ("b", "{{c}}"), ("c", "{{a}}")]);
+        assert!(!config.compute(&registry, &mut data));
+        let config = computed(&[("a", "{{a}}")]);
+        assert!(!config.compute(&registry, &mut data));
+        let config = computed(&[("a", "{{missing}}")]);
+        assert!(!config.compute(&registry, &mut data));
+        let config = computed(&[("a", "{{#if}}")]);
+        assert!(!config.compute(&registry, &mut data));
+
+        let err = Config::try_from(ConfigRead {
+            template: Some(PathBuf::from("main.hbs")),
+            output: Some(PathBuf::from("out.md")),
+            computed: serde_json::Map::from_iter([("year".to_owned(), json!(2024))]),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert!(matches!(err, ConfigError::InvalidComputed(_)));
+    }
 
     #[test]
-    fn create_registry() {
+    fn log_level() {
+        let cli = crate::cli::get_cli;
+        let config = Config::try_from(cli().get_matches_from(["docfmt", "a", "b"])).unwrap();
+        assert_eq!(config.log_level(), log::Level::Warn);
+        let config = Config::try_from(cli().get_matches_from(["docfmt", "-q", "a", "b"])).unwrap();
+        assert_eq!(config.log_level(), log::Level::Error);
+        let config = Config::try_from(cli().get_matches_from(["docfmt", "-vv", "a", "b"])).unwrap();
+        assert_eq!(config.log_level(), log::Level::Trace);
+        assert!(cli()
+            .try_get_matches_from(["docfmt", "-q", "-v", "a", "b"])
+            .is_err());
+    }
+
+    #[test]
+    fn front_matter() {
         let config = Config {
-            template: PathBuf::from("tests/templates/main.hbs"),
-            output: PathBuf::from("tests/output/main.md"),
-            force: false,
-            follow: false,
-            strict: false,
-            verbose: false,
-            include: vec![
-                PathBuf::from("tests/templates/input1"),
-                PathBuf::from("tests/templates/input2"),
-                PathBuf::from("tests/templates/file.hbs"),
-            ],
-            extensions: vec!["hbs".into(), "md".into()],
-            datafiles: vec![],
-            data: serde_json::Value::Object(serde_json::Map::default()),
+            template: Some(PathBuf::from("tests/templates/front_matter.hbs")),
+            datafiles: vec![PathBuf::from("tests/data/data1.toml")],
+            data: json!({"author": "Jane"}),
+            ..Default::default()
         };
 
-        let registry = config.new_registry();
-        assert!(registry.is_some());
-        let registry = registry.unwrap();
+        let registry = config.new_registry().unwrap();
+        let data = config.read_data().unwrap();
+        assert_eq!(data["title"], "This is a title");
+        assert_eq!(data["author"], "Jane");
+        assert_eq!(data["layout"], "page");
 
-        assert!(registry.get_template("main").is_some());
-        assert!(registry.get_template("input1/file").is_some());
-        assert!(registry.get_template("input1/.hidden").is_none());
-        assert!(registry.get_template("input1/subdir/file").is_some());
-        assert!(registry.get_template("input2/file").is_some());
-        assert!(registry.get_template("input2/.hidden").is_none());
-        assert!(registry.get_template("file").is_some());
+        let content = registry.render("main", &data).unwrap();
+        assert_eq!(content, "This is a title by Jane (page)\n");
+    }
 
-        let data = config.read_data();
-        assert!(data.is_some());
-        let data = data.unwrap();
+    #[test]
+    fn resolve_output() {
+        let registry = config_registry();
+        let data = json!({"title": "Hello World", "dir": "../out"});
+        let mut config = Config {
+            output_template: Some("docs/{{slug title}}.md".to_owned()),
+            ..Default::default()
+        };
+        assert!(config.resolve_output(&registry, &data));
+        assert_eq!(config.output, PathBuf::from("docs/hello-world.md"));
 
-        let content = registry.render("main", &data);
-        assert!(content.is_ok());
-        let content = content.unwrap();
+        let mut config = Config {
+            output_template: Some("{{missing}}".to_owned()),
+            ..Default::default()
+        };
+        assert!(!config.resolve_output(&registry, &data));
 
-        assert_eq!(content, "Hello World!\nGoodbye!\nFor now!");
+        let mut config = Config {
+            output_template: Some("{{dir}}/{{slug title}}.md".to_owned()),
+            ..Default::default()
+        };
+        assert!(config.resolve_output(&registry, &data));
+        let mut config = Config {
+            output_template: Some("{{dir}}/{{slug title}}.md".to_owned()),
+            safe: true,
+            ..Default::default()
+        };
+        assert!(!config.resolve_output(&registry, &data));
+
+        let data = json!({"name": "Q&A <\"draft\">", "draft": true});
+        let mut config = Config {
+            output_template: Some("{{name}}{{#if draft}}-{{name}}{{/if}}.md".to_owned()),
+            output_template_dir: Some(PathBuf::from("../docs")),
+            safe: true,
+            ..Default::default()
+        };
+        assert!(config.resolve_output(&registry, &data));
+        assert_eq!(
+            config.output,
+            PathBuf::from("../docs/Q&A <\"draft\">-Q&A <\"draft\">.md")
+        );
     }
 
     #[test]
-    fn read_data() {
+    fn each_contexts() {
+        let data = json!({"site": "Docs", "title": "Index", "pages": [{"title": "One"}, "two"]});
         let config = Config {
-            template: PathBuf::from("tests/templates/main.hbs"),
-            output: PathBuf::from("tests/output/main.md"),
-            force: false,
-            follow: false,
-            strict: false,
-            verbose: false,
-            include: vec![],
-            extensions: vec![],
-            datafiles: vec![
-                PathBuf::from("tests/data/data1.toml"),
-                PathBuf::from("tests/data/data2.json"),
-            ],
-            data: serde_json::Value::Object(serde_json::Map::default()),
+            each: Some("/pages".to_owned()),
+            ..Default::default()
         };
 
-        let data = config.read_data();
-        assert!(data.is_some());
-        let data = data.unwrap();
-        let expected = json!({
-            "cities": [
-              "colombo"
-            ],
-            "person": {
-              "firstName": "Jane"
-            },
-            "title": "This is another title"
-        });
+        let contexts = config.each_contexts(&data).unwrap();
+        assert_eq!(contexts.len(), 2);
+        assert_eq!(contexts[0]["site"], "Docs");
+        assert_eq!(contexts[0]["title"], "One");
+        assert_eq!(contexts[0]["docfmt"]["index"], 0);
+        assert_eq!(contexts[1]["title"], "Index");
+        assert_eq!(contexts[1]["docfmt"]["item"], "two");
+        assert_eq!(contexts[1]["docfmt"]["index"], 1);
 
-        assert_eq!(data, expected);
+        let config = Config {
+            each: Some("/site".to_owned()),
+            ..Default::default()
+        };
+        assert!(config.each_contexts(&data).is_none());
+        let config = Config {
+            each: Some("/missing".to_owned()),
+            ..Default::default()
+        };
+        assert!(config.each_contexts(&data).is_none());
+    }
+
+    fn config_registry() -> Handlebars<'static> {
+        let mut registry = Handlebars::new();
+        helpers::register(&mut registry);
+        registry
+    }
+
+    #[test]
+    fn output_format() {
+        let config = Config::default();
+        assert!(config.check_output("{ not json"));
+
+        let config = Config {
+            output_format: OutputFormat::Json,
+            ..Default::default()
+        };
+        assert!(config.check_output(r#"{"title": "Hello"}"#));
+        assert!(!config.check_output(r#"{"title": "Hello",}"#));
+
+        let config = Config {
+            output_format: OutputFormat::Toml,
+            ..Default::default()
+        };
+        assert!(config.check_output("title = \"Hello\"\n"));
+        assert!(!config.check_output("title = Hello\n"));
+    }
+
+    #[test]
+    fn pretty_output() {
+        assert_eq!(
+            OutputFormat::Json
+                .pretty(r#"{"title": "Hello", "tags": [1, 2]}"#.to_owned())
+                .unwrap(),
+            "{\n  \"title\": \"Hello\",\n  \"tags\": [\n    1,\n    2\n  ]\n}\n"
+        );
+        assert_eq!(
+            OutputFormat::Toml
+                .pretty("title =   \"Hello\"\n[a]\nb=1".to_owned())
+                .unwrap(),
+            "title = \"Hello\"\n\n[a]\nb = 1\n"
+        );
+        assert_eq!(
+            OutputFormat::Text.pretty("{ not json".to_owned()).unwrap(),
+            "{ not json"
+        );
+        assert!(OutputFormat::Json.pretty("{ not json".to_owned()).is_err());
+
+        let mut config = ConfigRead {
+            template: Some(PathBuf::from("main.hbs")),
+            output: Some(PathBuf::from(STDIN)),
+            ..Default::default()
+        };
+        config.resolve_paths(Path::new("site"));
+        assert_eq!(config.template, Some(PathBuf::from("site/main.hbs")));
+        assert_eq!(config.output, Some(PathBuf::from(STDIN)));
+    }
+
+    #[test]
+    fn collapse_blank_lines() {
+        let mut registry = Handlebars::new();
+        registry
+            .register_template_string(
+                "main",
+                "# Items\n\n{{#each items}}\n{{#if this}}\n- {{this}}\n{{/if}}\n\n{{/each}}\n\nEnd\n",
+            )
+            .unwrap();
+        let content = registry
+            .render("main", &json!({"items": ["a", "", "", "b"]}))
+            .unwrap();
+        assert!(content.contains("\n\n\n"));
+        assert_eq!(
+            super::collapse_blank_lines(&content),
+            "# Items\n\n- a\n\n- b\n\nEnd\n"
+        );
+        assert_eq!(
+            super::collapse_blank_lines("a\r\n \r\n\r\nb"),
+            "a\r\n \r\nb"
+        );
+    }
+
+    #[test]
+    fn line_ending() {
+        let content = "a\nb\r\nc\n";
+        assert_eq!(LineEnding::Lf.normalize(content), "a\nb\nc\n");
+        assert_eq!(LineEnding::Crlf.normalize(content), "a\r\nb\r\nc\r\n");
+        #[cfg(windows)]
+        assert_eq!(LineEnding::Native.normalize(content), "a\r\nb\r\nc\r\n");
+        #[cfg(not(windows))]
+        assert_eq!(LineEnding::Native.normalize(content), "a\nb\nc\n");
     }
 
     #[test]
     fn write_output() {
         let config = Config {
-            template: PathBuf::from("tests/templates/main.hbs"),
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
             output: PathBuf::from("tests/output/main.md"),
             force: true,
             follow: false,
             strict: false,
-            verbose: false,
+            verbose: 0,
             include: vec![],
             extensions: vec![],
             datafiles: vec![],
             data: serde_json::Value::Object(serde_json::Map::default()),
+            ..Default::default()
         };
 
         let content = "Hello World!\nGoodbye!\nFor now!".to_owned();
@@ -493,21 +6098,199 @@ mod tests {
         assert_eq!(content, "Hello World!\nGoodbye!\nFor now!");
 
         let config = Config {
-            template: PathBuf::from("tests/templates/main.hbs"),
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
             output: PathBuf::from("tests/output/main.md"),
             force: false,
             follow: false,
             strict: false,
-            verbose: false,
+            verbose: 0,
             include: vec![],
             extensions: vec![],
             datafiles: vec![],
             data: serde_json::Value::Object(serde_json::Map::default()),
+            ..Default::default()
         };
 
         let content = "Hello World!\nGoodbye!\nFor now!".to_owned();
         let success = config.write_output(content);
         assert!(!success);
+    }
+
+    #[test]
+    fn write_output_create_dir() {
+        let root = std::env::temp_dir().join(format!("docfmt-create-dir-{}", std::process::id()));
+        let output = root.join("nested/dir/main.md");
+        let config = Config {
+            template: Some(PathBuf::from("tests/templates/main.hbs")),
+            output: output.clone(),
+            force: false,
+            follow: false,
+            strict: false,
+            verbose: 0,
+            include: vec![],
+            extensions: vec![],
+            datafiles: vec![],
+            data: serde_json::Value::Object(serde_json::Map::default()),
+            ..Default::default()
+        };
+
+        let content = "Hello World!".to_owned();
+        let success = config.write_output(content);
+        assert!(success);
+        let content = std::fs::read_to_string(&output);
+        assert!(content.is_ok());
+        assert_eq!(content.unwrap(), "Hello World!");
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn overwrite_source() {
+        let root = std::env::temp_dir().join(format!("docfmt-source-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let template = root.join("main.hbs");
+        std::fs::write(&template, "Template").unwrap();
+
+        let config = Config {
+            template: Some(template.clone()),
+            force: true,
+            ..Default::default()
+        };
+        let output = root.join(".").join("main.hbs");
+        assert!(!config.write_output_to(&output, "Output".to_owned()));
+        assert_eq!(std::fs::read_to_string(&template).unwrap(), "Template");
+
+        let config = Config {
+            template: None,
+            include: vec![root.clone()],
+            extensions: vec!["hbs".into()],
+            ..config
+        };
+        assert!(!config.write_output_to(&output, "Output".to_owned()));
+        assert!(config.write_output_to(&root.join("main.md"), "Output".to_owned()));
+
+        let config = Config {
+            allow_overwrite_source: true,
+            ..config
+        };
+        assert!(config.write_output_to(&output, "Output".to_owned()));
+        assert_eq!(std::fs::read_to_string(&template).unwrap(), "Output");
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn append_output() {
+        let root = std::env::temp_dir().join(format!("docfmt-append-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let output = root.join("CHANGELOG.md");
+        let config = Config {
+            append: true,
+            ..Default::default()
+        };
+
+        assert!(config.write_output_to(&output, "* First\n".to_owned()));
+        assert!(config.write_output_to(&output, "* Second".to_owned()));
+        assert!(config.write_output_to(&output, "* Third\n".to_owned()));
+        assert_eq!(
+            std::fs::read_to_string(&output).unwrap(),
+            "* First\n* Second\n* Third\n"
+        );
+
+        let config = Config {
+            line_ending: Some(LineEnding::Crlf),
+            ..config
+        };
+        std::fs::write(&output, "").unwrap();
+        assert!(config.write_output_to(&output, "* First".to_owned()));
+        assert!(config.write_output_to(&output, "* Second".to_owned()));
+        assert_eq!(
+            std::fs::read_to_string(&output).unwrap(),
+            "* First\r\n* Second"
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn check_links() {
+        let root = std::env::temp_dir().join(format!("docfmt-links-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/install.md"), "").unwrap();
+        let output = root.join("README.md");
+        std::fs::write(
+            &output,
+            concat!(
+                "[Install](docs/install.md#linux) [Home](https://example.com)\n",
+                "[Usage](#usage) ![Logo](img/logo.png)\n",
+            ),
+        )
+        .unwrap();
+
+        let config = Config::default();
+        assert!(!config.check_links());
+        assert!(!config.check_output_links(std::slice::from_ref(&output)));
+        std::fs::create_dir_all(root.join("img")).unwrap();
+        std::fs::write(root.join("img/logo.png"), "").unwrap();
+        assert!(config.check_output_links(std::slice::from_ref(&output)));
+        assert!(!config.check_output_links(&[root.join("missing.md")]));
+
+        let config = Config {
+            check_remote_links: true,
+            ..config
+        };
+        assert!(config.check_links());
+        #[cfg(not(feature = "http"))]
+        assert!(!config.check_output_links(&[output]));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn write_atomic() {
+        let root = std::env::temp_dir().join(format!("docfmt-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let output = root.join("main.md");
+        std::fs::write(&output, "Old content").unwrap();
+
+        assert!(Config::write_atomic(&output, b"New content").is_ok());
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "New content");
+
+        let entries = std::fs::read_dir(&root).unwrap().count();
+        assert_eq!(entries, 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let permissions = std::fs::Permissions::from_mode(0o750);
+            std::fs::set_permissions(&output, permissions).unwrap();
+            assert!(Config::write_atomic(&output, b"Newer content").is_ok());
+            let mode = std::fs::metadata(&output).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o750);
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn render_timeout() {
+        let matches =
+            crate::cli::get_cli().get_matches_from(["docfmt", "-c", "tests/config/render.toml"]);
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(config.timeout(), None);
+
+        let matches = crate::cli::get_cli().get_matches_from([
+            "docfmt",
+            "-c",
+            "tests/config/render.toml",
+            "--timeout",
+            "5",
+        ]);
+        let config = Config::try_from(matches).unwrap();
+        assert_eq!(config.timeout(), Some(Duration::from_secs(5)));
 
+        let result = crate::cli::get_cli().try_get_matches_from(["docfmt", "--timeout", "0"]);
+        assert!(result.is_err());
     }
 }
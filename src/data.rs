@@ -0,0 +1,220 @@
+//! Merging of the data read from front matter, configuration and data files.
+//!
+//! TOML datetimes are converted into strings in the RFC 3339 profile of ISO
+//! 8601, see [`from_toml`].
+
+use serde::Deserialize;
+use serde_json::Value;
+
+/// How arrays present on both sides of a merge are combined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeStrategy {
+    /// The incoming array replaces the existing one.
+    #[default]
+    Replace,
+    /// The incoming items are appended to the existing array.
+    Append,
+}
+
+impl MergeStrategy {
+    /// The strategy of the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "replace" => Some(MergeStrategy::Replace),
+            "append" => Some(MergeStrategy::Append),
+            _ => None,
+        }
+    }
+}
+
+/// Merge `b` into `a`.
+///
+/// Objects are merged recursively. A key whose incoming value is `null` is
+/// deleted from `a`, so a later data file can remove a key defined by an
+/// earlier one. Arrays are combined according to `strategy`, any other value
+/// of `b` replaces the value of `a`. Keys keep the position of their first
+/// occurrence, new keys are appended in the order of `b`.
+pub fn merge(a: &mut Value, b: Value, strategy: MergeStrategy) {
+    // CREDITS: https://stackoverflow.com/a/54118457
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (k, v) in b {
+                if v.is_null() {
+                    a.shift_remove(&k);
+                } else {
+                    merge(a.entry(k).or_insert(Value::Null), v, strategy);
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if strategy == MergeStrategy::Append => {
+            a.extend(b);
+        }
+        (a, b) => *a = b,
+    }
+}
+
+/// Whether merging `b` into `a` replaces a root of data of another kind, an
+/// object by an array or scalar or the other way round, which would silently
+/// discard data. An empty object, the default root, may be replaced.
+pub fn replaces_root(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Object(a), _) if a.is_empty() => false,
+        (Value::Object(_), Value::Object(_)) => false,
+        (Value::Null, _) => false,
+        (Value::Object(_), _) | (_, Value::Object(_)) => true,
+        _ => false,
+    }
+}
+
+/// Convert a TOML value into JSON. Datetimes become strings in the RFC 3339
+/// profile of ISO 8601, with `T` between date and time, keeping the parts
+/// the TOML value has, e.g. `1979-05-27T07:32:00Z`, `1979-05-27T07:32:00`,
+/// `1979-05-27` or `07:32:00`. Fractional seconds and offsets are kept as
+/// written. Floats that are not finite become `null`.
+pub fn from_toml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(from_toml).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, from_toml(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Nest `value` at the location of a JSON pointer, e.g. `/a/b` yields
+/// `{"a": {"b": value}}`. The escapes `~1` for `/` and `~0` for `~` are
+/// decoded. An empty pointer returns the value unchanged.
+pub fn nest(value: Value, pointer: &str) -> Value {
+    pointer
+        .split('/')
+        .skip(1)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .fold(value, |value, token| {
+            let key = token.replace("~1", "/").replace("~0", "~");
+            Value::Object([(key, value)].into_iter().collect())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merge_objects() {
+        let mut a = json!({"title": "A", "person": {"first": "John", "last": "Doe"}});
+        merge(
+            &mut a,
+            json!({"person": {"last": "Roe", "age": 42}, "city": "Paris"}),
+            MergeStrategy::Replace,
+        );
+        assert_eq!(
+            a,
+            json!({
+                "title": "A",
+                "person": {"first": "John", "last": "Roe", "age": 42},
+                "city": "Paris",
+            })
+        );
+        let keys = a.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(keys, ["title", "person", "city"]);
+    }
+
+    #[test]
+    fn merge_arrays() {
+        let mut a = json!({"tags": ["a", "b"]});
+        merge(&mut a, json!({"tags": ["c"]}), MergeStrategy::Replace);
+        assert_eq!(a, json!({"tags": ["c"]}));
+
+        merge(&mut a, json!({"tags": ["d"]}), MergeStrategy::Append);
+        assert_eq!(a, json!({"tags": ["c", "d"]}));
+
+        merge(&mut a, json!({"tags": "e"}), MergeStrategy::Append);
+        assert_eq!(a, json!({"tags": "e"}));
+    }
+
+    #[test]
+    fn merge_scalars() {
+        let mut a = json!({"count": 1, "nested": {"key": "value"}});
+        merge(
+            &mut a,
+            json!({"count": "one", "nested": 2}),
+            MergeStrategy::Replace,
+        );
+        assert_eq!(a, json!({"count": "one", "nested": 2}));
+
+        let mut a = json!("root");
+        merge(&mut a, json!({"key": "value"}), MergeStrategy::Replace);
+        assert_eq!(a, json!({"key": "value"}));
+    }
+
+    #[test]
+    fn replace_root() {
+        assert!(!replaces_root(&json!({}), &json!([1])));
+        assert!(!replaces_root(&json!(null), &json!("text")));
+        assert!(!replaces_root(&json!([1]), &json!([2])));
+        assert!(!replaces_root(&json!({"a": 1}), &json!({"b": 2})));
+        assert!(replaces_root(&json!({"a": 1}), &json!([1])));
+        assert!(replaces_root(&json!([1]), &json!({"a": 1})));
+    }
+
+    #[test]
+    fn toml_datetimes() {
+        let value = toml::from_str::<toml::Value>(concat!(
+            "offset = 1979-05-27T07:32:00Z\n",
+            "fraction = 1979-05-27 00:32:00.5-07:00\n",
+            "local = 1979-05-27T07:32:00\n",
+            "date = 1979-05-27\n",
+            "time = 07:32:00\n",
+            "nan = nan\n",
+            "[nested]\n",
+            "dates = [2024-04-04]\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            from_toml(value),
+            json!({
+                "offset": "1979-05-27T07:32:00Z",
+                "fraction": "1979-05-27T00:32:00.5-07:00",
+                "local": "1979-05-27T07:32:00",
+                "date": "1979-05-27",
+                "time": "07:32:00",
+                "nan": null,
+                "nested": {"dates": ["2024-04-04"]},
+            })
+        );
+    }
+
+    #[test]
+    fn nest_pointer() {
+        assert_eq!(nest(json!(1), ""), json!(1));
+        assert_eq!(nest(json!({"x": 1}), "/a/b"), json!({"a": {"b": {"x": 1}}}));
+        assert_eq!(nest(json!(1), "/a~1b/c~0d"), json!({"a/b": {"c~d": 1}}));
+    }
+
+    #[test]
+    fn merge_null_deletes() {
+        let mut a = json!({"keep": 1, "drop": 2, "nested": {"drop": 3, "keep": 4}});
+        merge(
+            &mut a,
+            json!({"drop": null, "nested": {"drop": null}, "missing": null}),
+            MergeStrategy::Replace,
+        );
+        assert_eq!(a, json!({"keep": 1, "nested": {"keep": 4}}));
+
+        let mut a = json!([1, 2]);
+        merge(&mut a, json!(null), MergeStrategy::Append);
+        assert_eq!(a, json!(null));
+    }
+}
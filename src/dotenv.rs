@@ -0,0 +1,125 @@
+//! Data files in dotenv format.
+//!
+//! Each line is a `KEY=value` pair, optionally preceded by `export`. Blank
+//! lines and lines starting with `#` are skipped. Values may be quoted with
+//! single or double quotes. Double-quoted values support the escapes `\n`,
+//! `\r`, `\t`, `\"` and `\\`, single-quoted values are taken literally. An
+//! unquoted value ends at a ` #` comment and is trimmed.
+
+use serde_json::{Map, Value};
+
+/// Parse dotenv content into a flat object of strings. Later definitions of
+/// a key replace earlier ones.
+pub fn parse(content: &str) -> Result<Value, DotenvError> {
+    let mut map = Map::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(DotenvError::MissingEquals(number));
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(DotenvError::MissingKey(number));
+        }
+
+        map.insert(key.to_owned(), Value::String(parse_value(value, number)?));
+    }
+    Ok(Value::Object(map))
+}
+
+fn parse_value(value: &str, number: usize) -> Result<String, DotenvError> {
+    let value = value.trim_start();
+
+    if let Some(rest) = value.strip_prefix('\'') {
+        let Some((value, _)) = rest.split_once('\'') else {
+            return Err(DotenvError::UnterminatedQuote(number));
+        };
+        return Ok(value.to_owned());
+    }
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut result = String::with_capacity(rest.len());
+        let mut chars = rest.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Ok(result),
+                '\\' => match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('r') => result.push('\r'),
+                    Some('t') => result.push('\t'),
+                    Some(c) => result.push(c),
+                    None => break,
+                },
+                c => result.push(c),
+            }
+        }
+        return Err(DotenvError::UnterminatedQuote(number));
+    }
+
+    let value = match value.find(" #") {
+        Some(index) => &value[..index],
+        None => value,
+    };
+    Ok(value.trim_end().to_owned())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DotenvError {
+    #[error("Missing `=` on line {0}")]
+    MissingEquals(usize),
+    #[error("Missing key on line {0}")]
+    MissingKey(usize),
+    #[error("Unterminated quote on line {0}")]
+    UnterminatedQuote(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_dotenv() {
+        let content = concat!(
+            "# Database\n",
+            "\n",
+            "HOST=localhost\n",
+            "export PORT = 5432 # default\n",
+            "NAME=\"docs \\\"db\\\"\\n\"\n",
+            "PATTERN='a\\nb # c'\n",
+            "EMPTY=\n",
+            "HOST=example.com\n",
+        );
+        assert_eq!(
+            parse(content).unwrap(),
+            json!({
+                "HOST": "example.com",
+                "PORT": "5432",
+                "NAME": "docs \"db\"\n",
+                "PATTERN": "a\\nb # c",
+                "EMPTY": "",
+            })
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(matches!(
+            parse("HOST=localhost\nPORT\n"),
+            Err(DotenvError::MissingEquals(2))
+        ));
+        assert!(matches!(parse("=value"), Err(DotenvError::MissingKey(1))));
+        assert!(matches!(
+            parse("NAME=\"docs"),
+            Err(DotenvError::UnterminatedQuote(1))
+        ));
+    }
+}
@@ -0,0 +1,60 @@
+use std::process::ExitCode;
+
+use crate::config::ConfigError;
+
+/// The classes of failures and their exit codes. The codes are stable and
+/// can be relied upon by scripts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Failure {
+    /// A template failed to register or to render.
+    Template = 1,
+    /// A data file failed to be read or parsed.
+    Data = 2,
+    /// The output file could not be written.
+    Output = 3,
+    /// The command line arguments or the configuration file are invalid.
+    Config = 4,
+    /// A warning was logged and warnings are treated as errors.
+    Warning = 5,
+    /// Rendering did not finish within the timeout.
+    Timeout = 6,
+}
+
+impl From<Failure> for ExitCode {
+    fn from(failure: Failure) -> Self {
+        ExitCode::from(failure as u8)
+    }
+}
+
+impl From<&ConfigError> for Failure {
+    fn from(err: &ConfigError) -> Self {
+        match err {
+            ConfigError::TemplateError(_) | ConfigError::RenderError { .. } => Failure::Template,
+            _ => Failure::Config,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(Failure::Template as u8, 1);
+        assert_eq!(Failure::Data as u8, 2);
+        assert_eq!(Failure::Output as u8, 3);
+        assert_eq!(Failure::Config as u8, 4);
+        assert_eq!(Failure::Warning as u8, 5);
+        assert_eq!(Failure::Timeout as u8, 6);
+
+        assert_eq!(
+            Failure::from(&ConfigError::MissingTemplate),
+            Failure::Config
+        );
+        assert_eq!(Failure::from(&ConfigError::MissingOutput), Failure::Config);
+        assert_eq!(ConfigError::MissingTemplate.code(), "MISSING_TEMPLATE");
+        assert_eq!(ConfigError::RenderConflict.code(), "RENDER_CONFLICT");
+    }
+}
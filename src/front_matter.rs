@@ -0,0 +1,131 @@
+//! Front matter at the top of the main template.
+//!
+//! A YAML front matter block is delimited by lines containing only `---`, a
+//! TOML front matter block by lines containing only `+++`. The opening
+//! delimiter must be the first line of the template.
+
+/// The format of a front matter block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Yaml,
+    Toml,
+}
+
+/// A front matter block split off a template.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FrontMatter<'a> {
+    pub format: Format,
+    pub content: &'a str,
+}
+
+impl FrontMatter<'_> {
+    /// Parse the front matter into data. An empty block yields `null`.
+    pub fn parse(&self) -> Result<serde_json::Value, FrontMatterError> {
+        if self.content.trim().is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+        match self.format {
+            Format::Yaml => serde_yaml::from_str(self.content).map_err(FrontMatterError::Yaml),
+            Format::Toml => Ok(crate::data::from_toml(toml::from_str(self.content)?)),
+        }
+    }
+}
+
+/// Split a leading front matter block off the template source. Returns the
+/// front matter, if any, and the remaining template body.
+pub fn split(source: &str) -> Result<(Option<FrontMatter<'_>>, &str), FrontMatterError> {
+    let (first, rest) = next_line(source);
+    let format = match first.trim_end() {
+        "---" => Format::Yaml,
+        "+++" => Format::Toml,
+        _ => return Ok((None, source)),
+    };
+    let delimiter = first.trim_end();
+    let mut remaining = rest;
+    let mut length = 0;
+
+    while !remaining.is_empty() {
+        let (line, next) = next_line(remaining);
+        if line.trim_end() == delimiter {
+            let front_matter = FrontMatter {
+                format,
+                content: &rest[..length],
+            };
+            return Ok((Some(front_matter), next));
+        }
+        length += remaining.len() - next.len();
+        remaining = next;
+    }
+    Err(FrontMatterError::Unterminated)
+}
+
+/// Split off the first line including its line break.
+fn next_line(source: &str) -> (&str, &str) {
+    match source.find('\n') {
+        Some(index) => (&source[..index], &source[index + 1..]),
+        None => (source, ""),
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum FrontMatterError {
+    #[error("Unterminated front matter")]
+    Unterminated,
+    #[error("Invalid YAML front matter: {0}")]
+    Yaml(serde_yaml::Error),
+    #[error("Invalid TOML front matter: {0}")]
+    Toml(#[from] toml::de::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn split_yaml() {
+        let (front_matter, body) = split("---\ntitle: Hello\n---\nBody\n").unwrap();
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.format, Format::Yaml);
+        assert_eq!(front_matter.parse().unwrap(), json!({"title": "Hello"}));
+        assert_eq!(body, "Body\n");
+    }
+
+    #[test]
+    fn split_toml() {
+        let (front_matter, body) = split("+++\r\ntitle = \"Hello\"\r\n+++\r\nBody").unwrap();
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.format, Format::Toml);
+        assert_eq!(front_matter.parse().unwrap(), json!({"title": "Hello"}));
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn split_without_front_matter() {
+        let (front_matter, body) = split("Body\n---\n").unwrap();
+        assert!(front_matter.is_none());
+        assert_eq!(body, "Body\n---\n");
+
+        let (front_matter, body) = split("").unwrap();
+        assert!(front_matter.is_none());
+        assert_eq!(body, "");
+    }
+
+    #[test]
+    fn split_invalid() {
+        assert!(matches!(
+            split("---\ntitle: Hello\nBody\n"),
+            Err(FrontMatterError::Unterminated)
+        ));
+
+        let (front_matter, _) = split("---\ntitle: [Hello\n---\nBody").unwrap();
+        assert!(front_matter.unwrap().parse().is_err());
+
+        let (front_matter, _) = split("---\n---\nBody").unwrap();
+        assert_eq!(
+            front_matter.unwrap().parse().unwrap(),
+            serde_json::Value::Null
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Graph of the templates included by the entry templates.
+//!
+//! Starting from the entry templates, the partials called with a literal name
+//! and the templates included with `include` are followed, as for
+//! `--report-unused`. Each template is a node with the file it was read from,
+//! its edges lead to the registered templates it includes. Partials that are
+//! not registered, like inline partials, are left out.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use handlebars::Handlebars;
+use serde::Serialize;
+
+use crate::references;
+
+/// The format of the graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// A directed graph in the DOT language of Graphviz.
+    #[default]
+    Dot,
+    Json,
+}
+
+impl GraphFormat {
+    /// The graph format of the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dot" => Some(GraphFormat::Dot),
+            "json" => Some(GraphFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A template of the graph.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Node {
+    /// The file the template was read from, if it is a file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// The names of the templates included, sorted.
+    pub partials: Vec<String>,
+}
+
+/// The templates included by the entry templates.
+#[derive(Debug, Serialize)]
+pub struct Graph {
+    pub entries: Vec<String>,
+    /// The templates reachable from the entries by their names.
+    pub templates: BTreeMap<String, Node>,
+}
+
+impl Graph {
+    /// Build the graph of the templates reachable from the `entries`. The
+    /// `sources` map the names of the templates to their files.
+    pub fn new(
+        registry: &Handlebars,
+        sources: &HashMap<String, PathBuf>,
+        entries: &[String],
+    ) -> Self {
+        let mut templates = BTreeMap::new();
+        let mut pending = entries.to_vec();
+
+        while let Some(name) = pending.pop() {
+            if templates.contains_key(&name) {
+                continue;
+            }
+            let Some(template) = registry.get_template(&name) else {
+                continue;
+            };
+            let partials = references::partials(template)
+                .into_iter()
+                .filter(|partial| registry.get_template(partial).is_some())
+                .collect::<Vec<_>>();
+            pending.extend(partials.iter().cloned());
+            let node = Node {
+                file: sources.get(&name).cloned(),
+                partials,
+            };
+            templates.insert(name, node);
+        }
+        Self {
+            entries: entries.to_vec(),
+            templates,
+        }
+    }
+
+    /// Format the graph in the given format, ending with a line break.
+    pub fn format(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Json => {
+                serde_json::to_string_pretty(self).expect("JSON is serializable") + "\n"
+            }
+        }
+    }
+
+    /// Format the graph in the DOT language, one statement per node and edge.
+    /// The entries are drawn as boxes.
+    fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph templates {\n");
+        for (name, node) in &self.templates {
+            if self.entries.contains(name) {
+                dot += &format!("    {} [shape=box];\n", quote(name));
+            } else {
+                dot += &format!("    {};\n", quote(name));
+            }
+            for partial in &node.partials {
+                dot += &format!("    {} -> {};\n", quote(name), quote(partial));
+            }
+        }
+        dot + "}\n"
+    }
+}
+
+/// Quote an identifier for the DOT language.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph() {
+        let mut registry = Handlebars::new();
+        crate::helpers::register(&mut registry);
+        for (name, source) in [
+            (
+                "main",
+                "{{> header}}{{> row}}{{#*inline \"row\"}}{{/inline}}",
+            ),
+            ("header", "{{include \"nav\"}}{{> missing}}"),
+            ("nav", "{{> header}}"),
+            ("old", ""),
+        ] {
+            registry.register_template_string(name, source).unwrap();
+        }
+        let sources = HashMap::from([("main".to_owned(), PathBuf::from("main.hbs"))]);
+
+        let graph = Graph::new(&registry, &sources, &["main".to_owned()]);
+        assert_eq!(
+            graph.templates.keys().collect::<Vec<_>>(),
+            ["header", "main", "nav"]
+        );
+        assert_eq!(
+            graph.format(GraphFormat::Dot),
+            concat!(
+                "digraph templates {\n",
+                "    \"header\";\n",
+                "    \"header\" -> \"nav\";\n",
+                "    \"main\" [shape=box];\n",
+                "    \"main\" -> \"header\";\n",
+                "    \"nav\";\n",
+                "    \"nav\" -> \"header\";\n",
+                "}\n"
+            )
+        );
+        let value = serde_json::from_str::<serde_json::Value>(&graph.format(GraphFormat::Json));
+        assert_eq!(
+            value.unwrap(),
+            serde_json::json!({
+                "entries": ["main"],
+                "templates": {
+                    "header": {"partials": ["nav"]},
+                    "main": {"file": "main.hbs", "partials": ["header"]},
+                    "nav": {"partials": ["header"]}
+                }
+            })
+        );
+        assert_eq!(quote("a \"b\""), "\"a \\\"b\\\"\"");
+    }
+}
@@ -0,0 +1,1849 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use handlebars::{
+    handlebars_helper, html_escape, BlockContext, Context, Handlebars, Helper, HelperDef,
+    HelperResult, JsonTruthy, Output, RenderContext, RenderError, RenderErrorReason, Renderable,
+    ScopedJson,
+};
+use log::{info, log_enabled, warn, Level};
+use sha2::{Digest, Sha256};
+
+use crate::data::{self, MergeStrategy};
+
+/// Register the built-in helpers with the registry. Returns their names.
+pub fn register(registry: &mut Handlebars) -> Vec<&'static str> {
+    let helpers: [(&'static str, Box<dyn HelperDef + Send + Sync>); 38] = [
+        ("slug", Box::new(slug)),
+        ("slugify", Box::new(slug)),
+        ("upper", Box::new(upper)),
+        ("lower", Box::new(lower)),
+        ("kebab", Box::new(kebab)),
+        ("snake", Box::new(snake)),
+        ("title_case", Box::new(title_case)),
+        ("base64", Box::new(base64_encode)),
+        ("sha256", Box::new(sha256)),
+        ("json", Box::new(Serialize { toml: false })),
+        ("toml", Box::new(Serialize { toml: true })),
+        ("heading", Box::new(heading)),
+        ("default", Box::new(DefaultValue)),
+        ("include", Box::new(Include)),
+        ("lookup_file", Box::new(LookupFile)),
+        ("debug", Box::new(Debug)),
+        ("format_number", Box::new(FormatNumber { currency: false })),
+        ("format_currency", Box::new(FormatNumber { currency: true })),
+        ("add", Box::new(Arithmetic::Add)),
+        ("sub", Box::new(Arithmetic::Sub)),
+        ("mul", Box::new(Arithmetic::Mul)),
+        ("div", Box::new(Arithmetic::Div)),
+        ("mod", Box::new(Arithmetic::Mod)),
+        ("len", Box::new(length)),
+        ("pluralize", Box::new(Plural::Word)),
+        ("count_of", Box::new(Plural::Count)),
+        ("first", Box::new(Element::First)),
+        ("last", Box::new(Element::Last)),
+        ("nth", Box::new(Element::Nth)),
+        ("eq", Box::new(Comparison::Eq)),
+        ("ne", Box::new(Comparison::Ne)),
+        ("lt", Box::new(Comparison::Lt)),
+        ("lte", Box::new(Comparison::Lte)),
+        ("gt", Box::new(Comparison::Gt)),
+        ("gte", Box::new(Comparison::Gte)),
+        ("and", Box::new(Logic::And)),
+        ("or", Box::new(Logic::Or)),
+        ("not", Box::new(Logic::Not)),
+    ];
+    helpers
+        .into_iter()
+        .map(|(name, helper)| {
+            registry.register_helper(name, helper);
+            name
+        })
+        .collect()
+}
+
+/// Convert a text into an anchor-safe slug.
+///
+/// Alphanumeric characters are lowercased and kept, whitespace, hyphens and
+/// underscores are collapsed into single hyphens, and all other characters are
+/// dropped. Leading and trailing hyphens are removed.
+pub fn to_slug(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut hyphen = false;
+
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if hyphen && !result.is_empty() {
+                result.push('-');
+            }
+            hyphen = false;
+            result.extend(c.to_lowercase());
+        } else if c.is_whitespace() || c == '-' || c == '_' {
+            hyphen = true;
+        }
+    }
+    result
+}
+
+handlebars_helper!(slug: |text: str| to_slug(text));
+
+/// Split a text into words at non-alphanumeric characters and at case
+/// changes, e.g. `parseHTTPResponse_code` yields `parse`, `HTTP`, `Response`
+/// and `code`.
+pub fn to_words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut chars = text.char_indices().peekable();
+    let mut previous: Option<char> = None;
+
+    while let Some((index, c)) = chars.next() {
+        if !c.is_alphanumeric() {
+            if let Some(start) = start.take() {
+                words.push(&text[start..index]);
+            }
+            previous = None;
+            continue;
+        }
+        // A new word starts at an uppercase letter following a lowercase
+        // letter or digit, or at the last uppercase letter of an acronym
+        // followed by a lowercase letter.
+        let boundary = match previous {
+            Some(p) if c.is_uppercase() => {
+                !p.is_uppercase() || chars.peek().is_some_and(|(_, next)| next.is_lowercase())
+            }
+            _ => false,
+        };
+        if boundary {
+            if let Some(start) = start {
+                words.push(&text[start..index]);
+            }
+            start = Some(index);
+        }
+        start.get_or_insert(index);
+        previous = Some(c);
+    }
+    if let Some(start) = start {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// Join the lowercased words of a text with a separator.
+fn join_lower(text: &str, separator: &str) -> String {
+    to_words(text)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Capitalize each word of a text and join the words with spaces.
+pub fn to_title_case(text: &str) -> String {
+    to_words(text)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| {
+                    first
+                        .to_uppercase()
+                        .chain(chars.flat_map(char::to_lowercase))
+                })
+                .into_iter()
+                .flatten()
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+handlebars_helper!(upper: |text: str| text.to_uppercase());
+handlebars_helper!(lower: |text: str| text.to_lowercase());
+handlebars_helper!(kebab: |text: str| join_lower(text, "-"));
+handlebars_helper!(snake: |text: str| join_lower(text, "_"));
+handlebars_helper!(title_case: |text: str| to_title_case(text));
+
+/// The lowercase hex SHA-256 digest of the bytes.
+pub fn to_sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+handlebars_helper!(base64_encode: |text: str| BASE64.encode(text));
+handlebars_helper!(sha256: |text: str| to_sha256(text.as_bytes()));
+
+/// Render an HTML heading with an id derived from its text.
+///
+/// `{{heading 2 "Installation"}}` renders as
+/// `<h2 id="installation">Installation</h2>`. The level is clamped to 1–6.
+fn heading(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let level = h
+        .param(0)
+        .and_then(|v| v.value().as_i64())
+        .ok_or(RenderErrorReason::ParamTypeMismatchForName(
+            "heading",
+            "0".to_owned(),
+            "number".to_owned(),
+        ))?
+        .clamp(1, 6);
+    let text = h.param(1).and_then(|v| v.value().as_str()).ok_or(
+        RenderErrorReason::ParamTypeMismatchForName("heading", "1".to_owned(), "string".to_owned()),
+    )?;
+
+    out.write(&format!(
+        "<h{level} id=\"{}\">{}</h{level}>",
+        to_slug(text),
+        html_escape(text)
+    ))?;
+    Ok(())
+}
+
+/// Return the value if it is present and not null, otherwise the fallback.
+///
+/// `{{default author.name "Anonymous"}}` does not fail in strict mode if
+/// `author.name` is missing, as the parameters are not checked.
+struct DefaultValue;
+
+impl HelperDef for DefaultValue {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let value = h
+            .param(0)
+            .map(|v| v.value())
+            .filter(|value| !value.is_null())
+            .or_else(|| h.param(1).map(|v| v.value()))
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(ScopedJson::Derived(value))
+    }
+}
+
+/// Serialize a value as pretty JSON or TOML.
+///
+/// `{{json settings}}` indents with two spaces, `indent=4` sets the width and
+/// `indent=0` writes compact JSON. `{{toml settings}}` requires a table. The
+/// result is escaped like any other value, use triple braces to insert it
+/// verbatim. A missing value is an error.
+struct Serialize {
+    toml: bool,
+}
+
+impl Serialize {
+    fn name(&self) -> &'static str {
+        if self.toml {
+            "toml"
+        } else {
+            "json"
+        }
+    }
+
+    fn to_json(value: &serde_json::Value, indent: usize) -> Result<String, serde_json::Error> {
+        if indent == 0 {
+            return serde_json::to_string(value);
+        }
+        let indent = " ".repeat(indent);
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        let mut content = Vec::new();
+        let mut serializer = serde_json::Serializer::with_formatter(&mut content, formatter);
+
+        serde::Serialize::serialize(value, &mut serializer)?;
+        Ok(String::from_utf8(content).expect("JSON is valid UTF-8"))
+    }
+}
+
+impl HelperDef for Serialize {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = self.name();
+        let param = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(name, 0))?;
+
+        if param.is_value_missing() {
+            return Err(RenderErrorReason::Other(format!(
+                "Helper {:?} got a missing value: {:?}",
+                name,
+                param.relative_path().map_or("", String::as_str)
+            ))
+            .into());
+        }
+        let content = if self.toml {
+            toml::to_string_pretty(param.value())
+                .map_err(|err| RenderErrorReason::Other(format!("Unable to write TOML: {}", err)))?
+        } else {
+            let indent = match h.hash_get("indent") {
+                Some(v) => v.value().as_u64().ok_or_else(|| {
+                    RenderErrorReason::HashTypeMismatchForName(
+                        name,
+                        "indent".to_owned(),
+                        "number".to_owned(),
+                    )
+                })? as usize,
+                None => 2,
+            };
+            Self::to_json(param.value(), indent)
+                .map_err(|err| RenderErrorReason::Other(format!("Unable to write JSON: {}", err)))?
+        };
+
+        Ok(ScopedJson::Derived(content.into()))
+    }
+}
+
+/// Format a number with optional grouping and a fixed number of decimals.
+///
+/// `{{format_number value thousands=true decimals=2}}` renders `1234.5` as
+/// `1,234.50`. The separators are set with `separator` and `point`. The value
+/// may be a number or a numeric string. Without `decimals` the number is
+/// written as is. `null` renders as an empty string, in strict mode it is an
+/// error.
+///
+/// `{{format_currency value symbol="€" symbol_after=true}}` groups thousands
+/// and uses two decimals by default. The symbol defaults to `$` and is put
+/// before the number unless `symbol_after` is set.
+struct FormatNumber {
+    currency: bool,
+}
+
+impl FormatNumber {
+    fn name(&self) -> &'static str {
+        if self.currency {
+            "format_currency"
+        } else {
+            "format_number"
+        }
+    }
+}
+
+impl HelperDef for FormatNumber {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = self.name();
+        let mismatch = || {
+            RenderErrorReason::ParamTypeMismatchForName(name, "0".to_owned(), "number".to_owned())
+        };
+        let number = match h.param(0).map(|v| v.value()) {
+            None | Some(serde_json::Value::Null) if r.strict_mode() => {
+                return Err(RenderErrorReason::Other(format!(
+                    "Helper {:?} got no value in strict mode",
+                    name
+                ))
+                .into());
+            }
+            None | Some(serde_json::Value::Null) => return Ok(ScopedJson::Derived("".into())),
+            Some(serde_json::Value::Number(number)) => number.to_string(),
+            Some(serde_json::Value::String(text)) => {
+                let text = text.trim();
+                text.parse::<f64>()
+                    .ok()
+                    .filter(|number| number.is_finite())
+                    .ok_or_else(mismatch)?;
+                text.to_owned()
+            }
+            Some(_) => return Err(mismatch().into()),
+        };
+
+        let hash_str = |key: &str, default: &'static str| {
+            h.hash_get(key)
+                .and_then(|v| v.value().as_str().map(str::to_owned))
+                .unwrap_or_else(|| default.to_owned())
+        };
+        let hash_bool = |key: &str, default: bool| {
+            h.hash_get(key)
+                .and_then(|v| v.value().as_bool())
+                .unwrap_or(default)
+        };
+        let decimals = match h.hash_get("decimals") {
+            Some(v) => Some(v.value().as_u64().ok_or_else(|| {
+                RenderErrorReason::HashTypeMismatchForName(
+                    name,
+                    "decimals".to_owned(),
+                    "number".to_owned(),
+                )
+            })?),
+            None if self.currency => Some(2),
+            None => None,
+        };
+        let separator = hash_bool("thousands", self.currency).then(|| hash_str("separator", ","));
+        let formatted = format_number(
+            &number,
+            decimals.map(|decimals| decimals.min(20) as usize),
+            separator.as_deref(),
+            &hash_str("point", "."),
+        );
+
+        let result = if self.currency {
+            let symbol = hash_str("symbol", "$");
+            if hash_bool("symbol_after", false) {
+                format!("{} {}", formatted, symbol)
+            } else if let Some(formatted) = formatted.strip_prefix('-') {
+                format!("-{}{}", symbol, formatted)
+            } else {
+                format!("{}{}", symbol, formatted)
+            }
+        } else {
+            formatted
+        };
+        Ok(ScopedJson::Derived(result.into()))
+    }
+}
+
+/// Format a numeric string with `decimals` fixed decimals, grouping the
+/// integer digits by three with `separator` and using `point` as the
+/// decimal point.
+pub fn format_number(
+    number: &str,
+    decimals: Option<usize>,
+    separator: Option<&str>,
+    point: &str,
+) -> String {
+    let text = match decimals {
+        Some(decimals) => {
+            let value = number.parse::<f64>().unwrap_or_default();
+            let text = format!("{:.*}", decimals, value);
+            // Values rounding to zero are not negative.
+            match text.strip_prefix('-') {
+                Some(rest) if rest.chars().all(|c| c == '0' || c == '.') => rest.to_owned(),
+                _ => text,
+            }
+        }
+        None if number
+            .strip_prefix('-')
+            .unwrap_or(number)
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.') =>
+        {
+            number.to_owned()
+        }
+        // Exponents and signs are written out in plain decimal notation.
+        None => number.parse::<f64>().unwrap_or_default().to_string(),
+    };
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text.as_str()),
+    };
+    let (integer, fraction) = match text.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (text, None),
+    };
+
+    let mut result = String::with_capacity(text.len() + integer.len() / 3 + 1);
+    result.push_str(sign);
+    for (index, c) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            result.push_str(separator.unwrap_or_default());
+        }
+        result.push(c);
+    }
+    if let Some(fraction) = fraction {
+        result.push_str(point);
+        result.push_str(fraction);
+    }
+    result
+}
+
+/// Arithmetic on two numbers.
+///
+/// `{{add a b}}`, `{{sub a b}}`, `{{mul a b}}`, `{{div a b}}` and
+/// `{{mod a b}}` take numbers or numeric strings. Two integers give an integer
+/// unless the result overflows, `div` only if the division is exact, e.g.
+/// `{{div 7 2}}` is `3.5`. A float operand gives a float. Division by zero and
+/// a `null` operand give `null`, which renders as an empty string, in strict
+/// mode they are an error.
+#[derive(Clone, Copy, Debug)]
+enum Arithmetic {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+impl Arithmetic {
+    fn name(self) -> &'static str {
+        match self {
+            Arithmetic::Add => "add",
+            Arithmetic::Sub => "sub",
+            Arithmetic::Mul => "mul",
+            Arithmetic::Div => "div",
+            Arithmetic::Mod => "mod",
+        }
+    }
+
+    /// The integer result, `None` if it overflows or is not an integer.
+    fn integer(self, a: i64, b: i64) -> Option<i64> {
+        match self {
+            Arithmetic::Add => a.checked_add(b),
+            Arithmetic::Sub => a.checked_sub(b),
+            Arithmetic::Mul => a.checked_mul(b),
+            Arithmetic::Div => a
+                .checked_rem(b)
+                .filter(|rem| *rem == 0)
+                .and_then(|_| a.checked_div(b)),
+            Arithmetic::Mod => a.checked_rem(b),
+        }
+    }
+
+    fn float(self, a: f64, b: f64) -> f64 {
+        match self {
+            Arithmetic::Add => a + b,
+            Arithmetic::Sub => a - b,
+            Arithmetic::Mul => a * b,
+            Arithmetic::Div => a / b,
+            Arithmetic::Mod => a % b,
+        }
+    }
+
+    /// Apply the operation, `None` for a division by zero or a result that is
+    /// not finite.
+    fn apply(self, a: &serde_json::Number, b: &serde_json::Number) -> Option<serde_json::Number> {
+        let float = |number: &serde_json::Number| number.as_f64().unwrap_or_default();
+        if matches!(self, Arithmetic::Div | Arithmetic::Mod) && float(b) == 0.0 {
+            return None;
+        }
+        if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+            if let Some(result) = self.integer(a, b) {
+                return Some(result.into());
+            }
+        }
+        serde_json::Number::from_f64(self.float(float(a), float(b)))
+    }
+}
+
+impl HelperDef for Arithmetic {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = self.name();
+        let mut operands = Vec::with_capacity(2);
+
+        for index in 0..2 {
+            let param = h
+                .param(index)
+                .ok_or(RenderErrorReason::ParamNotFoundForIndex(name, index))?;
+            let mismatch = || {
+                RenderErrorReason::ParamTypeMismatchForName(
+                    name,
+                    index.to_string(),
+                    "number".to_owned(),
+                )
+            };
+            let number = match param.value() {
+                serde_json::Value::Null => None,
+                serde_json::Value::Number(number) => Some(number.clone()),
+                serde_json::Value::String(text) => Some(parse_number(text).ok_or_else(mismatch)?),
+                _ => return Err(mismatch().into()),
+            };
+            operands.push(number);
+        }
+
+        let result = match (&operands[0], &operands[1]) {
+            (Some(a), Some(b)) => self.apply(a, b),
+            _ if r.strict_mode() => {
+                return Err(RenderErrorReason::Other(format!(
+                    "Helper {:?} got no value in strict mode",
+                    name
+                ))
+                .into());
+            }
+            _ => return Ok(ScopedJson::Derived(serde_json::Value::Null)),
+        };
+        match result {
+            Some(number) => Ok(ScopedJson::Derived(number.into())),
+            None if r.strict_mode() => Err(RenderErrorReason::Other(format!(
+                "Helper {:?} divided by zero or overflowed",
+                name
+            ))
+            .into()),
+            None => Ok(ScopedJson::Derived(serde_json::Value::Null)),
+        }
+    }
+}
+
+/// Parse a numeric string, ignoring surrounding whitespace. Integers stay
+/// integers, a float that is not finite is `None`.
+fn parse_number(text: &str) -> Option<serde_json::Number> {
+    let text = text.trim();
+    match text.parse::<i64>() {
+        Ok(number) => Some(number.into()),
+        Err(_) => text
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64),
+    }
+}
+
+// Replaces the built-in `len`, which counts the bytes of a string.
+handlebars_helper!(length: |value: Json| match value {
+    serde_json::Value::Array(items) => items.len(),
+    serde_json::Value::Object(map) => map.len(),
+    serde_json::Value::String(text) => text.chars().count(),
+    _ => 0,
+});
+
+/// The word form matching a count.
+///
+/// `{{pluralize count "item" "items"}}` returns the singular if the count is
+/// `1` or `-1` and the plural otherwise, also for zero and fractions. Without
+/// a plural form the singular with an `s` appended is used. `{{count_of count
+/// "item"}}` prefixes the count, e.g. `1 item` or `0 items`. The count may be
+/// a number or a numeric string. A `null` count gives `null`, in strict mode
+/// it is an error.
+#[derive(Clone, Copy, Debug)]
+enum Plural {
+    Word,
+    Count,
+}
+
+impl Plural {
+    fn name(self) -> &'static str {
+        match self {
+            Plural::Word => "pluralize",
+            Plural::Count => "count_of",
+        }
+    }
+}
+
+impl HelperDef for Plural {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = self.name();
+        let param = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(name, 0))?;
+        let mismatch = || {
+            RenderErrorReason::ParamTypeMismatchForName(name, "0".to_owned(), "number".to_owned())
+        };
+        let count = match param.value() {
+            serde_json::Value::Null if r.strict_mode() => {
+                return Err(RenderErrorReason::Other(format!(
+                    "Helper {:?} got no value in strict mode",
+                    name
+                ))
+                .into());
+            }
+            serde_json::Value::Null => return Ok(ScopedJson::Derived(serde_json::Value::Null)),
+            serde_json::Value::Number(number) => number.clone(),
+            serde_json::Value::String(text) => parse_number(text).ok_or_else(mismatch)?,
+            _ => return Err(mismatch().into()),
+        };
+        let word = |index: usize| {
+            h.param(index).and_then(|v| v.value().as_str()).ok_or(
+                RenderErrorReason::ParamTypeMismatchForName(
+                    name,
+                    index.to_string(),
+                    "string".to_owned(),
+                ),
+            )
+        };
+        let singular = word(1)?;
+
+        let word = if count.as_f64().is_some_and(|count| count.abs() == 1.0) {
+            singular.to_owned()
+        } else if h.param(2).is_some() {
+            word(2)?.to_owned()
+        } else {
+            format!("{}s", singular)
+        };
+        Ok(ScopedJson::Derived(
+            match self {
+                Plural::Word => word,
+                Plural::Count => format!("{} {}", count, word),
+            }
+            .into(),
+        ))
+    }
+}
+
+/// An element of an array, object or string.
+///
+/// `{{first items}}`, `{{last items}}` and `{{nth items 2}}` return the first,
+/// last or zero-based nth item of an array, value of an object in key order or
+/// character of a string. An element out of range and a `null` value give
+/// `null`, which renders as an empty string, in strict mode they are an error.
+#[derive(Clone, Copy, Debug)]
+enum Element {
+    First,
+    Last,
+    Nth,
+}
+
+impl Element {
+    fn name(self) -> &'static str {
+        match self {
+            Element::First => "first",
+            Element::Last => "last",
+            Element::Nth => "nth",
+        }
+    }
+
+    /// The index of the element among `len` elements, `None` if out of range.
+    fn index(self, len: usize, nth: usize) -> Option<usize> {
+        match self {
+            Element::First => Some(0),
+            Element::Last => len.checked_sub(1),
+            Element::Nth => Some(nth),
+        }
+        .filter(|index| *index < len)
+    }
+}
+
+impl HelperDef for Element {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = self.name();
+        let value = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(name, 0))?
+            .value();
+        let nth = match self {
+            Element::Nth => {
+                let param = h
+                    .param(1)
+                    .ok_or(RenderErrorReason::ParamNotFoundForIndex(name, 1))?;
+                let nth = match param.value() {
+                    serde_json::Value::String(text) => text.trim().parse::<u64>().ok(),
+                    value => value.as_u64(),
+                };
+                let nth = nth.ok_or_else(|| {
+                    RenderErrorReason::ParamTypeMismatchForName(
+                        name,
+                        "1".to_owned(),
+                        "number".to_owned(),
+                    )
+                })?;
+                usize::try_from(nth).unwrap_or(usize::MAX)
+            }
+            Element::First | Element::Last => 0,
+        };
+
+        let element = match value {
+            serde_json::Value::Null => None,
+            serde_json::Value::Array(items) => self
+                .index(items.len(), nth)
+                .map(|index| items[index].clone()),
+            serde_json::Value::Object(map) => self
+                .index(map.len(), nth)
+                .and_then(|index| map.values().nth(index))
+                .cloned(),
+            serde_json::Value::String(text) => self
+                .index(text.chars().count(), nth)
+                .and_then(|index| text.chars().nth(index))
+                .map(|c| c.to_string().into()),
+            _ => {
+                return Err(RenderErrorReason::ParamTypeMismatchForName(
+                    name,
+                    "0".to_owned(),
+                    "array, object or string".to_owned(),
+                )
+                .into());
+            }
+        };
+        match element {
+            Some(element) => Ok(ScopedJson::Derived(element)),
+            None if r.strict_mode() => Err(RenderErrorReason::Other(format!(
+                "Helper {:?} found no element at index {}",
+                name, nth
+            ))
+            .into()),
+            None => Ok(ScopedJson::Derived(serde_json::Value::Null)),
+        }
+    }
+}
+
+/// Comparison of two values, replacing the built-in helpers, which order
+/// integers only.
+///
+/// `{{#if (eq status "done")}}` and `{{#if (gt count 10)}}` compare by the
+/// following rules:
+///
+/// - Two strings are compared as strings, by Unicode code points, e.g.
+///   `(lt "10" "9")` is true.
+/// - Numbers are compared by value, `(eq 1 1.0)` is true. A number and a
+///   numeric string are compared as numbers like in the arithmetic helpers,
+///   `(eq 5 "5")` and `(lt "9" 10)` are true.
+/// - Other values are only equal if they are the same JSON value, arrays and
+///   objects are compared item by item. A missing value is `null`, so
+///   `(eq missing null)` is true.
+///
+/// `lt`, `lte`, `gt` and `gte` of values that are neither two strings nor
+/// two numbers, e.g. a `null` operand, are false, in strict mode they are an
+/// error.
+#[derive(Clone, Copy, Debug)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+impl Comparison {
+    fn name(self) -> &'static str {
+        match self {
+            Comparison::Eq => "eq",
+            Comparison::Ne => "ne",
+            Comparison::Lt => "lt",
+            Comparison::Lte => "lte",
+            Comparison::Gt => "gt",
+            Comparison::Gte => "gte",
+        }
+    }
+
+    /// The result of the comparison, `None` if the values can not be
+    /// ordered.
+    fn apply(self, a: &serde_json::Value, b: &serde_json::Value) -> Option<bool> {
+        let ordering = order(a, b);
+        match self {
+            Comparison::Eq => Some(ordering.map_or_else(|| a == b, Ordering::is_eq)),
+            Comparison::Ne => Some(ordering.map_or_else(|| a != b, Ordering::is_ne)),
+            Comparison::Lt => ordering.map(Ordering::is_lt),
+            Comparison::Lte => ordering.map(Ordering::is_le),
+            Comparison::Gt => ordering.map(Ordering::is_gt),
+            Comparison::Gte => ordering.map(Ordering::is_ge),
+        }
+    }
+}
+
+/// The order of two strings or two numbers, where a numeric string compared
+/// with a number counts as a number.
+fn order(a: &serde_json::Value, b: &serde_json::Value) -> Option<Ordering> {
+    let number = |value: &serde_json::Value| match value {
+        serde_json::Value::Number(number) => Some(number.clone()),
+        serde_json::Value::String(text) => parse_number(text),
+        _ => None,
+    };
+    match (a, b) {
+        (serde_json::Value::String(a), serde_json::Value::String(b)) => Some(a.cmp(b)),
+        (serde_json::Value::Number(_), _) | (_, serde_json::Value::Number(_)) => {
+            let (a, b) = (number(a)?, number(b)?);
+            match (a.as_i64(), b.as_i64(), a.as_u64(), b.as_u64()) {
+                (Some(a), Some(b), _, _) => Some(a.cmp(&b)),
+                (_, _, Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+            }
+        }
+        _ => None,
+    }
+}
+
+impl HelperDef for Comparison {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = self.name();
+        let a = h
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(name, 0))?
+            .value();
+        let b = h
+            .param(1)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(name, 1))?
+            .value();
+
+        match self.apply(a, b) {
+            Some(result) => Ok(ScopedJson::Derived(result.into())),
+            None if r.strict_mode() => Err(RenderErrorReason::Other(format!(
+                "Helper {:?} can not compare {} and {}",
+                name, a, b
+            ))
+            .into()),
+            None => Ok(ScopedJson::Derived(false.into())),
+        }
+    }
+}
+
+/// Boolean logic on the truthiness of values, replacing the built-in helpers,
+/// which take two values only.
+///
+/// `{{#if (and draft (not published))}}` and `(or a b c)` take any number of
+/// values, `not` one. A value is false like in `{{#if}}` if it is `false`,
+/// `null`, missing, `0`, an empty string, an empty array or an empty object.
+/// The result is `true` or `false`.
+#[derive(Clone, Copy, Debug)]
+enum Logic {
+    And,
+    Or,
+    Not,
+}
+
+impl Logic {
+    fn name(self) -> &'static str {
+        match self {
+            Logic::And => "and",
+            Logic::Or => "or",
+            Logic::Not => "not",
+        }
+    }
+}
+
+impl HelperDef for Logic {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = self.name();
+        if h.params().is_empty() {
+            return Err(RenderErrorReason::ParamNotFoundForIndex(name, 0).into());
+        }
+        let mut values = h.params().iter().map(|v| v.value().is_truthy(false));
+        let result = match self {
+            Logic::And => values.all(|value| value),
+            Logic::Or => values.any(|value| value),
+            Logic::Not => !h.param(0).is_some_and(|v| v.value().is_truthy(false)),
+        };
+        Ok(ScopedJson::Derived(result.into()))
+    }
+}
+
+/// Log the current context or a value as JSON and render nothing.
+///
+/// `{{debug}}` logs the context at its position, e.g. the current item inside
+/// `{{#each}}`, `{{debug author.name}}` logs the value of the path. The values
+/// are logged at info level, so they are only shown with `-v`, and are not
+/// serialized otherwise. A missing value is logged as such.
+struct Debug;
+
+impl HelperDef for Debug {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        _: &mut dyn Output,
+    ) -> HelperResult {
+        if !log_enabled!(Level::Info) {
+            return Ok(());
+        }
+        // Blocks like `{{#each}}` have no name of their own.
+        let template = rc
+            .get_current_template_name()
+            .or_else(|| rc.get_root_template_name())
+            .map_or("", String::as_str);
+        let (path, value) = match h.param(0) {
+            Some(param) => (
+                param
+                    .relative_path()
+                    .map_or_else(|| param.value().to_string(), String::to_owned),
+                (!param.is_value_missing()).then(|| param.value().to_string()),
+            ),
+            None => {
+                let value = rc.evaluate(ctx, "this")?;
+                ("this".to_owned(), Some(value.as_json().to_string()))
+            }
+        };
+
+        match value {
+            Some(value) => info!("Debug {:?} in {:?}: {}", path, template, value),
+            None => info!("Debug {:?} in {:?}: missing", path, template),
+        }
+        Ok(())
+    }
+}
+
+/// Render a partial if it is registered, otherwise the block.
+///
+/// `{{#include "intro"}}No introduction.{{/include}}` renders the partial
+/// `intro` with the current context. The block is rendered like any other
+/// block, so strict mode applies to it.
+struct Include;
+
+impl HelperDef for Include {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let name = h.param(0).and_then(|v| v.value().as_str()).ok_or(
+            RenderErrorReason::ParamTypeMismatchForName(
+                "include",
+                "0".to_owned(),
+                "string".to_owned(),
+            ),
+        )?;
+
+        match r.get_template(name) {
+            Some(template) => template.render(r, ctx, rc, out),
+            None => match h.template() {
+                Some(template) => template.render(r, ctx, rc, out),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// Render a template with its sidecar data merged over the context.
+///
+/// With `sidecar_data` enabled, an included template with a data file of the
+/// same stem next to it is wrapped in `{{#sidecar "name"}}...{{/sidecar}}`.
+/// The block is rendered with the data of `name` merged over the current
+/// context, so the keys of the sidecar data take precedence. Other values,
+/// like `@root`, are unchanged. Without sidecar data the block is rendered as
+/// is.
+pub struct Sidecar {
+    data: HashMap<String, serde_json::Value>,
+    strategy: MergeStrategy,
+}
+
+impl Sidecar {
+    pub fn new(data: HashMap<String, serde_json::Value>, strategy: MergeStrategy) -> Self {
+        Self { data, strategy }
+    }
+}
+
+impl HelperDef for Sidecar {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        rc: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let name = h.param(0).and_then(|v| v.value().as_str()).ok_or(
+            RenderErrorReason::ParamTypeMismatchForName(
+                "sidecar",
+                "0".to_owned(),
+                "string".to_owned(),
+            ),
+        )?;
+        let Some(template) = h.template() else {
+            return Ok(());
+        };
+        let Some(data) = self.data.get(name) else {
+            return template.render(r, ctx, rc, out);
+        };
+
+        let mut value = rc.evaluate(ctx, "this")?.as_json().clone();
+        data::merge(&mut value, data.clone(), self.strategy);
+        let mut block = BlockContext::new();
+        block.set_base_value(value);
+        rc.push_block(block);
+        let result = template.render(r, ctx, rc, out);
+        rc.pop_block();
+        result
+    }
+}
+
+/// The name of a registered template selected by a data value, for dynamic
+/// partials.
+///
+/// `{{> (lookup_file "status" state)}}` renders the partial `status/done` if
+/// `state` is `done`. The name is the directory, a slash and the value, which
+/// may be a string or a number, framed by the hash values `prefix` and
+/// `suffix`, e.g. `(lookup_file "snippets" state prefix="status-")` selects
+/// `snippets/status-done`. An empty directory adds no slash. If no template
+/// of that name is registered, or the value is `null`, the name of the
+/// `fallback`, by default `<dir>/default`, is returned. In strict mode the
+/// name is not replaced but an error.
+struct LookupFile;
+
+impl HelperDef for LookupFile {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = "lookup_file";
+        let dir = h.param(0).and_then(|v| v.value().as_str()).ok_or(
+            RenderErrorReason::ParamTypeMismatchForName(name, "0".to_owned(), "string".to_owned()),
+        )?;
+        let param = h
+            .param(1)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex(name, 1))?;
+        let key = match param.value() {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(key) => Some(key.to_owned()),
+            serde_json::Value::Number(key) => Some(key.to_string()),
+            _ => {
+                return Err(RenderErrorReason::ParamTypeMismatchForName(
+                    name,
+                    "1".to_owned(),
+                    "string or number".to_owned(),
+                )
+                .into());
+            }
+        };
+        let hash_str = |key: &str| h.hash_get(key).and_then(|v| v.value().as_str());
+        let prefix = if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", dir.trim_end_matches('/'))
+        };
+
+        let template = key.map(|key| {
+            format!(
+                "{}{}{}{}",
+                prefix,
+                hash_str("prefix").unwrap_or_default(),
+                key,
+                hash_str("suffix").unwrap_or_default()
+            )
+        });
+        match template {
+            Some(template) if r.get_template(&template).is_some() => {
+                Ok(ScopedJson::Derived(template.into()))
+            }
+            template if r.strict_mode() => Err(RenderErrorReason::Other(format!(
+                "Helper {:?} found no template for {:?}",
+                name,
+                template.unwrap_or_else(|| param.value().to_string())
+            ))
+            .into()),
+            _ => {
+                let fallback = hash_str("fallback")
+                    .map_or_else(|| format!("{}default", prefix), str::to_owned);
+                Ok(ScopedJson::Derived(fallback.into()))
+            }
+        }
+    }
+}
+
+/// Insert the contents of a file without compiling it as a template.
+///
+/// `{{include_raw "LICENSE"}}` reads the file relative to the base directory.
+/// The contents are escaped like any other value, use triple braces to insert
+/// them verbatim. Paths leaving the base directory are refused.
+pub struct IncludeRaw {
+    base: PathBuf,
+}
+
+impl IncludeRaw {
+    pub fn new(base: &Path) -> Self {
+        IncludeRaw {
+            base: base.to_owned(),
+        }
+    }
+
+    fn read(&self, name: &str) -> Result<String, String> {
+        let path = self.resolve(name)?;
+        std::fs::read_to_string(&path)
+            .map_err(|err| format!("Unable to read file {:?}: {}", name, err))
+    }
+
+    /// The canonical path of the file relative to the base directory.
+    fn resolve(&self, name: &str) -> Result<PathBuf, String> {
+        let base = self
+            .base
+            .canonicalize()
+            .map_err(|err| format!("Unable to read base directory {:?}: {}", self.base, err))?;
+        let path = base
+            .join(name)
+            .canonicalize()
+            .map_err(|err| format!("Unable to read file {:?}: {}", name, err))?;
+
+        if !path.starts_with(&base) {
+            return Err(format!("File {:?} is outside of {:?}", name, self.base));
+        }
+        Ok(path)
+    }
+}
+
+impl HelperDef for IncludeRaw {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = h.param(0).and_then(|v| v.value().as_str()).ok_or(
+            RenderErrorReason::ParamTypeMismatchForName(
+                "include_raw",
+                "0".to_owned(),
+                "string".to_owned(),
+            ),
+        )?;
+        let content = self.read(name).map_err(RenderErrorReason::Other)?;
+
+        Ok(ScopedJson::Derived(content.into()))
+    }
+}
+
+/// The SHA-256 digest of a file as lowercase hex.
+///
+/// `{{file_sha256 "assets/app.js"}}` reads the file relative to the base
+/// directory of `include_raw`, e.g. for cache-busting query strings. Paths
+/// leaving the base directory and missing files are errors.
+pub struct FileSha256 {
+    files: IncludeRaw,
+}
+
+impl FileSha256 {
+    pub fn new(base: &Path) -> Self {
+        FileSha256 {
+            files: IncludeRaw::new(base),
+        }
+    }
+}
+
+impl HelperDef for FileSha256 {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let name = h.param(0).and_then(|v| v.value().as_str()).ok_or(
+            RenderErrorReason::ParamTypeMismatchForName(
+                "file_sha256",
+                "0".to_owned(),
+                "string".to_owned(),
+            ),
+        )?;
+        let path = self.files.resolve(name).map_err(RenderErrorReason::Other)?;
+        let content = std::fs::read(&path).map_err(|err| {
+            RenderErrorReason::Other(format!("Unable to read file {:?}: {}", name, err))
+        })?;
+
+        Ok(ScopedJson::Derived(to_sha256(&content).into()))
+    }
+}
+
+/// Key of the translations in the data.
+pub const TRANSLATIONS_KEY: &str = "translations";
+
+/// Look up a translated string in the data.
+///
+/// `{{t "nav.home"}}` returns `translations.<locale>.nav.home` of the data,
+/// the key may also be a literal key of the locale containing dots. The
+/// locales are tried in order: the locale, given by the hash value `locale` or
+/// else the configured one, then its parents by dropping the last subtag,
+/// e.g. `de-AT` then `de`, then the fallback locale and its parents. Strings
+/// and numbers are translations. A missing translation is logged as a warning
+/// and renders the key, in strict mode it is an error.
+pub struct Translate {
+    locale: Option<String>,
+    fallback: Option<String>,
+}
+
+impl Translate {
+    pub fn new(locale: Option<&str>, fallback: Option<&str>) -> Self {
+        Translate {
+            locale: locale.map(str::to_owned),
+            fallback: fallback.map(str::to_owned),
+        }
+    }
+
+    /// The locales to try for the given locale, followed by the fallback, each
+    /// followed by its parents and without duplicates.
+    fn chain(&self, locale: Option<&str>) -> Vec<String> {
+        let mut chain = Vec::<String>::new();
+
+        for mut locale in [locale, self.fallback.as_deref()].into_iter().flatten() {
+            loop {
+                if !chain.iter().any(|known| known == locale) {
+                    chain.push(locale.to_owned());
+                }
+                match locale.rsplit_once(['-', '_']) {
+                    Some((parent, _)) => locale = parent,
+                    None => break,
+                }
+            }
+        }
+        chain
+    }
+
+    /// The translation of the key in the translations of one locale.
+    fn lookup(translations: &serde_json::Value, key: &str) -> Option<serde_json::Value> {
+        let value = translations.get(key).or_else(|| {
+            key.split('.')
+                .try_fold(translations, |value, part| value.get(part))
+        })?;
+        match value {
+            serde_json::Value::String(_) | serde_json::Value::Number(_) => Some(value.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+impl HelperDef for Translate {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        r: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let key = h.param(0).and_then(|v| v.value().as_str()).ok_or(
+            RenderErrorReason::ParamTypeMismatchForName("t", "0".to_owned(), "string".to_owned()),
+        )?;
+        let locale = h
+            .hash_get("locale")
+            .and_then(|v| v.value().as_str())
+            .or(self.locale.as_deref());
+        let chain = self.chain(locale);
+
+        let translations = ctx.data().get(TRANSLATIONS_KEY);
+        let translation = chain.iter().find_map(|locale| {
+            translations
+                .and_then(|translations| translations.get(locale))
+                .and_then(|translations| Self::lookup(translations, key))
+        });
+        match translation {
+            Some(translation) => Ok(ScopedJson::Derived(translation)),
+            None if r.strict_mode() => Err(RenderErrorReason::Other(format!(
+                "Missing translation {:?} for locales {:?}",
+                key, chain
+            ))
+            .into()),
+            None => {
+                warn!("Missing translation {:?} for locales {:?}", key, chain);
+                Ok(ScopedJson::Derived(key.into()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn slug_text() {
+        assert_eq!(to_slug("Installation"), "installation");
+        assert_eq!(to_slug("  Getting   started! "), "getting-started");
+        assert_eq!(to_slug("snake_case and-kebab"), "snake-case-and-kebab");
+        assert_eq!(to_slug(""), "");
+        assert_eq!(to_slug("Café Crème Brûlée"), "café-crème-brûlée");
+    }
+
+    #[test]
+    fn case_conversion() {
+        assert_eq!(
+            to_words("parseHTTPResponse_code v2"),
+            ["parse", "HTTP", "Response", "code", "v2"]
+        );
+        assert!(to_words("").is_empty());
+        assert!(to_words(" -_ ").is_empty());
+
+        let mut registry = Handlebars::new();
+        register(&mut registry);
+        let data = json!({"title": "Ärger über Straße", "name": "userID count", "empty": ""});
+        let render = |template: &str| registry.render_template(template, &data).unwrap();
+
+        assert_eq!(render("{{slugify title}}"), "ärger-über-straße");
+        assert_eq!(render("{{upper title}}"), "ÄRGER ÜBER STRASSE");
+        assert_eq!(render("{{lower title}}"), "ärger über straße");
+        assert_eq!(render("{{kebab name}}"), "user-id-count");
+        assert_eq!(render("{{snake name}}"), "user_id_count");
+        assert_eq!(render("{{title_case name}}"), "User Id Count");
+        assert_eq!(render("{{title_case \"élan vital\"}}"), "Élan Vital");
+        for helper in ["slugify", "upper", "lower", "kebab", "snake", "title_case"] {
+            assert_eq!(render(&format!("{{{{{helper} empty}}}}")), "");
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut registry = Handlebars::new();
+        register(&mut registry);
+        let data =
+            json!({"a": 7, "b": 2, "f": 1.5, "text": " 3 ", "zero": 0, "none": null, "list": [1]});
+        let render = |template: &str| registry.render_template(template, &data);
+
+        assert_eq!(render("{{add a b}}").unwrap(), "9");
+        assert_eq!(render("{{sub b a}}").unwrap(), "-5");
+        assert_eq!(render("{{mul a b}}").unwrap(), "14");
+        assert_eq!(render("{{div a b}}").unwrap(), "3.5");
+        assert_eq!(render("{{div 8 b}}").unwrap(), "4");
+        assert_eq!(render("{{mod a b}}").unwrap(), "1");
+        assert_eq!(render("{{add a f}}").unwrap(), "8.5");
+        assert_eq!(render("{{mul f b}}").unwrap(), "3.0");
+        assert_eq!(render("{{mod a f}}").unwrap(), "1.0");
+        assert_eq!(render("{{add text a}}").unwrap(), "10");
+        assert_eq!(render("{{add (mul a b) 1}}").unwrap(), "15");
+        assert_eq!(
+            render("{{add 9223372036854775807 1}}").unwrap(),
+            "9.223372036854776e+18"
+        );
+        assert_eq!(render("{{div a zero}}").unwrap(), "");
+        assert_eq!(render("{{mod a 0.0}}").unwrap(), "");
+        assert_eq!(render("{{add a none}}").unwrap(), "");
+        assert!(render("{{add a}}").is_err());
+        assert!(render(r#"{{add a "abc"}}"#).is_err());
+        assert!(render("{{add a list}}").is_err());
+
+        registry.set_strict_mode(true);
+        let render = |template: &str| registry.render_template(template, &data);
+        assert_eq!(render("{{div 1 4}}").unwrap(), "0.25");
+        assert!(render("{{div a zero}}").is_err());
+        assert!(render("{{add a none}}").is_err());
+    }
+
+    #[test]
+    fn lookup_file() {
+        let mut registry = Handlebars::new();
+        register(&mut registry);
+        for (name, content) in [
+            ("status/done", "Done"),
+            ("status/open", "Open"),
+            ("status/blocked", "Blocked by {{reason}}"),
+            ("status/default", "Unknown"),
+            ("status/icon-404", "Missing"),
+        ] {
+            registry.register_template_string(name, content).unwrap();
+        }
+        let template = r#"{{#each items}}{{> (lookup_file "status" state)}};{{/each}}"#;
+        let data = json!({"items": [
+            {"state": "done"},
+            {"state": "blocked", "reason": "review"},
+            {"state": "open"},
+            {"state": "archived"},
+            {},
+        ]});
+        assert_eq!(
+            registry.render_template(template, &data).unwrap(),
+            "Done;Blocked by review;Open;Unknown;Unknown;"
+        );
+
+        let render = |template: &str| registry.render_template(template, &json!({"code": 404}));
+        assert_eq!(
+            render(r#"{{lookup_file "status/" code prefix="icon-"}}"#).unwrap(),
+            "status/icon-404"
+        );
+        assert_eq!(
+            render(r#"{{lookup_file "" "x" fallback="status/open"}}"#).unwrap(),
+            "status/open"
+        );
+        assert_eq!(render(r#"{{lookup_file "" "x"}}"#).unwrap(), "default");
+        assert!(render(r#"{{lookup_file "status" this}}"#).is_err());
+        assert!(render(r#"{{lookup_file "status"}}"#).is_err());
+
+        registry.set_strict_mode(true);
+        let render = |template: &str| registry.render_template(template, &json!({"state": "x"}));
+        assert_eq!(
+            render(r#"{{lookup_file "status" "done"}}"#).unwrap(),
+            "status/done"
+        );
+        assert!(render(r#"{{lookup_file "status" state fallback="status/done"}}"#).is_err());
+    }
+
+    #[test]
+    fn include_raw() {
+        let mut registry = Handlebars::new();
+        registry.register_helper("include_raw", Box::new(IncludeRaw::new(Path::new("tests"))));
+
+        let content =
+            registry.render_template(r#"{{{include_raw "helpers/double.rhai"}}}"#, &json!({}));
+        assert_eq!(
+            content.unwrap(),
+            std::fs::read_to_string("tests/helpers/double.rhai").unwrap()
+        );
+        let content = registry.render_template(r#"{{include_raw "missing.txt"}}"#, &json!({}));
+        assert!(content.is_err());
+        let content = registry.render_template(r#"{{include_raw "../Cargo.toml"}}"#, &json!({}));
+        assert!(content.is_err());
+    }
+
+    #[test]
+    fn default_value() {
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        register(&mut registry);
+
+        let data = json!({"deeply": {"nested": {"key": "value"}}, "empty": null});
+        let content = registry.render_template(r#"{{default deeply.nested.key "n/a"}}"#, &data);
+        assert_eq!(content.unwrap(), "value");
+        let content = registry.render_template(r#"{{default deeply.missing.key "n/a"}}"#, &data);
+        assert_eq!(content.unwrap(), "n/a");
+        let content = registry.render_template(r#"{{default empty "n/a"}}"#, &data);
+        assert_eq!(content.unwrap(), "n/a");
+        let content =
+            registry.render_template(r#"{{slug (default title "Getting started")}}"#, &data);
+        assert_eq!(content.unwrap(), "getting-started");
+    }
+
+    #[test]
+    fn elements() {
+        let mut registry = Handlebars::new();
+        register(&mut registry);
+        let data = json!({
+            "items": ["a", "b", "c"],
+            "map": {"x": 1, "y": 2},
+            "text": "Äbc",
+            "empty": [],
+            "none": null,
+            "number": 3,
+            "nested": [[1, 2], [3]],
+        });
+        let render = |template: &str| registry.render_template(template, &data);
+
+        assert_eq!(render("{{len items}}").unwrap(), "3");
+        assert_eq!(render("{{len map}}").unwrap(), "2");
+        assert_eq!(render("{{len text}}").unwrap(), "3");
+        assert_eq!(render("{{len none}}").unwrap(), "0");
+        assert_eq!(render("{{first items}}{{last items}}").unwrap(), "ac");
+        assert_eq!(render("{{first map}}{{last map}}").unwrap(), "12");
+        assert_eq!(render("{{first text}}{{last text}}").unwrap(), "Äc");
+        assert_eq!(
+            render("{{nth items 1}}{{nth map 1}}{{nth text 1}}").unwrap(),
+            "b2b"
+        );
+        assert_eq!(render(r#"{{nth items "2"}}"#).unwrap(), "c");
+        assert_eq!(render("{{nth items 3}}").unwrap(), "");
+        assert_eq!(render("{{first empty}}{{last empty}}").unwrap(), "");
+        assert_eq!(render("{{last none}}").unwrap(), "");
+        assert_eq!(render("{{len (first nested)}}").unwrap(), "2");
+        assert!(render("{{first number}}").is_err());
+        assert!(render("{{nth items -1}}").is_err());
+        assert!(render("{{nth items}}").is_err());
+
+        registry.set_strict_mode(true);
+        let render = |template: &str| registry.render_template(template, &data);
+        assert_eq!(render("{{nth items 2}}").unwrap(), "c");
+        assert!(render("{{nth items 3}}").is_err());
+        assert!(render("{{last empty}}").is_err());
+        assert!(render("{{first none}}").is_err());
+    }
+
+    #[test]
+    fn plurals() {
+        let mut registry = Handlebars::new();
+        register(&mut registry);
+        let data = json!({"one": 1, "none": null, "text": "1", "half": 0.5});
+        let render = |template: &str| registry.render_template(template, &data);
+
+        assert_eq!(
+            render(r#"{{pluralize one "item" "items"}}"#).unwrap(),
+            "item"
+        );
+        assert_eq!(render(r#"{{pluralize 2 "item"}}"#).unwrap(), "items");
+        assert_eq!(
+            render(r#"{{pluralize 0 "entry" "entries"}}"#).unwrap(),
+            "entries"
+        );
+        assert_eq!(render(r#"{{pluralize -1 "item"}}"#).unwrap(), "item");
+        assert_eq!(render(r#"{{pluralize -3 "item"}}"#).unwrap(), "items");
+        assert_eq!(render(r#"{{pluralize half "mile"}}"#).unwrap(), "miles");
+        assert_eq!(render(r#"{{pluralize text "item"}}"#).unwrap(), "item");
+        assert_eq!(render(r#"{{count_of one "item"}}"#).unwrap(), "1 item");
+        assert_eq!(render(r#"{{count_of 0 "item"}}"#).unwrap(), "0 items");
+        assert_eq!(
+            render(r#"{{count_of 3 "child" "children"}}"#).unwrap(),
+            "3 children"
+        );
+        assert_eq!(render(r#"{{count_of none "item"}}"#).unwrap(), "");
+        assert!(render(r#"{{pluralize "many" "item"}}"#).is_err());
+        assert!(render("{{pluralize 2}}").is_err());
+        assert!(render(r#"{{pluralize 2 "item" 3}}"#).is_err());
+
+        registry.set_strict_mode(true);
+        let render = |template: &str| registry.render_template(template, &data);
+        assert!(render(r#"{{count_of none "item"}}"#).is_err());
+    }
+
+    #[test]
+    fn translations() {
+        let mut registry = Handlebars::new();
+        registry.register_helper("t", Box::new(Translate::new(Some("de-AT"), Some("en"))));
+        let data = json!({
+            "translations": {
+                "en": {"nav": {"home": "Home", "about": "About"}, "count": 3, "a.b": "Dotted"},
+                "de": {"nav": {"home": "Startseite"}},
+                "de-AT": {"greeting": "Servus"},
+                "fr": {"nav": {"home": "Accueil"}},
+            },
+        });
+        let render = |template: &str| registry.render_template(template, &data);
+
+        assert_eq!(render(r#"{{t "greeting"}}"#).unwrap(), "Servus");
+        assert_eq!(render(r#"{{t "nav.home"}}"#).unwrap(), "Startseite");
+        assert_eq!(render(r#"{{t "nav.about"}}"#).unwrap(), "About");
+        assert_eq!(render(r#"{{t "count"}}"#).unwrap(), "3");
+        assert_eq!(render(r#"{{t "a.b"}}"#).unwrap(), "Dotted");
+        assert_eq!(
+            render(r#"{{t "nav.home" locale="fr"}}"#).unwrap(),
+            "Accueil"
+        );
+        assert_eq!(render(r#"{{t "nav.missing"}}"#).unwrap(), "nav.missing");
+        assert_eq!(render(r#"{{t "nav"}}"#).unwrap(), "nav");
+        assert!(render("{{t}}").is_err());
+        assert_eq!(
+            Translate::new(Some("de_CH"), Some("en-US")).chain(Some("de_CH")),
+            ["de_CH", "de", "en-US", "en"]
+        );
+        assert_eq!(Translate::new(None, None).chain(None), Vec::<String>::new());
+
+        registry.set_strict_mode(true);
+        let render = |template: &str| registry.render_template(template, &data);
+        assert_eq!(render(r#"{{t "nav.home"}}"#).unwrap(), "Startseite");
+        let err = render(r#"{{t "nav.missing"}}"#).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains(r#"Missing translation "nav.missing" for locales ["de-AT", "de", "en"]"#));
+    }
+
+    #[test]
+    fn comparisons() {
+        let mut registry = Handlebars::new();
+        register(&mut registry);
+        let data = json!({
+            "status": "done",
+            "count": 12,
+            "price": 9.5,
+            "text": " 5 ",
+            "none": null,
+            "tags": ["a", "b"],
+        });
+        let render = |template: &str| registry.render_template(template, &data);
+        let test = |condition: &str| {
+            render(&format!(
+                "{{{{#if {condition}}}}}yes{{{{else}}}}no{{{{/if}}}}"
+            ))
+            .unwrap()
+        };
+
+        assert_eq!(test(r#"(eq status "done")"#), "yes");
+        assert_eq!(test(r#"(ne status "Done")"#), "yes");
+        assert_eq!(test("(eq 1 1.0)"), "yes");
+        assert_eq!(test("(eq text 5)"), "yes");
+        assert_eq!(test(r#"(eq text "5")"#), "no");
+        assert_eq!(test("(eq tags tags)"), "yes");
+        assert_eq!(test("(eq none null)"), "yes");
+        assert_eq!(test("(eq missing null)"), "yes");
+        assert_eq!(test("(eq none 0)"), "no");
+        assert_eq!(test("(eq true true)"), "yes");
+        assert_eq!(test("(gt count 10)"), "yes");
+        assert_eq!(test("(lt price 10)"), "yes");
+        assert_eq!(test("(lte count 12.0)"), "yes");
+        assert_eq!(test("(gte price count)"), "no");
+        assert_eq!(test(r#"(lt "10" "9")"#), "yes");
+        assert_eq!(test(r#"(lt "9" 10)"#), "yes");
+        assert_eq!(test("(gt 18446744073709551615 9223372036854775807)"), "yes");
+        assert_eq!(test("(lt none 1)"), "no");
+        assert_eq!(test("(gte none 1)"), "no");
+        assert_eq!(test("(lt tags 1)"), "no");
+        assert_eq!(render("{{eq count 12}}").unwrap(), "true");
+        assert!(render("{{eq count}}").is_err());
+
+        assert_eq!(test(r#"(and status count "x")"#), "yes");
+        assert_eq!(test("(and status 0)"), "no");
+        assert_eq!(test("(or none \"\" tags)"), "yes");
+        assert_eq!(test("(or none missing)"), "no");
+        assert_eq!(test("(not (eq status \"done\"))"), "no");
+        assert_eq!(test("(not none)"), "yes");
+        assert_eq!(render("{{or 0 none}}").unwrap(), "false");
+        assert!(render("{{and}}").is_err());
+
+        registry.set_strict_mode(true);
+        let render = |template: &str| registry.render_template(template, &data);
+        assert_eq!(render("{{lt count 20}}").unwrap(), "true");
+        assert!(render("{{lt none 1}}").is_err());
+        assert!(render("{{gt tags status}}").is_err());
+    }
+
+    #[test]
+    fn debug_context() {
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        register(&mut registry);
+
+        let data = json!({"items": [{"name": "a"}, {"name": "b"}]});
+        let content = registry.render_template(
+            "{{debug}}{{#each items}}{{debug}}{{debug name}}{{name}}{{/each}}{{debug missing}}",
+            &data,
+        );
+        assert_eq!(content.unwrap(), "ab");
+    }
+
+    #[test]
+    fn include_block() {
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        register(&mut registry);
+        registry
+            .register_template_string("intro", "Hello {{name}}!")
+            .unwrap();
+
+        let data = json!({"name": "World"});
+        let content =
+            registry.render_template(r#"{{#include "intro"}}Fallback{{/include}}"#, &data);
+        assert_eq!(content.unwrap(), "Hello World!");
+        let content =
+            registry.render_template(r#"{{#include "outro"}}Bye {{name}}{{/include}}"#, &data);
+        assert_eq!(content.unwrap(), "Bye World");
+        let content =
+            registry.render_template(r#"{{#include "outro"}}{{missing}}{{/include}}"#, &data);
+        assert!(content.is_err());
+    }
+
+    #[test]
+    fn base64_digests() {
+        let mut registry = Handlebars::new();
+        register(&mut registry);
+        registry.register_helper("file_sha256", Box::new(FileSha256::new(Path::new("tests"))));
+        let data = json!({"text": "abc", "empty": ""});
+        let render = |template: &str| registry.render_template(template, &data);
+
+        assert_eq!(render("{{base64 text}}").unwrap(), "YWJj");
+        assert_eq!(render("{{base64 empty}}").unwrap(), "");
+        assert_eq!(
+            render("{{sha256 text}}").unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            render("{{sha256 empty}}").unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            render(r#"{{file_sha256 "helpers/double.rhai"}}"#).unwrap(),
+            "695e467ea683cb218ea388ff4ccac756e7b9608c2f08302eac8418251f0cbd1c"
+        );
+        assert!(render(r#"{{file_sha256 "missing.txt"}}"#).is_err());
+        assert!(render(r#"{{file_sha256 "../Cargo.toml"}}"#).is_err());
+    }
+
+    #[test]
+    fn serialize_values() {
+        let mut registry = Handlebars::new();
+        registry.register_escape_fn(handlebars::no_escape);
+        register(&mut registry);
+        let data = json!({
+            "settings": {"name": "docs", "port": 8080, "tags": ["a"], "db": {"host": "local"}},
+            "list": [1, 2],
+            "empty": null,
+        });
+        let render = |template: &str| registry.render_template(template, &data);
+
+        assert_eq!(
+            render("{{json settings.db}}").unwrap(),
+            "{\n  \"host\": \"local\"\n}"
+        );
+        assert_eq!(
+            render("{{json settings.db indent=4}}").unwrap(),
+            "{\n    \"host\": \"local\"\n}"
+        );
+        assert_eq!(render("{{json list indent=0}}").unwrap(), "[1,2]");
+        assert_eq!(render("{{json empty}}").unwrap(), "null");
+        assert_eq!(
+            render("{{toml settings}}").unwrap(),
+            "name = \"docs\"\nport = 8080\ntags = [\"a\"]\n\n[db]\nhost = \"local\"\n"
+        );
+        assert!(render("{{toml list}}").is_err());
+        assert!(render("{{json missing}}").is_err());
+        assert!(render("{{toml settings.missing}}").is_err());
+
+        let mut registry = Handlebars::new();
+        register(&mut registry);
+        let content = registry.render_template("{{json this}}", &json!({"a": "<b>"}));
+        assert_eq!(
+            content.unwrap(),
+            "{\n  &quot;a&quot;: &quot;&lt;b&gt;&quot;\n}"
+        );
+    }
+
+    #[test]
+    fn format_numbers() {
+        assert_eq!(
+            format_number("1234567.891", Some(2), Some(","), "."),
+            "1,234,567.89"
+        );
+        assert_eq!(format_number("-1234", None, Some("."), ","), "-1.234");
+        assert_eq!(format_number("123", None, Some(","), "."), "123");
+        assert_eq!(format_number("0.5", Some(0), None, "."), "0");
+        assert_eq!(format_number("-0.001", Some(2), None, "."), "0.00");
+        assert_eq!(format_number("1234.5", None, None, ","), "1234,5");
+        assert_eq!(format_number("+1.5e4", None, Some(","), "."), "15,000");
+
+        let mut registry = Handlebars::new();
+        register(&mut registry);
+
+        let data = json!({"amount": 1234.5, "text": "-98765", "none": null, "word": "abc"});
+        let content =
+            registry.render_template("{{format_number amount thousands=true decimals=2}}", &data);
+        assert_eq!(content.unwrap(), "1,234.50");
+        let content = registry.render_template("{{format_number text thousands=true}}", &data);
+        assert_eq!(content.unwrap(), "-98,765");
+        let content = registry.render_template("{{format_currency amount}}", &data);
+        assert_eq!(content.unwrap(), "$1,234.50");
+        let content = registry.render_template(
+            r#"{{format_currency text symbol="€" symbol_after=true separator="." point=","}}"#,
+            &data,
+        );
+        assert_eq!(content.unwrap(), "-98.765,00 €");
+        let content = registry.render_template("{{format_number none}}", &data);
+        assert_eq!(content.unwrap(), "");
+        let content = registry.render_template("{{format_number word}}", &data);
+        assert!(content.is_err());
+
+        registry.set_strict_mode(true);
+        let content = registry.render_template("{{format_number none}}", &data);
+        assert!(content.is_err());
+    }
+
+    #[test]
+    fn heading_id() {
+        let mut registry = Handlebars::new();
+        register(&mut registry);
+
+        let content = registry.render_template(r#"{{heading 2 "Installation"}}"#, &json!({}));
+        assert_eq!(
+            content.unwrap(),
+            r#"<h2 id="installation">Installation</h2>"#
+        );
+
+        let content = registry.render_template(r#"{{heading 9 "Q & A"}}"#, &json!({}));
+        assert_eq!(content.unwrap(), r#"<h6 id="q-a">Q &amp; A</h6>"#);
+
+        let content =
+            registry.render_template(r#"{{heading 0 title}}"#, &json!({"title": "Usage"}));
+        assert_eq!(content.unwrap(), r#"<h1 id="usage">Usage</h1>"#);
+    }
+}
@@ -0,0 +1,68 @@
+//! Data files fetched over HTTP.
+//!
+//! Only available with the feature `http`. A response with an error status is
+//! an error, as is a request not completed within the timeout.
+
+use std::time::Duration;
+
+use ureq::Agent;
+
+/// The body of a response and the media type of its `Content-Type` header,
+/// e.g. `application/json`.
+pub struct Response {
+    pub body: String,
+    pub mime_type: Option<String>,
+}
+
+/// Fetch a URL with a GET request.
+pub fn get(url: &str, timeout: Duration) -> Result<Response, HttpError> {
+    let error = |err| HttpError::new(err, timeout);
+
+    let mut response = agent(timeout).get(url).call().map_err(error)?;
+    let body = response.body_mut();
+    Ok(Response {
+        mime_type: body.mime_type().map(str::to_ascii_lowercase),
+        body: body.read_to_string().map_err(error)?,
+    })
+}
+
+/// Check that a URL can be retrieved with a HEAD request, or with a GET
+/// request if the server does not allow HEAD requests.
+pub fn check(url: &str, timeout: Duration) -> Result<(), HttpError> {
+    let agent = agent(timeout);
+
+    let result = match agent.head(url).call() {
+        Err(ureq::Error::StatusCode(405)) => agent.get(url).call(),
+        result => result,
+    };
+    result
+        .map(|_| ())
+        .map_err(|err| HttpError::new(err, timeout))
+}
+
+fn agent(timeout: Duration) -> Agent {
+    Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .build()
+        .into()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HttpError {
+    #[error("Server responded with HTTP status {0}")]
+    Status(u16),
+    #[error("Request timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("{0}")]
+    Request(ureq::Error),
+}
+
+impl HttpError {
+    fn new(err: ureq::Error, timeout: Duration) -> Self {
+        match err {
+            ureq::Error::StatusCode(status) => HttpError::Status(status),
+            ureq::Error::Timeout(_) => HttpError::Timeout(timeout),
+            err => HttpError::Request(err),
+        }
+    }
+}
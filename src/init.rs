@@ -0,0 +1,120 @@
+//! Scaffolding of a new document with `docfmt init`.
+
+use std::path::Path;
+
+use log::{error, info};
+
+/// Starter configuration exercising the common keys.
+const CONFIG: &str = r#"# Path to the main template defining the document structure.
+template = "main.hbs"
+
+# Path to the rendered output file.
+output = "README.md"
+
+# Files and directories to include as partials. Directories are traversed
+# recursively, a template is named after its path without the extension, e.g.
+# `partials/intro.hbs` is included with `{{> partials/intro}}`.
+include = []
+
+# File extensions of the templates included from directories, in addition to
+# `md` and `markdown`.
+extensions = ["hbs"]
+
+# Data files merged in order into the data of the templates. Supported are
+# JSON, JSON5 and TOML files.
+datafiles = ["data.toml"]
+
+# Data defined inline, overridden by the data files.
+[data]
+author = "Jane Doe"
+"#;
+
+/// Starter main template.
+const TEMPLATE: &str = r#"# {{title}}
+
+{{description}}
+
+{{#each features}}
+- {{this}}
+{{/each}}
+
+Written by {{author}} on {{docfmt.date}}.
+"#;
+
+/// Starter data file.
+const DATA: &str = r#"title = "My document"
+description = "Rendered by docfmt from main.hbs and data.toml."
+features = ["Handlebars templates", "TOML and JSON data", "Partials"]
+"#;
+
+/// The files written by `init` and their content.
+const FILES: [(&str, &str); 3] = [
+    ("docfmt.toml", CONFIG),
+    ("main.hbs", TEMPLATE),
+    ("data.toml", DATA),
+];
+
+/// Write a starter configuration, main template and data file into `dir`.
+/// If any of the files already exists, nothing is written unless `force` is
+/// set. Returns `false` on failure.
+pub fn init(dir: &Path, force: bool) -> bool {
+    if !force {
+        let existing = FILES
+            .iter()
+            .map(|(name, _)| dir.join(name))
+            .filter(|path| path.exists())
+            .collect::<Vec<_>>();
+
+        for path in &existing {
+            error!("Output file already exists: {:?}", path);
+        }
+        if !existing.is_empty() {
+            return false;
+        }
+    }
+
+    for (name, content) in FILES {
+        let path = dir.join(name);
+
+        info!("Writing file: {:?}", path);
+        if let Err(err) = std::fs::write(&path, content) {
+            error!("Unable to write file: {:?}", path);
+            error!("{}", err);
+            return false;
+        }
+        println!("Created {}", path.display());
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn init_files() {
+        let root = std::env::temp_dir().join(format!("docfmt-init-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        assert!(init(&root, false));
+        assert!(root.join("main.hbs").is_file());
+        assert!(root.join("data.toml").is_file());
+        let config = std::fs::read_to_string(root.join("docfmt.toml")).unwrap();
+        let config = toml::from_str::<toml::Value>(&config).unwrap();
+        assert_eq!(config["template"].as_str(), Some("main.hbs"));
+
+        std::fs::write(root.join("main.hbs"), "Custom").unwrap();
+        assert!(!init(&root, false));
+        assert_eq!(
+            std::fs::read_to_string(root.join("main.hbs")).unwrap(),
+            "Custom"
+        );
+        assert!(init(&root, true));
+        assert_eq!(
+            std::fs::read_to_string(root.join("main.hbs")).unwrap(),
+            TEMPLATE
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+//! Links of rendered markdown.
+//!
+//! Inline links and images like `[text](target "title")` and reference
+//! definitions like `[label]: target` are found line by line. Fenced code
+//! blocks and code spans are skipped. HTML tags and autolinks like
+//! `<https://example.com>` are not links in this sense.
+
+use std::path::{Path, PathBuf};
+
+/// A link target and the line it was found on, counted from 1.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Link<'a> {
+    pub line: usize,
+    pub target: &'a str,
+}
+
+/// What a link target refers to.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Target {
+    /// A path relative to the document, without query and fragment.
+    Local(PathBuf),
+    /// An `http` or `https` URL.
+    Remote(String),
+    /// A fragment of the document itself, a root-relative path or a URL of
+    /// another scheme like `mailto:`, which are not checked.
+    Skipped,
+}
+
+/// Collect the targets of the links in markdown content.
+pub fn extract(content: &str) -> Vec<Link<'_>> {
+    let mut links = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker))
+        {
+            fence = Some(marker);
+            continue;
+        }
+
+        let line_no = index + 1;
+        if let Some(target) = definition(trimmed) {
+            links.push(Link {
+                line: line_no,
+                target,
+            });
+            continue;
+        }
+        let mut rest = line;
+        while let Some(index) = rest.find(['`', ']']) {
+            if rest[index..].starts_with('`') {
+                let ticks = rest[index..].len() - rest[index..].trim_start_matches('`').len();
+                let after = &rest[index + ticks..];
+                match after.find(&rest[index..index + ticks]) {
+                    Some(end) => rest = &after[end + ticks..],
+                    None => rest = after,
+                }
+                continue;
+            }
+            rest = &rest[index + 1..];
+            if let Some(inner) = rest.strip_prefix('(') {
+                if let Some(target) = destination(inner) {
+                    links.push(Link {
+                        line: line_no,
+                        target,
+                    });
+                }
+            }
+        }
+    }
+    links
+}
+
+/// The target of a reference definition like `[label]: target "title"`.
+fn definition(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix('[')?;
+    let (label, rest) = rest.split_once("]:")?;
+    if label.is_empty() || label.starts_with('^') {
+        return None;
+    }
+    destination(rest.trim_start()).filter(|target| !target.is_empty())
+}
+
+/// The destination at the start of `text`, either enclosed in `<` and `>` or
+/// up to the first whitespace or unbalanced `)`.
+fn destination(text: &str) -> Option<&str> {
+    let text = text.trim_start();
+    if let Some(rest) = text.strip_prefix('<') {
+        return rest.split_once('>').map(|(target, _)| target);
+    }
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(&text[..index]),
+            ')' => depth -= 1,
+            c if c.is_whitespace() => return Some(&text[..index]),
+            _ => {}
+        }
+    }
+    Some(text)
+}
+
+/// Classify a link target. Percent-encoded characters of local paths are
+/// decoded.
+pub fn classify(target: &str) -> Target {
+    if target.starts_with("http://") || target.starts_with("https://") {
+        return Target::Remote(target.to_owned());
+    }
+    let path = target.split(['#', '?']).next().unwrap_or_default();
+    if path.is_empty() || path.starts_with('/') || has_scheme(path) {
+        return Target::Skipped;
+    }
+    Target::Local(PathBuf::from(decode(path)))
+}
+
+/// Whether the target starts with a URL scheme like `mailto:`. A single
+/// letter before the colon is taken for a drive letter.
+fn has_scheme(target: &str) -> bool {
+    match target.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Decode percent-encoded bytes like `%20`. Invalid escapes are kept.
+fn decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escape = bytes
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Resolve a local link target against the directory of the document.
+pub fn resolve(document: &Path, path: &Path) -> PathBuf {
+    document
+        .parent()
+        .map_or_else(|| path.to_owned(), |dir| dir.join(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_links() {
+        let content = concat!(
+            "# Guide\n",
+            "See [install](docs/install.md#linux) and ![logo](<img/my logo.png> \"Logo\").\n",
+            "Code `[x](skipped.md)` and [nested (parens)](a_(b).md).\n",
+            "```md\n",
+            "[fenced](skipped.md)\n",
+            "```\n",
+            "[ref]: ../CONTRIBUTING.md \"Contributing\"\n",
+            "[^note]: not a link\n",
+        );
+        let links = extract(content);
+        assert_eq!(
+            links,
+            [
+                Link {
+                    line: 2,
+                    target: "docs/install.md#linux"
+                },
+                Link {
+                    line: 2,
+                    target: "img/my logo.png"
+                },
+                Link {
+                    line: 3,
+                    target: "a_(b).md"
+                },
+                Link {
+                    line: 7,
+                    target: "../CONTRIBUTING.md"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn classify_targets() {
+        assert_eq!(
+            classify("docs/install.md#linux"),
+            Target::Local(PathBuf::from("docs/install.md"))
+        );
+        assert_eq!(
+            classify("my%20logo.png?raw=1"),
+            Target::Local(PathBuf::from("my logo.png"))
+        );
+        assert_eq!(
+            classify("https://example.com/a"),
+            Target::Remote("https://example.com/a".to_owned())
+        );
+        assert_eq!(classify("#usage"), Target::Skipped);
+        assert_eq!(classify("/docs/root.md"), Target::Skipped);
+        assert_eq!(classify("mailto:jane@example.com"), Target::Skipped);
+        assert_eq!(classify("100%"), Target::Local(PathBuf::from("100%")));
+        assert_eq!(
+            resolve(Path::new("site/README.md"), Path::new("docs/a.md")),
+            PathBuf::from("site/docs/a.md")
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! Logging to stderr, as text or as JSON lines.
+//!
+//! Text logs are colored if stderr is a terminal. JSON logs write one object
+//! per line with the keys `time`, `level` and `message`, for ingestion by CI
+//! systems. The key-values of a record, like the `code` of an error, are added
+//! as further keys. Only records of this crate are logged.
+//!
+//! Warnings are counted even if the level hides them, see [`warnings`], and
+//! errors and warnings are collected for the report, see [`report`]. The
+//! registered secrets are masked in every record, see [`secrets`].
+
+use std::{
+    borrow::Cow,
+    io::IsTerminal,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use log::{
+    kv::{Error, Key, Value, VisitSource},
+    Level, LevelFilter, Log, Metadata, Record,
+};
+use serde::Deserialize;
+use serde_json::Map;
+
+use crate::{report, secrets};
+
+/// The number of warnings logged so far.
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// The format of the log records.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    /// The log format of the given name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Initialize the logger with the given level and format.
+pub fn init(level: Level, format: LogFormat) {
+    match format {
+        LogFormat::Text => {
+            let color = if std::io::stderr().is_terminal() {
+                stderrlog::ColorChoice::Auto
+            } else {
+                stderrlog::ColorChoice::Never
+            };
+            let mut logger = stderrlog::new();
+            logger
+                .module(env!("CARGO_CRATE_NAME"))
+                .verbosity(level)
+                .color(color);
+            log::set_boxed_logger(Box::new(CountingLogger(logger))).unwrap();
+            log::set_max_level(level.to_level_filter().max(LevelFilter::Warn));
+        }
+        LogFormat::Json => {
+            log::set_boxed_logger(Box::new(CountingLogger(JsonLogger { level }))).unwrap();
+            log::set_max_level(LevelFilter::Trace);
+        }
+    }
+}
+
+/// The number of warnings of this crate logged so far, including those
+/// hidden by the log level.
+pub fn warnings() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+/// Whether a record is an error or a warning of this crate.
+fn is_diagnostic(metadata: &Metadata) -> bool {
+    metadata.level() <= Level::Warn && metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+}
+
+/// Logger counting the warnings and collecting the errors and warnings for
+/// the report before passing the records on.
+struct CountingLogger<L>(L);
+
+impl<L: Log> Log for CountingLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        is_diagnostic(metadata) || self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        let diagnostic = is_diagnostic(record.metadata());
+        let enabled = self.0.enabled(record.metadata());
+        if !diagnostic && !enabled {
+            return;
+        }
+        let text = record.args().to_string();
+        let message = secrets::mask(&text);
+        if diagnostic {
+            if record.level() == Level::Warn {
+                WARNINGS.fetch_add(1, Ordering::Relaxed);
+            }
+            let code = record.key_values().get(Key::from("code"));
+            report::record(
+                record.level(),
+                message.to_string(),
+                code.map(|code| code.to_string()),
+            );
+        }
+        if !enabled {
+            return;
+        }
+        match message {
+            Cow::Borrowed(_) => self.0.log(record),
+            Cow::Owned(message) => self.0.log(
+                &record
+                    .to_builder()
+                    .args(format_args!("{}", message))
+                    .build(),
+            ),
+        }
+    }
+
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
+
+/// Format a log record as a JSON line without the line break. The `fields`
+/// follow the message.
+pub fn json_line(level: Level, message: &str, fields: Map<String, serde_json::Value>) -> String {
+    let mut line = Map::new();
+
+    line.insert("time".into(), chrono::Local::now().to_rfc3339().into());
+    line.insert("level".into(), level.as_str().into());
+    line.insert("message".into(), message.into());
+    for (key, value) in fields {
+        line.entry(key).or_insert(value);
+    }
+    serde_json::Value::Object(line).to_string()
+}
+
+/// Collects the key-values of a record as strings.
+struct Fields(Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+        self.0.insert(key.to_string(), value.to_string().into());
+        Ok(())
+    }
+}
+
+/// Logger writing JSON lines to stderr.
+struct JsonLogger {
+    level: Level,
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level && metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let mut fields = Fields(Map::new());
+            let _ = record.key_values().visit(&mut fields);
+            let line = json_line(record.level(), &record.args().to_string(), fields.0);
+            eprintln!("{}", line);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_lines() {
+        let line = json_line(
+            Level::Error,
+            "Unable to read \"data.json\"\nmissing",
+            Map::new(),
+        );
+        let value = serde_json::from_str::<serde_json::Value>(&line).unwrap();
+        assert!(!line.contains('\n'));
+        assert_eq!(value["level"], "ERROR");
+        assert_eq!(value["message"], "Unable to read \"data.json\"\nmissing");
+        assert!(value["time"].is_string());
+        assert!(value.get("code").is_none());
+
+        let record = Record::builder()
+            .level(Level::Error)
+            .key_values(&[("code", "MISSING_TEMPLATE")])
+            .build();
+        let mut fields = Fields(Map::new());
+        record.key_values().visit(&mut fields).unwrap();
+        let line = json_line(Level::Error, "Missing template file", fields.0);
+        let value = serde_json::from_str::<serde_json::Value>(&line).unwrap();
+        assert_eq!(value["code"], "MISSING_TEMPLATE");
+        let keys = value.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(keys, ["time", "level", "message", "code"]);
+
+        assert_eq!(LogFormat::from_name("json"), Some(LogFormat::Json));
+        assert_eq!(LogFormat::from_name("xml"), None);
+    }
+
+    #[test]
+    fn count_warnings() {
+        let logger = CountingLogger(JsonLogger {
+            level: Level::Error,
+        });
+        let warning = Record::builder()
+            .level(Level::Warn)
+            .target(env!("CARGO_CRATE_NAME"))
+            .build();
+        let error = Record::builder()
+            .level(Level::Error)
+            .target(env!("CARGO_CRATE_NAME"))
+            .build();
+        let foreign = Record::builder()
+            .level(Level::Warn)
+            .target("handlebars")
+            .build();
+
+        assert!(logger.enabled(warning.metadata()));
+        assert!(!logger.enabled(foreign.metadata()));
+        let before = warnings();
+        logger.log(&warning);
+        logger.log(&error);
+        logger.log(&foreign);
+        assert_eq!(warnings() - before, 1);
+    }
+}
@@ -18,6 +18,217 @@
 //! features. The documentation for Handlebars is extensive and can be found
 //! [here](https://handlebarsjs.com/guide/).
 //!  
+//! A leading UTF-8 byte order mark is removed from templates, data files
+//! and configuration files before they are parsed. The removal is logged
+//! with `-v`.
+//!  
+//! ## Helpers
+//! 
+//! Besides the built-in helpers of Handlebars, the following helpers are
+//! available:
+//! 
+//! - `{{slug text}}`: Convert the text into an anchor-safe slug, e.g. `Getting
+//!   started!` becomes `getting-started`.
+//! - `{{heading level text}}`: Render an HTML heading with an id derived from
+//!   the text using `slug`, e.g. `{{heading 2 "Installation"}}` becomes
+//!   `<h2 id="installation">Installation</h2>`. The level is clamped to 1–6.
+//!   The output is not escaped, the text is.
+//! - `{{include_raw path}}`: Insert the contents of a file without compiling it
+//!   as a template, e.g. a code sample or a license. The path is relative to
+//!   the directory given by `--raw-dir`, which defaults to the current
+//!   directory, and must not leave it. The contents are escaped like any other
+//!   value unless triple braces `{{{include_raw path}}}` are used.
+//! - `{{default value fallback}}`: The value if it is present and not null,
+//!   otherwise the fallback, e.g. `{{default author.name "Anonymous"}}`. A
+//!   missing value does not fail in strict mode.
+//! - `{{#include name}}fallback{{/include}}`: Render the partial `name` with
+//!   the current context if it is registered, otherwise the block, e.g.
+//!   `{{#include "intro"}}No introduction.{{/include}}`. Unlike `{{> name}}`, a
+//!   missing partial is not an error. The block is subject to strict mode.
+//! - `{{lookup_file dir value}}`: The name of the registered template
+//!   `<dir>/<value>` for a dynamic partial, e.g. `{{> (lookup_file "status"
+//!   state)}}` renders `status/done` if `state` is `done`. The value may be a
+//!   string or a number, the hash values `prefix` and `suffix` frame it, e.g.
+//!   `(lookup_file "snippets" code prefix="error-")` selects
+//!   `snippets/error-404`. If no such template is registered or the value is
+//!   `null`, the `fallback` is returned, by default `<dir>/default`. In
+//!   strict mode an unregistered name is an error instead.
+//! - `{{t key}}`: The translation of the key in the locale of `--locale`,
+//!   e.g. `{{t "nav.home"}}` is `translations.de.nav.home` of the data for
+//!   the locale `de`. The key is a path separated by dots or a literal key of
+//!   the locale. The hash value `locale` overrides the locale, e.g. `{{t
+//!   "nav.home" locale="fr"}}`. The parents of the locale and the fallback
+//!   locale of `--fallback-locale` are tried in turn. Strings and numbers are
+//!   translations. A missing translation is logged as a warning and renders
+//!   the key, with `--fail-on-warn` the run fails at its end, in strict mode it
+//!   is an error.
+//! - `{{format_number value thousands=true decimals=2}}`: Format a number or
+//!   numeric string, e.g. `1234.5` becomes `1,234.50`. `thousands` groups the
+//!   digits with `separator` (default `,`), `decimals` rounds to a fixed number
+//!   of decimals, and `point` sets the decimal point (default `.`). Without
+//!   `decimals` the number is written as is. `null` renders as an empty string,
+//!   in strict mode it is an error.
+//! - `{{format_currency value symbol="€"}}`: Like `format_number`, but groups
+//!   thousands and uses two decimals by default, and adds the currency `symbol`
+//!   (default `$`), e.g. `$1,234.50`. With `symbol_after=true` the symbol
+//!   follows the number, e.g. `{{format_currency value symbol="€"
+//!   symbol_after=true separator="." point=","}}` renders `1.234,50 €`.
+//! - `{{slugify text}}`: Same as `slug`. Unicode letters are kept, e.g. `Café
+//!   Crème` becomes `café-crème`.
+//! - `{{upper text}}`, `{{lower text}}`: Convert the text to upper or lower
+//!   case.
+//! - `{{kebab text}}`, `{{snake text}}`: Split the text into words at
+//!   punctuation, whitespace and case changes and join the lowercased words
+//!   with hyphens or underscores, e.g. `userID count` becomes `user-id-count`
+//!   or `user_id_count`.
+//! - `{{title_case text}}`: Split the text into words like `kebab` and
+//!   capitalize them, e.g. `userID count` becomes `User Id Count`.
+//! - `{{base64 text}}`: Encode the text as standard Base64 with padding, e.g.
+//!   `abc` becomes `YWJj`.
+//! - `{{sha256 text}}`: The SHA-256 digest of the text as lowercase hex.
+//! - `{{file_sha256 path}}`: The SHA-256 digest of a file as lowercase hex,
+//!   e.g. for cache-busting `app.js?v={{file_sha256 "assets/app.js"}}`. The
+//!   path is relative to `--raw-dir` like for `include_raw`. A missing file is
+//!   an error.
+//! - `{{json value indent=2}}`: Serialize the value as pretty JSON, indented by
+//!   `indent` spaces (default 2). `indent=0` writes compact JSON.
+//! - `{{toml value}}`: Serialize the value as TOML, e.g. `{{{toml settings}}}`
+//!   for a nested configuration block. The value must be a table. Like any
+//!   other value the output of `json` and `toml` is escaped unless triple
+//!   braces are used. A missing value is an error.
+//! - `{{add a b}}`, `{{sub a b}}`, `{{mul a b}}`, `{{div a b}}`, `{{mod a b}}`:
+//!   Add, subtract, multiply, divide or take the remainder of two numbers or
+//!   numeric strings, e.g. `{{mul price (add count 1)}}`. Two integers give an
+//!   integer unless the result overflows, `div` only if the division is exact,
+//!   e.g. `{{div 7 2}}` is `3.5`. A float operand gives a float. Division by
+//!   zero and a `null` operand give `null`, which renders as an empty string,
+//!   in strict mode they are an error.
+//! - `{{debug}}`, `{{debug path}}`: Log the current context, e.g. the current
+//!   item inside `{{#each}}` or `{{#with}}`, or the value of the path as JSON
+//!   and render nothing. The values are logged at info level, so they are only
+//!   shown with `-v`. A missing value is logged as missing and does not fail in
+//!   strict mode.
+//! - `{{len value}}`: The number of items of an array, keys of an object or
+//!   characters of a string, e.g. `Äbc` has 3. Other values have length 0.
+//!   Replaces the built-in `len` of Handlebars, which counts the bytes of a
+//!   string.
+//! - `{{pluralize count singular plural}}`, `{{count_of count singular
+//!   plural}}`: The word form matching a count, e.g. `{{pluralize count
+//!   "entry" "entries"}}`, or the count followed by it, e.g. `{{count_of
+//!   count "item"}}` renders `1 item` or `0 items`. The singular is used for
+//!   `1` and `-1`, the plural for any other count including zero and
+//!   fractions. Without a plural form an `s` is appended to the singular. The
+//!   count may be a number or a numeric string. A `null` count gives `null`,
+//!   which renders as an empty string, in strict mode it is an error.
+//! - `{{first value}}`, `{{last value}}`, `{{nth value index}}`: The first,
+//!   last or zero-based nth item of an array, value of an object in key order
+//!   or character of a string, e.g. `{{nth items 2}}` is the third item. An
+//!   index out of range and a `null` value give `null`, which renders as an
+//!   empty string, in strict mode they are an error.
+//! - `{{eq a b}}`, `{{ne a b}}`, `{{lt a b}}`, `{{lte a b}}`, `{{gt a b}}`,
+//!   `{{gte a b}}`: Compare two values, e.g. `{{#if (eq status "done")}}` or
+//!   `{{#if (gt count 10)}}`. Two strings are compared as strings, `(lt "10"
+//!   "9")` is true. Numbers are compared by value, `(eq 1 1.0)` is true, and
+//!   a number and a numeric string are compared as numbers like in `add`,
+//!   `(eq 5 "5")` is true. Other values are only equal if they are the same
+//!   JSON value, arrays and objects item by item, and a missing value equals
+//!   `null`. Ordering values that are neither two strings nor two numbers,
+//!   e.g. `(lt missing 1)`, is false, in strict mode it is an error. Replaces
+//!   the built-in comparisons of Handlebars, which order integers only.
+//! - `{{and a b …}}`, `{{or a b …}}`, `{{not value}}`: Combine values by
+//!   their truthiness, e.g. `{{#if (and draft (not published))}}`. `and` and
+//!   `or` take any number of values. Like in `{{#if}}`, `false`, `null`, a
+//!   missing value, `0`, an empty string, an empty array and an empty object
+//!   are false. The result is `true` or `false`.
+//! 
+//! ## Built-in values
+//! 
+//! Unless disabled with `--no-builtins`, the following values are added to the
+//! data under the key `docfmt`:
+//! 
+//! - `docfmt.now`: The local time of the run in RFC 3339 format, e.g.
+//!   `2024-04-04T12:00:00+02:00`.
+//! - `docfmt.date`: The local date of the run, formatted with `--date-format`
+//!   (default `%Y-%m-%d`). The format uses the
+//!   [chrono](https://docs.rs/chrono/latest/chrono/format/strftime/index.html)
+//!   syntax.
+//! 
+//! Values defined by the data take precedence over the built-in values.
+//! 
+//! ## Front matter
+//! 
+//! The main template may start with a front matter block, which contributes to
+//! the data. A YAML block is delimited by lines containing only `---`, a TOML
+//! block by lines containing only `+++`. The block is removed from the template
+//! before it is registered. The front matter has the lowest priority, the
+//! `data` of the configuration file and the data files take precedence over it.
+//! 
+//! ```handlebars
+//! ---
+//! title: My title
+//! ---
+//! # {{title}}
+//! ```
+//! 
+//! ## Layouts
+//! 
+//! Documents can share a layout with partial blocks. A layout is a partial with
+//! placeholders like `{{#> content}}Default{{/content}}`, which render their
+//! block unless a partial of that name is defined. A document renders the
+//! layout with `{{#> name}} ... {{/name}}` and overrides placeholders with
+//! inline partials. The layout is referenced by its template name, which is
+//! derived from its path like any other included file. For example, with
+//! `--include layouts` the file `layouts/base.hbs` is named `layouts/base`:
+//! 
+//! ```handlebars
+//! {{#> layouts/base}}
+//! {{#*inline "content"}}
+//! Welcome to {{title}}.
+//! {{/inline}}
+//! {{/layouts/base}}
+//! ```
+//! 
+//! Inline partials are only visible inside the block they are defined in, so
+//! the layout sees them, but other documents do not.
+//! 
+//! ## Ordering
+//! 
+//! Templates are registered and data is merged in a fixed order, so the same
+//! inputs render the same output on every run and machine, independent of the
+//! file system, the number of threads and the environment.
+//! 
+//! Templates are registered in this order:
+//! 
+//! 1. The main template.
+//! 2. The include entries in the order they are defined, the configuration
+//!    files first and then the command line.
+//! 3. Within a directory, the entries are sorted by file name, compared byte by
+//!    byte, and each subdirectory is traversed right where it sorts, e.g.
+//!    `a.hbs`, `b/x.hbs`, `c.hbs`. The files of an archive are sorted by path
+//!    the same way.
+//! 
+//! A template name registered again replaces the earlier template with a
+//! warning, so the later file wins, e.g. `page.md` over `page.hbs`. With
+//! `--strict` this is an error. `--max-templates` stops at the first template
+//! over the limit in this order.
+//! 
+//! Data is merged in this order, each step taking precedence over the previous
+//! ones:
+//! 
+//! 1. The default data file.
+//! 2. The front matter of the main template.
+//! 3. The `data` table of the configuration files.
+//! 4. The data files in the order they are defined. The files matched by a glob
+//!    pattern are merged in sorted order, each followed by its profile overlay.
+//! 5. The secret files of `--secrets`.
+//! 6. The outputs of the data commands in the order they are defined.
+//! 7. The built-in values and the environment of `--env`, which only add keys
+//!    the data does not define.
+//! 8. The computed values of the configuration.
+//! 
+//! Keys keep the order they first appear in, a key redefined by a later step
+//! keeps its position. The variables of `--env` are added sorted by name.
+//! 
 //! ## Usage
 //! 
 //! ```bash
@@ -28,9 +239,27 @@
 //! 
 //! ### `-c`, `--config`
 //! 
-//! Path to a TOML file containing the configuration. The configuration file can
-//! be used to define the template, output, data, and includes. The command line
-//! arguments take precedence over the configuration file.
+//! Path to a TOML, JSON or JSON5 file containing the configuration. The format
+//! is determined by the file extension (`.toml`, `.json`, or `.json5` and
+//! `.jsonc`). JSON5 allows comments and trailing commas. The configuration file
+//! can be used to define the template, output, data, and includes. Can be used
+//! multiple times, the files are laid over each other in the order they are
+//! defined like a configuration over the base it `extends`: later files take
+//! precedence for single values, lists are extended and data is merged. The
+//! command line arguments take precedence over all configuration files, lists
+//! given on the command line extend those of the files. If `--config` is not
+//! given, the configuration file is taken from the environment variable
+//! `DOCFMT_CONFIG` if it is set and not empty, otherwise `docfmt.toml` in the
+//! current directory is read if it exists, e.g. the one written by `docfmt
+//! init`. Without any of them, all settings come from the command line.
+//! 
+//! ### `--entry`
+//! 
+//! Name of the template to render, defaults to `main`. The main template is
+//! registered under this name. If a configuration file defines no `template`,
+//! the entry may name one of the included templates instead, e.g. `--entry
+//! pages/index`. The program exits with an error if no template of this name is
+//! registered.
 //! 
 //! ### `-i`, `--include`
 //! 
@@ -38,22 +267,460 @@
 //! Directories are traversed recursively. Files and directories are stripped
 //! from the path and the file extension. Dotfiles are ignored when traversing
 //! directories. The files are included in the order they are defined.
+//! Directories are traversed sorted by file name, so templates are registered
+//! in the same order on every run and platform. Including two files that map to
+//! the same template name, e.g. `a/foo.hbs` and `a/foo.md`, logs a warning and
+//! the later file replaces the earlier one. With `--strict` this is an error.
 //! 
 //! On Windows, the stripped path naming the template are converted to use
 //! forward slashes as well.
 //! 
+//! An include of the form `prefix:path` registers the templates under the given
+//! name prefix instead of the name of the directory, e.g. `--include
+//! partials:./shared` registers `shared/intro.hbs` as `partials/intro`. An
+//! empty prefix as in `:./shared` registers it as `intro`. The prefix applies
+//! to included files as well. A path that exists as given is never split.
+//! 
+//! An include ending in `.zip`, `.tar`, `.tar.gz` or `.tgz` is read as an
+//! archive of templates, e.g. `--include templates.zip` or `--include
+//! partials:bundle.tar.gz`. Its files are registered like those of a directory
+//! under their paths inside the archive, e.g. `partials/footer.md` as
+//! `partials/footer`, without the name of the archive unless a prefix is given.
+//! Extensions, dotfiles and `--max-depth` apply as for directories, ignore
+//! files do not. An archive that can not be read, or an entry with an absolute
+//! path or `..`, fails the registration like an unreadable file. Archives are
+//! only supported if the program is compiled with the feature `archive`.
+//! 
 //! ### `-e`, `--ext`
 //! 
 //! Comma-separated list of file extensions to include in directories. Defaults
 //! to `md,markdown`.
 //! 
+//! ### `--no-default-extensions`
+//! 
+//! Do not add the default extensions `md` and `markdown` when `--ext` is not
+//! given, so only the extensions of the configuration are included, e.g.
+//! `extensions = ["hbs"]`. Without it the defaults are added to those of the
+//! configuration. Extensions given with `--ext` always replace the defaults.
+//! 
+//! ### `--partial-ext`
+//! 
+//! Comma-separated list of file extensions of partials to include in
+//! directories, e.g. `hbs`. Files with these extensions are registered in
+//! addition to those matching `--ext`, so partials can be kept apart from the
+//! documents without adding their extension to the document extensions. Empty
+//! by default.
+//! 
+//! ### `--raw-dir`
+//! 
+//! Base directory of the files inserted with the `include_raw` helper and
+//! hashed with `file_sha256`. Defaults to the current directory.
+//! 
+//! ### `--root`
+//! 
+//! Base directory of the names of templates found in included directories. By
+//! default, a template is named after its path relative to the parent of the
+//! included directory, e.g. `--include docs/partials` registers
+//! `docs/partials/intro.hbs` as `partials/intro`. With `--root docs/partials`
+//! it is registered as `intro`, with `--root .` as `docs/partials/intro`.
+//! Leading `./` components are ignored. An included directory outside of the
+//! root is an error. Includes with a name prefix and included files are not
+//! affected.
+//! 
+//! ### `--max-depth`
+//! 
+//! Maximum depth of the files registered from included directories, e.g.
+//! `--max-depth 1` registers only the files directly in an included directory
+//! and `--max-depth 2` also those of its subdirectories. Unbounded by default.
+//! 
+//! ### `--max-templates`
+//! 
+//! Maximum number of registered templates, including the main template. If an
+//! include would register more, the program stops registering and exits with an
+//! error. Unbounded by default. Together with `--max-depth` this guards against
+//! an include entry pointing at a huge directory tree, e.g. in CI.
+//! 
+//! ### `--ignore-file`
+//! 
+//! Path to a file with gitignore-style patterns. Files matching the patterns
+//! are skipped when traversing included directories. Patterns are relative to
+//! the directory containing the ignore file and support negation with `!`.
+//! Independently of this option, a `.docfmtignore` file in an included
+//! directory is applied to that directory. Dotfiles are always skipped.
+//! 
 //! ### `-d`, `--data`
 //! 
 //! Path or file to include in the document. Can be used multiple times.
-//! Directories are traversed recursively. Data may be defined in JSON or TOML
-//! format. The type is determined by the file extension. If defined multiple
-//! times, the data is merged. Merging is done in the sequence the files are
-//! defined. The last file takes precedence over the previous ones.
+//! Directories are traversed recursively. Data may be defined in JSON, JSON5,
+//! TOML, YAML, INI or dotenv format. The type is determined by the file
+//! extension, `.json5` and `.jsonc` files are read as JSON5, which allows
+//! comments and trailing commas, `.yaml` and `.yml` files as YAML. `.ini` files
+//! are read as INI, keys outside of a section are top-level keys and each
+//! section becomes an object. `.env` files, including a file named `.env`, are
+//! read as dotenv files of `KEY=value` lines, which may start with `export`.
+//! Blank lines and comments starting with `#` are skipped, values may be quoted
+//! with single or double quotes. All INI and dotenv values are strings. A file
+//! ending in `.gz` is decompressed and read in the format of the extension
+//! before it, e.g. `data.json.gz` is read as JSON. A corrupt compressed file is
+//! an error. A single data file may be `-` to read the data from stdin, its
+//! format is given by `--data-format`. If defined multiple times, the data is
+//! merged. Merging is done in the sequence the files are defined. The last file
+//! takes precedence over the previous ones. Keys keep the order of the source
+//! files. A key redefined by a later file keeps its original position, new keys
+//! are appended. A key set to `null` in a JSON file is removed. A path
+//! containing `*`, `?` or `[` is a glob pattern, e.g. `-d "data/*.toml"`, and
+//! the matching files are merged in sorted order. A pattern matching no files
+//! only logs a warning. A data file of the form `path@/pointer`, e.g. `-d
+//! "data/meta.toml@/site/meta"`, is merged at the location of the JSON pointer,
+//! as if it was `{"site": {"meta": ...}}`. A path that exists as given has no
+//! pointer. The data may be an array or a single value instead of an object,
+//! e.g. a data file holding a top-level JSON array is iterated with `{{#each
+//! this}}`. It can only be merged with other arrays or values, merging it with
+//! an object, including the front matter or the `data` table of the
+//! configuration, is an error. The built-in values and the environment are not
+//! added to such data.
+//! 
+//! TOML datetimes, in data files, front matter and the `data` table of the
+//! configuration, are strings in the RFC 3339 profile of ISO 8601 with only the
+//! parts the value has: `1979-05-27T07:32:00Z` or `1979-05-27T07:32:00-07:00`
+//! for a datetime with offset, `1979-05-27T07:32:00` for a local datetime,
+//! `1979-05-27` for a date and `07:32:00` for a time. A space between date and
+//! time is written as `T`, fractional seconds are kept as written.
+//! 
+//! ### `--secrets`
+//! 
+//! Data file read and merged like `--data`, after the data files, whose values
+//! are secrets, e.g. `--secrets .env` for a dotenv file with credentials.
+//! Templates render with the real values, but every log record and the output
+//! of `--dump-data` have the values replaced by `***`, wherever they appear,
+//! also inside a longer string like a computed URL or in the message of an
+//! error. Booleans, `null` and values shorter than 4 characters are not masked,
+//! as masking them would garble the log. May be defined multiple times.
+//! 
+//! ### `--sensitive`
+//! 
+//! JSON pointer of a data key whose values are secrets like those of
+//! `--secrets`, e.g. `--sensitive /db/password` or `--sensitive /env/API_TOKEN`
+//! with `--env`. The values of an object or array are all secrets. The key is
+//! looked up after all data is merged, a missing key is skipped. May be defined
+//! multiple times.
+//! 
+//! ### `--data-format`
+//! 
+//! Format of the data read from stdin with `-d -`, one of `json`, `json5`,
+//! `toml`, `yaml`, `ini` or `env`. Required if a data file is `-`, e.g.
+//! `generate | docfmt -d - --data-format json main.hbs README.md`.
+//! 
+//! ### `--data-cmd`
+//! 
+//! Run a command and read its standard output as data, e.g. `--data-cmd
+//! "./gen-data.sh:json"`. The suffix after the last `:` is the format of the
+//! output, one of `json`, `json5`, `toml`, `yaml`, `ini` or `env`. The command
+//! is run by the shell (`sh -c`, `cmd /C` on Windows) without input. Can be
+//! used multiple times, the outputs are merged after the data files in the
+//! given order. If the command can not be run or exits with a non-zero status,
+//! the program exits with an error and logs the standard error of the command.
+//! 
+//! ### `--profile`
+//! 
+//! Select a profile of the data files, e.g. `--profile prod`. For each data
+//! file, the overlay with the profile inserted before the extension is merged
+//! right after it, if it exists, e.g. `data.prod.toml` after `data.toml` and
+//! `data.prod.json.gz` after `data.json.gz`. A missing overlay is not an error.
+//! Overlays are merged in the order of their base files, so a later data file
+//! still takes precedence over the overlay of an earlier one. An overlay
+//! matched by the same glob pattern as its base is merged after the base only,
+//! overlays of other profiles are matched like any other file. Data files
+//! without an extension, like `.env`, have no overlay.
+//! 
+//! ### `--merge-arrays`
+//! 
+//! How arrays defined by several data sources are merged, `replace` or
+//! `append`. Defaults to `replace`, where an array of a later data file
+//! replaces the array of an earlier one. With `append` the items of the later
+//! array are appended. Objects are always merged key by key, a key set to
+//! `null` is removed, and any other value replaces the earlier one.
+//! 
+//! ### `--sidecar-data`
+//! 
+//! Render an included template with the data file of the same stem next to it,
+//! e.g. `partials/card.json` next to `partials/card.hbs`, so the data of a
+//! partial lives with the partial instead of in the global data. The extensions
+//! `json`, `toml`, `yaml` and `yml` are tried in this order, the first file
+//! found is read. Whenever the template renders, e.g. by `{{> partials/card}}`,
+//! its sidecar data is merged over the context it is rendered with, which at
+//! the top level is the global data: keys of the sidecar data take precedence,
+//! objects are merged recursively and arrays according to `--merge-arrays`.
+//! `@root` still refers to the global data. The main template and the templates
+//! of archives have no sidecar data. A sidecar data file that cannot be read
+//! fails the registration like a template.
+//! 
+//! ### `-j`, `--jobs`
+//! 
+//! Number of threads reading and parsing data files, defaults to the number of
+//! logical CPUs. The data is always merged in the order the files are defined,
+//! so the result does not depend on the number of threads. `-j 1` reads the
+//! files one after another on the main thread. Templates are registered
+//! sequentially.
+//! 
+//! ### `--timeout`
+//! 
+//! Seconds rendering may take, e.g. `--timeout 60`, to stop a pathological
+//! template or helper from hanging a CI job. The time is measured from the
+//! computed values to the last written output, reading the data and registering
+//! the templates are not included. Handlebars can not cancel a render in
+//! progress, so when the time is up the error `Rendering timed out after 60s`
+//! is logged and the whole process exits with code 6. Outputs written before
+//! are kept, the output being rendered is not written. Without it, rendering is
+//! not limited.
+//! 
+//! ### `--http-timeout`
+//! 
+//! Seconds to wait for a data file given as a URL, defaults to `30`. A data
+//! file starting with `http://` or `https://`, e.g. `-d
+//! https://example.com/data/site.json`, is fetched with a GET request and
+//! merged like a local file. Its format is given by the extension of the URL
+//! path, ignoring the query, and otherwise by the `Content-Type` of the
+//! response, e.g. `application/json`, `application/toml` or `application/yaml`.
+//! A request that fails, times out or returns an error status is reported with
+//! the URL and makes the run fail. URLs are neither glob patterns nor resolved
+//! relative to the configuration file, and a JSON pointer may be appended as
+//! for files. This option and data URLs are only available if the program is
+//! compiled with the feature `http`, so offline builds never access the
+//! network.
+//! 
+//! ### `--no-builtins`
+//! 
+//! Do not add the built-in values `docfmt.now` and `docfmt.date` to the data.
+//! 
+//! ### `--no-default-data`
+//! 
+//! Do not read the default data file. Without this option `docfmt.data.toml`
+//! or, if it does not exist, `docfmt.data.json` is read from the directory of
+//! the configuration file, the last one if several are given, or from the
+//! current directory without a configuration file. It is merged below all other
+//! data, so the front matter, the inline data of the configuration, the data
+//! files and the data commands take precedence. This keeps the data of a single
+//! project out of the configuration without listing it in `datafiles`. The file
+//! is protected from being overwritten like any other data file. Set
+//! `default_data = false` in the configuration file to disable it there.
+//! 
+//! ### `--env`
+//! 
+//! Add the environment variables to the data under the key `env`, e.g.
+//! `{{env.HOME}}`. Data from the configuration, the front matter or the data
+//! files takes precedence over the environment. As the environment often
+//! contains secrets, this is disabled by default. The `env_vars` key of the
+//! configuration file restricts the variables to the listed names.
+//! 
+//! ### `--no-escape`
+//! 
+//! Do not HTML-escape the values of `{{ }}` expressions. By default Handlebars
+//! escapes `&`, `<`, `>`, quotes and backticks, which turns `a & b` into `a
+//! &amp; b` in the rendered markdown. Escaping stays on by default because
+//! markdown is usually published as HTML, where an unescaped value of the data
+//! could inject markup and scripts into the page, so only disable it if the
+//! data is trusted or the output is not rendered as HTML. Values can also be
+//! left unescaped individually with triple braces `{{{ }}}`.
+//! 
+//! ### `--date-format`
+//! 
+//! Format of the built-in value `docfmt.date` in
+//! [chrono](https://docs.rs/chrono/latest/chrono/format/strftime/index.html)
+//! syntax. Defaults to `%Y-%m-%d`.
+//! 
+//! ### `--locale`
+//! 
+//! Locale of the translations looked up by the helper `t`, e.g. `de-AT`.
+//! Translations are read from the top-level data key `translations`, one table
+//! per locale, e.g. `[translations.de.nav]` with `home = "Startseite"` in a
+//! TOML data file, or from a data file per locale nested with a JSON pointer,
+//! e.g. `-d locales/de.toml@/translations/de`. If the locale has no translation
+//! for a key, its parents are tried by dropping the last subtag, e.g. `de-AT`
+//! then `de`.
+//! 
+//! ### `--fallback-locale`
+//! 
+//! Locale whose translations the helper `t` uses if the locale and its parents
+//! have none, e.g. `en`. Its parents are tried as well. A key missing in all
+//! locales is logged as a warning and rendered as is, in strict mode it is an
+//! error.
+//! 
+//! ### `--output-template`
+//! 
+//! Template of the output path, used instead of `<OUTPUT>`. The template is
+//! rendered against the merged data with the same helpers and partials as the
+//! main template, e.g. `--output-template "docs/{{slug title}}.md"`. The
+//! rendered path is not HTML escaped and must not be empty. An output template
+//! of a configuration file is relative to the directory of the file.
+//! 
+//! ### `--each`
+//! 
+//! [JSON pointer](https://datatracker.ietf.org/doc/html/rfc6901) to an array in
+//! the data, e.g. `/pages`. The main template is rendered once per item and
+//! written to the path rendered from `--output-template`, which is required.
+//! The render context of an item is the data with the keys of the item laid
+//! over it. The item itself and its index are available as `docfmt.item` and
+//! `docfmt.index`. All items are rendered even if some fail, the failures are
+//! reported at the end.
+//! 
+//! ### `--concat`
+//! 
+//! Name of a template to render into the output file, can be used multiple
+//! times. The templates are rendered in the given order against the shared data
+//! and their outputs are joined into a single file, e.g. `docfmt -i docs
+//! --concat docs/intro --concat docs/guide --output-template book.md`. The main
+//! template is optional, any included template may be concatenated, and the
+//! output is given as usual. Outputs that are empty or only hold whitespace are
+//! skipped. The `concat` entries of several configuration files are combined.
+//! Can not be combined with `--each`, `--output-dir` or render targets.
+//! 
+//! ### `--separator`
+//! 
+//! Text written between the outputs of `--concat`, e.g. `--separator "---"` for
+//! a page break. Defaults to nothing. The separator stands on its own lines: a
+//! line break is added before it if the preceding output does not end with one,
+//! and after it if it does not end with one itself. Nothing is written after
+//! the last output. In the configuration a separator of `"\n"` leaves a blank
+//! line between the outputs.
+//! 
+//! ### `--output-dir`
+//! 
+//! Render each registered template into its own file in this directory instead
+//! of rendering the main template into the output file, e.g. `docfmt -i docs
+//! --output-dir site`. The files are named after the templates,
+//! `<output-dir>/<name>.<ext>`, so the directory mirrors the include tree, e.g.
+//! `docs/guide/intro.md` is written to `site/docs/guide/intro.md`. Use `--root`
+//! to drop leading directories from the names. All templates share the data.
+//! The files are written like the output file, honoring `--force`, and failures
+//! are reported at the end unless `--fail-fast` is given. Can not be combined
+//! with an output file, `--output-template`, `--each`, `--concat` or render
+//! targets.
+//! 
+//! ### `--output-pattern`
+//! 
+//! Glob pattern of the template names rendered with `--output-dir`, e.g.
+//! `--output-pattern "docs/guide/*"`. `*` also matches `/`. Defaults to all
+//! templates, including partials. It is an error if no template matches.
+//! 
+//! ### `--output-ext`
+//! 
+//! Extension of the files written with `--output-dir`. Defaults to `md`.
+//! 
+//! ### `--fail-fast`
+//! 
+//! Stop at the first render target of the configuration that fails. By default
+//! all targets are rendered and the failures are reported at the end.
+//! 
+//! ### `--output-format`
+//! 
+//! Format of the rendered output, one of `text`, `json` or `toml`. Defaults to
+//! `text`, which is written as is. With `json` or `toml` the rendered output is
+//! parsed before it is written and the program exits with an error if it is not
+//! valid, to catch templates producing malformed configuration files. The
+//! output is validated only, it is written as rendered.
+//! 
+//! ### `--pretty`
+//! 
+//! Pretty-print the rendered output in the format of `--output-format` before
+//! it is written, e.g. to normalize the indentation of generated JSON. JSON is
+//! indented by two spaces and ends with a newline, TOML is written with one key
+//! per line and tables separated by a blank line. Comments and the formatting
+//! of the rendered TOML are not kept. With the format `text` the output is
+//! written as rendered.
+//! 
+//! ### `--line-ending`
+//! 
+//! Normalize the line endings of the output to `lf`, `crlf` or `native`, which
+//! is `crlf` on Windows and `lf` elsewhere. Line breaks already ending in
+//! `\r\n` are converted as well and never doubled. By default the output is
+//! written with the line endings as rendered.
+//! 
+//! ### `--collapse-blank-lines`
+//! 
+//! Collapse consecutive blank lines of the output into a single blank line
+//! before it is written. A line containing only whitespace counts as blank.
+//! This removes the gaps left by `{{#each}}` and `{{#if}}` blocks, which
+//! otherwise need `{{~ ~}}` whitespace control. Off by default.
+//! 
+//! ### `--safe`
+//! 
+//! Refuse output paths rendered from `--output-template` that are absolute or
+//! contain `..`. The check applies to the rendered path before it is resolved
+//! against the directory of the configuration file.
+//! 
+//! ### `--allow-overwrite-source`
+//! 
+//! Allow the output to overwrite the main template, an included file or a data
+//! file. Without it, the program refuses to write such an output even with
+//! `--force`. Paths are compared after resolving them, so `./README.md` and
+//! `README.md` are the same file. A file inside an included directory counts as
+//! included if its extension is included.
+//! 
+//! ### `--append`
+//! 
+//! Append the rendered output to the output file instead of replacing it, e.g.
+//! to grow a changelog with every run. The file is created if it does not
+//! exist, an existing file is not an error and `--force` is not needed. If the
+//! file does not end with a line break, one is inserted before the new content.
+//! A source file is still protected unless `--allow-overwrite-source` is given.
+//! 
+//! ### `--check-links`
+//! 
+//! After the output is written, check the links of every written file and fail
+//! if one is broken. Inline links and images like
+//! `[text](docs/install.md#linux)` and reference definitions like `[ref]:
+//! ../CONTRIBUTING.md` must point to an existing file or directory, relative to
+//! the directory of the output file. Fragments and queries are ignored,
+//! percent-encoded characters like `%20` are decoded. Links in fenced code
+//! blocks and code spans, fragments of the document itself like `#usage`,
+//! root-relative paths like `/docs` and URLs are not checked. Every broken link
+//! is logged with the output file and line, e.g. `Broken link in "README.md"
+//! line 12: "docs/instal.md"`, and the run fails with exit code 1. As all
+//! outputs are written before the check, links between the outputs of one run
+//! are valid.
+//! 
+//! ### `--check-remote-links`
+//! 
+//! Like `--check-links`, but also check `http` and `https` links. Every URL is
+//! requested once with a HEAD request, or a GET request if the server does not
+//! allow HEAD, and is broken if the request fails, times out after
+//! `--http-timeout` or returns an error status. This option is only available
+//! if the program is compiled with the feature `http`.
+//! 
+//! ### `--report-unused`
+//! 
+//! After a successful render, log a warning for every registered template that
+//! is never included, e.g. `Template is never used: "partials/old"`, to find
+//! partials that can be pruned. Starting from the rendered templates, the
+//! partials called with `{{> name}}` or `{{#> name}}` and the templates
+//! included with the `include` helper are followed, wherever they appear in a
+//! template. A template including a partial under a name computed at render
+//! time, like `{{> (lookup_file "status" state)}}`, may include any registered
+//! template, so then no template is reported. The warnings are logged after the
+//! output is written, with `--fail-on-warn` they fail the run at its end.
+//! 
+//! ### `--report-usage`
+//! 
+//! After a successful render, log a warning for every top-level key of the data
+//! that no rendered template can use, e.g. `Data key is never used: "legacy"`,
+//! to find dead entries in the data files. The usage is found by a static scan
+//! of the templates, not recorded while rendering, so a reported key is safe to
+//! remove, but a key that is not reported may still be unused. The paths of the
+//! data used are logged with `-v`, e.g. `"items.*.name"` for the key `name` of
+//! the items iterated by `each`. The variables are resolved against the context
+//! of `each` and `with` blocks, their block parameters and the context passed
+//! to partials, and the partials and templates of `include` are followed. A
+//! partial or `include` whose name is computed at render time, like `{{>
+//! (lookup_file "status" state)}}`, may be any registered template, so all of
+//! them are followed. All branches count as used, whether they are rendered or
+//! not. A template using the whole data, like `{{json this}}` or `{{debug}}` at
+//! the root, uses every key, so no key is reported and a warning says so. An
+//! expression without arguments naming a built-in or script helper calls the
+//! helper and uses no data. The same scan is used by `--strict-data`. The keys
+//! `docfmt`, `env` and `translations` are never reported. Like
+//! `--report-unused`, the warnings are logged after the output is written, with
+//! `--fail-on-warn` they fail the run at its end.
 //! 
 //! ### `-f`, `--force`
 //! 
@@ -62,19 +729,176 @@
 //! ### `-v`, `--verbose`
 //! 
 //! Enable verbose logging. If defined the log level is set to `debug` if the
-//! program is compiled in debug mode, otherwise it is set to `info`. If not
-//! defined the log level is set to `warn`. The log is written to stderr.
+//! program is compiled in debug mode, otherwise it is set to `info`. If
+//! defined twice (`-vv`) the log level is set to `trace`, which has an effect
+//! in debug mode only. If not defined the log level is set to `warn`. The log
+//! is written to stderr.
+//! 
+//! ### `-q`, `--quiet`
+//! 
+//! Only log errors. Can not be combined with `--verbose`. Without it, a summary
+//! like `Rendered main → README.md (12.4 KB) from 50 templates and 3 datafiles
+//! in 120ms` is printed to stderr after a successful render.
+//! 
+//! ### `--log-format`
+//! 
+//! Format of the log written to stderr, `text` or `json`. Defaults to `text`,
+//! which is colored if stderr is a terminal. With `json` every log record and
+//! the summary are written as one JSON object per line with the keys `time`,
+//! `level` and `message`, e.g.
+//! `{"time":"2024-04-04T12:00:00+02:00","level":"ERROR","message":"Missing
+//! template file"}`. The rendered output is never written to stderr.
+//! 
+//! Errors of the configuration and of rendering carry a stable `code` key,
+//! which does not change with the wording of the message, e.g.
+//! `{"time":"…","level":"ERROR","message":"Missing template
+//! file","code":"MISSING_TEMPLATE"}`. The codes are `CONFIG_FILE_UNREADABLE`,
+//! `INVALID_CONFIG`, `EXTENDS_CYCLE`, `UNSUPPORTED_CONFIG_FORMAT`,
+//! `MISSING_TEMPLATE`, `MISSING_OUTPUT`, `MISSING_OUTPUT_TEMPLATE`,
+//! `MISSING_DATA_FORMAT`, `STDIN_CONFLICT`, `RENDER_CONFLICT`,
+//! `OUTPUT_DIR_CONFLICT`, `CONCAT_CONFLICT`, `INVALID_PATTERN`,
+//! `INVALID_COMPUTED`, `INVALID_DATA_COMMAND`, `INVALID_CONDITION`,
+//! `INVALID_SENSITIVE`, `TEMPLATE_ERROR` and `RENDER_ERROR`.
+//! 
+//! ### `--report`
+//! 
+//! Write a JSON report of the run to this file for CI dashboards and other
+//! tools, independent of the log level and format. The report is written also
+//! if the run fails and contains:
+//! 
+//! - `schema_version`: The version of the report format, currently `1`. It is
+//!   increased whenever a key is removed or changes its meaning, new keys may
+//!   be added without.
+//! - `success` and `exit_code`: Whether the run succeeded and its exit code,
+//!   as listed under exit codes.
+//! - `templates`: The sorted names of the registered templates.
+//! - `datafiles`: The data files read, with glob patterns expanded.
+//! - `outputs`: The output files written, empty if rendering failed.
+//! - `timings`: The milliseconds spent reading the data, registering the
+//!   templates, rendering and in total. A phase that was not reached is `null`.
+//! - `errors` and `warnings`: The logged errors and warnings as objects with
+//!   the `message` and the `code` of the error, if it has one. Secrets are
+//!   masked like in the log.
+//! 
+//! A run stopped by `--timeout` writes no report. If the report cannot be
+//! written, a successful run fails with exit code 3.
+//! 
+//! ### `--print-templates`
+//! 
+//! Print the sorted names of the registered templates to stderr and exit
+//! without rendering, e.g. `docfmt -i docs --print-templates` to find the name
+//! of a partial for `{{> name}}`. Neither a main template nor an output file is
+//! required. In `validate` mode the names are printed before the summary.
+//! 
+//! ### `--dump-data`
+//! 
+//! Print the data as pretty JSON to stdout and exit without rendering, e.g.
+//! `docfmt -c docfmt.toml --dump-data` to inspect the merged data files, front
+//! matter and computed values. Secrets given by `--secrets` and `--sensitive`
+//! are masked. Neither a main template nor an output file is required.
 //! 
 //! ### `-s`, `--strict`
 //! 
 //! Restrict accessing non-existing fields or indices in templates. If defined
 //! the program will exit with an error if a field or index is accessed that
-//! does not exist. If not defined the program will ignore such accesses.
+//! does not exist. If not defined the program will ignore such accesses. Render
+//! errors name the file, line and column of the failing expression, which is in
+//! the partial if the error occurred in one, followed by the partials including
+//! it, e.g. `Render failed in docs/card.hbs:2:3, included from
+//! docs/section.hbs:4, included from main.hbs:7: Variable "title" not found in
+//! strict mode`. The lines count from the start of the files, including the
+//! front matter of the main template. The partials are followed as for
+//! `--report-unused`, the shortest chain from the rendered templates is shown.
+//! 
+//! Before rendering, the variables the entry templates reference in the root of
+//! the data are checked against it, and each missing one is logged as a
+//! warning, so all of them are reported at once instead of only the first
+//! failing one. Variables tested by `{{#if}}`, `{{#unless}}` and the `default`
+//! helper may be missing and are not reported, neither are those inside
+//! `{{#each}}` and `{{#with}}` blocks. An expression without arguments naming a
+//! helper, like `{{debug}}` or a script helper, calls the helper and is not
+//! checked. The check is skipped with `--each`.
+//! 
+//! ### `--strict-data`
+//! 
+//! Exit with an error before rendering if a top-level key of the data can't be
+//! used by any rendered template, to catch stale data entries. The templates
+//! are scanned like for `--report-usage`, nothing is recorded while rendering:
+//! a key only used by a template that is never included, or a name inside
+//! `each` that resolves against the items, does not count as used. If a
+//! template uses the whole data, nothing is checked and a warning says so. The
+//! built-in values `docfmt`, the environment `env` and the translations
+//! `translations`, which are read by the helper `t`, are exempt.
+//! 
+//! ### `--require-data`
+//! 
+//! Exit with an error if the data is empty after merging the front matter, the
+//! inline data of the configuration, the data files and the data commands, to
+//! catch a data file pointed at the wrong path or a pattern matching nothing.
+//! The built-in values and the environment of `--env` do not count. The error
+//! tells whether no data file or data command is configured at all, e.g. `Data
+//! is required, but no data file or data command is configured`, or whether all
+//! of them are empty, e.g. `Data is required, but is empty after reading 2 data
+//! files and 0 data commands`. The run fails with exit code 2.
+//! 
+//! ### `--strict-undefined-partials`
+//! 
+//! Exit with an error if a registered template references a partial that is not
+//! registered, e.g. `Unable to find partial "hedaer" referenced by "main"`,
+//! independent of `--strict`. Rendering already fails when it reaches a missing
+//! partial, but a partial in a branch that is not taken, like `{{#if draft}}{{>
+//! hedaer}}{{/if}}`, goes unnoticed until the data changes. The templates are
+//! checked after registration, also in `validate` mode, so renamed or moved
+//! partials are found without rendering. Inline partials defined by any
+//! template count as registered. Partial blocks like `{{#> name}}...{{/name}}`,
+//! which render their block if the partial is missing, `@partial-block` and
+//! partials with a computed name are not checked.
+//! 
+//! ### `--fail-on-warn`
+//! 
+//! Exit with code `5` if a warning was logged, for strict CI runs. Warnings
+//! logged before rendering stop the run before any output is written. Warnings
+//! logged while rendering or by the reports after it fail the run at its end,
+//! after the outputs are written. Warnings are counted even if `-q` hides them.
+//! The following conditions log a warning:
+//! 
+//! - A template name registered before is overwritten by a later include,
+//!   without `--strict`.
+//! - A data file pattern matches no files.
+//! - A variable an entry template references in the root of the data is
+//!   missing, with `--strict`.
+//! - The unused data keys can't be checked because a template uses the whole
+//!   data, with `--strict-data`.
+//! - A translation of `{{t}}` is missing, while rendering.
+//! - A template is never used, with `--report-unused`, after rendering.
+//! - A data key is never used, or the unused keys can't be reported, with
+//!   `--report-usage`, after rendering.
+//! 
+//! Conditions that make the run fail, like a file name that is not valid UTF-8,
+//! are logged as errors. In `validate`, `lint` and `graph` mode and with
+//! `--print-templates` the warnings are checked at the end.
 //! 
 //! ### `--follow`
 //! 
 //! Follow symbolic links when traversing directories. This option is only
-//! available on Unix systems.
+//! available on Unix systems. A symbolic link pointing to one of its ancestor
+//! directories is reported as a symlink loop and the program exits with an
+//! error. The `follow_links` table of the configuration overrides it per
+//! include entry.
+//! 
+//! ### `--helpers`
+//! 
+//! Path to a [Rhai](https://rhai.rs/) script defining custom helpers. Can be
+//! used multiple times. Every public function of the script is registered as a
+//! helper of the same name. The helper arguments are converted from JSON into
+//! Rhai values and passed to the function in order, hash arguments are passed
+//! as an additional trailing object map. The return value is converted back
+//! into JSON, `()` becomes `null`. This option is only available if the program
+//! is compiled with the feature `scripting`.
+//! 
+//! ```rhai
+//! fn double(x) { x * 2 }
+//! ```
 //! 
 //! ### `-V`, `--version`
 //! 
@@ -95,85 +919,790 @@
 //! ### `<OUTPUT>`
 //! 
 //! Path to the output file. The output file may be omitted if the output is
-//! defined in the configuration file.
+//! defined in the configuration file. With `-` the output is written to stdout,
+//! while the log stays on stderr, e.g. `docfmt config.hbs - --output-format
+//! json --pretty | jq .`. The output is validated before anything is written,
+//! so nothing reaches stdout if it is invalid. The output `-` is kept as is in
+//! a configuration file.
+//! 
+//! ## Commands
+//! 
+//! ### `validate`
+//! 
+//! ```bash
+//! docfmt validate [OPTIONS] <TEMPLATE>
+//! ```
+//! 
+//! Register the main template and all includes without reading data or
+//! rendering, to check that every template parses. Prints the number of
+//! registered templates on success and exits with a nonzero code if any
+//! template failed to register. As when rendering, all files are registered
+//! even if some fail, and the number of failed files is reported with their
+//! paths, e.g. `3 of 50 templates failed to register`. The options `--config`,
+//! `--include`, `--ext`, `--strict` and `--follow` are honored as for
+//! rendering.
+//! 
+//! ### `lint`
+//! 
+//! ```bash
+//! docfmt lint [OPTIONS] <TEMPLATE>
+//! ```
+//! 
+//! Read the data and register the main template and the includes, skipping
+//! those whose `include_when` condition does not hold, without rendering, and
+//! report every problem found as an error:
+//! 
+//! - Registered templates that are not reachable from the main template, e.g.
+//!   `Template is not reachable from the entry templates: "partials/old"`.
+//! - References to partials that are neither registered nor defined inline,
+//!   e.g. `Unable to find partial "hedaer" referenced by "main"`.
+//! - Variables that are missing in the data, with the file and line of each
+//!   reference, e.g. `Variable is missing in the data: "person.name" at
+//!   docs/card.hbs:3`.
+//! 
+//! The partials are followed as for `--report-unused`, from the main template
+//! or the render targets of the configuration. A template including a partial
+//! under a name computed at render time, like `{{> (lookup_file "parts/status"
+//! state)}}`, may include any registered template, so then no template is
+//! reported as unreachable. Variables are checked like in strict mode: those in
+//! the blocks of `each` and `with` and those tested by `if`, `unless` and
+//! `default` are skipped. An expression without arguments naming a built-in or
+//! script helper, like `{{debug}}`, calls the helper and is not a variable. The
+//! data is read and computed as for rendering. The option `--schema` names a
+//! [JSON Schema](https://json-schema.org/) of the data in any data format, also
+//! set by the configuration key `schema`. Variables declared in it through
+//! `properties`, `additionalProperties` or `items` are not reported, e.g.
+//! optional values. Prints the number of registered templates and exits with
+//! `0` if nothing was found, otherwise exits with `1`, or with `2` if the data
+//! can't be read. The other options are honored as in `validate` mode.
+//! 
+//! ### `graph`
+//! 
+//! ```bash
+//! docfmt graph [OPTIONS] <TEMPLATE>
+//! ```
+//! 
+//! Register the main template and all includes without reading data or
+//! rendering, and print the graph of the templates included by the main
+//! template to stdout, e.g. `docfmt graph main.hbs -i docs | dot -Tsvg >
+//! templates.svg`. Starting from the main template, or the render targets of
+//! the configuration, the partials and the templates of `include` are followed
+//! as for `--report-unused`, so only names given literally are found. The
+//! includes of `include_when` are registered whatever their condition.
+//! Templates that are not reachable are left out, as are partials that are not
+//! registered. The option `--format` selects the format:
+//! 
+//! - `dot`: A directed graph for [Graphviz](https://graphviz.org/) with an edge
+//!   from every template to each template it includes, the default. The main
+//!   template is drawn as a box.
+//! - `json`: An object with the names of the main templates as `entries` and
+//!   the reachable templates as `templates`, each with the `file` it was read
+//!   from and the sorted names of the templates it includes as `partials`. A
+//!   build system can collect the files of the templates to rebuild a document
+//!   when one of them changes.
+//! 
+//! The other options are honored as in `validate` mode.
+//! 
+//! ### `init`
+//! 
+//! ```bash
+//! docfmt init [--force]
+//! ```
+//! 
+//! Write a starter configuration `docfmt.toml`, a main template `main.hbs` and
+//! a data file `data.toml` into the current directory. The configuration
+//! explains its keys in comments, `docfmt -c docfmt.toml` renders the starter
+//! document. If any of the files already exists, no file is written unless
+//! `--force` is given.
+//! 
+//! ## Exit codes
+//! 
+//! The program exits with one of the following codes:
+//! 
+//! - `0`: Success.
+//! - `1`: A template failed to register or to render.
+//! - `2`: A data file failed to be read or parsed.
+//! - `3`: The output file could not be written.
+//! - `4`: The command line arguments or the configuration file are invalid.
+//! - `5`: A warning was logged and `--fail-on-warn` is set.
+//! - `6`: Rendering did not finish within `--timeout`.
+//! 
+//! ## Performance
+//! 
+//! Templates are registered on every run and not cached between runs.
+//! Registering is mostly parsing: in a release build 2001 templates of a few
+//! lines each register in about 100 ms, of which reading the files is a small
+//! part. Parsed templates can not be stored, so a cache could only hold the
+//! sources, and it would still have to check every file for changes.
 //! 
 //! ## Configuration
 //! 
-//! The configuration file is a TOML file. The following keys are supported:
+//! The configuration file is a TOML, JSON or JSON5 file. The following keys are
+//! supported:
 //! 
 //! ```toml
+//! extends = "<path to base config>"
+//! relative_to = "config"
 //! template = "<path to template>"
+//! entry = "main"
 //! output = "<path to output>"
+//! output_template = "<template of output path>"
+//! concat = ["<template name>", "<template name>"]
+//! separator = "---"
+//! each = "<json pointer to array>"
+//! output_dir = "<path to directory>"
+//! output_pattern = "<glob of template names>"
+//! output_ext = "md"
+//! output_format = "text"
+//! pretty = false
+//! line_ending = "lf"
+//! collapse_blank_lines = false
 //! force = false
+//! safe = false
+//! allow_overwrite_source = false
+//! append = false
+//! report_unused = false
+//! report_usage = false
+//! check_links = false
+//! check_remote_links = false
 //! follow = false
 //! verbose = false
+//! quiet = false
+//! log_format = "text"
+//! report = "<path to report>"
 //! strict = false
+//! strict_data = false
+//! require_data = false
+//! sidecar_data = false
+//! strict_undefined_partials = false
+//! fail_on_warn = false
 //! include = ["<file to include>", "<path to include>"]
 //! ext = ["md", "markdown"]
+//! no_default_extensions = false
+//! partial_extensions = ["hbs"]
 //! datafiles = ["<path to json-file>", "<path to toml-file>"]
+//! secrets = ["<path to .env-file>"]
+//! sensitive = ["/db/password"]
+//! data_format = "json"
+//! profile = "<name>"
+//! data_commands = ["<command>:<format>"]
+//! helpers = ["<path to rhai-script>"]
+//! raw_dir = "<path to directory>"
+//! root = "<path to directory>"
+//! jobs = 4
+//! timeout = 60
+//! http_timeout = 30
+//! max_depth = 8
+//! max_templates = 1000
+//! merge_arrays = "replace"
+//! ignore_file = "<path to ignore-file>"
+//! schema = "<path to JSON schema>"
+//! builtins = true
+//! default_data = true
+//! env = false
+//! env_vars = ["HOME", "USER"]
+//! html_escape = true
+//! date_format = "%Y-%m-%d"
+//! locale = "<locale>"
+//! fallback_locale = "<locale>"
+//! 
+//! [data_formats]
+//! data = "json"
+//! 
+//! [follow_links]
+//! "<path to include>" = true
+//! 
+//! [include_when]
+//! "<path to include>" = "audience == internal"
 //! 
 //! [data]
 //! title = "My title"
+//! 
+//! [computed]
+//! full_title = "{{title}} ({{docfmt.date}})"
 //! ```
 //! 
-//! The `template` and `output` keys are required. The `force`, `follow`,
-//! `verbose`, and `strict` keys are optional and default to `false`. The
-//! `include` and `ext` keys are optional and default to `[]` and `["md",
+//! The `template` and `output` keys are required, `output` may be replaced by
+//! `output_template`, and both by `render`. The `force`, `safe`, `follow`,
+//! `verbose`, `quiet`, and `strict` keys are optional and default to `false`.
+//! The `include` and `ext` keys are optional and default to `[]` and `["md",
 //! "markdown"]` respectively. The `datafiles` key is optional and defaults to
 //! `[]`. The `data` key is optional and defaults to `{}`.
+//! 
+//! The `helpers` key is optional and defaults to `[]`. It requires the feature
+//! `scripting`. The `http_timeout` key requires the feature `http`. The
+//! `ignore_file` key is optional. The `schema` key is optional and only
+//! used by `lint`.
+//! 
+//! The `builtins` and `html_escape` keys are optional and default to `true`.
+//! The `date_format` key is optional and defaults to `"%Y-%m-%d"`.
+//! 
+//! The `extends` key is optional and names a base configuration, relative to
+//! the directory of the configuration file. The base is read first and the
+//! configuration is laid over it: values defined in the configuration take
+//! precedence, flags set in either are set, lists like `include` and
+//! `datafiles` extend those of the base and the `data` tables are merged. A
+//! base may extend another configuration, a cycle is an error.
+//! 
+//! Relative paths in a configuration file, like `template`, `output`,
+//! `include`, `datafiles`, `helpers`, `raw_dir` and the outputs of `render`,
+//! are resolved relative to the directory of the configuration file, so the
+//! configuration works from any directory. Include entries keep their name
+//! prefix and data files their JSON pointer, the keys of `follow_links` and
+//! `include_when` are resolved like the include entries. Paths given on the
+//! command line stay relative to the current directory, and so do
+//! `data_commands`, which are run there. Setting the `relative_to` key to
+//! `"cwd"` resolves the paths of the file relative to the current directory
+//! instead, it defaults to `"config"`.
+//! 
+//! The `data_formats` table maps file extensions of data files to their format,
+//! one of `json`, `json5`, `toml`, `yaml`, `ini` or `env`, e.g. `data = "json"`
+//! reads `.data` files as JSON. It takes precedence over the built-in
+//! extensions `.json`, `.json5`, `.jsonc`, `.toml`, `.yaml`, `.yml`, `.ini` and
+//! `.env`.
+//! 
+//! The `follow_links` table overrides `follow` for single include entries, e.g.
+//! `"docs/shared" = true` follows the symbolic links in `docs/shared` only. The
+//! keys are the include entries as given, with or without their name prefix.
+//! Entries that are not listed follow `--follow`.
+//! 
+//! The `include_when` table registers single include entries only if a
+//! condition on the data holds, e.g. `"docs/internal" = "audience == internal"`
+//! includes the partials in `docs/internal` only when building the internal
+//! variant. A condition is a path to a value of the data, as a JSON pointer
+//! like `/site/audience` or dotted like `site.audience`, optionally compared
+//! with `==` or `!=` to a string or JSON value. Without a comparison the value
+//! must be truthy, that is present and not `null`, `false`, `0`, an empty
+//! string or an empty array. The keys are the include entries as given, with or
+//! without their name prefix. The data is read before the templates are
+//! registered, skipped includes are not errors. In `validate` and `graph` mode
+//! no data is read, the conditions are not evaluated and all includes are
+//! registered, so the graph holds every template a variant may include.
+//! 
+//! The `computed` table defines values derived from the data with a template
+//! each, e.g. `full_version = "{{major}}.{{minor}}.{{patch}}"`. The templates
+//! are rendered against the merged data with the registered helpers and
+//! partials, and the results are added to the data as top-level strings before
+//! rendering. They are computed in the order of declaration, except that a
+//! value referencing other computed values is computed after them. Computed
+//! values referencing each other in a cycle are an error. A value failing to
+//! render is reported at `computed.<name>` with the line and column in its
+//! template, e.g. `Render failed in computed.full_version:1:1`. Computed values
+//! of several configuration files are combined, a later file replaces a value
+//! of the same name.
+//! 
+//! The `render` array renders several templates in one run, sharing the
+//! registered templates and the data. Each target names the `entry` template
+//! and its `output` file. The targets replace the output file, so they can not
+//! be combined with an output file or `--output-template`. The main template is
+//! optional, the entries may be any included templates. Failed targets are
+//! reported at the end unless `fail_fast` or `--fail-fast` is set. Render
+//! targets of several configuration files are combined.
+//!
+//! ```toml
+//! include = ["docs"]
+//!
+//! [[render]]
+//! entry = "docs/readme"
+//! output = "README.md"
+//!
+//! [[render]]
+//! entry = "docs/contributing"
+//! output = "CONTRIBUTING.md"
+//! ```
 
+#[cfg(feature = "archive")]
+pub mod archive;
 pub mod cli;
 pub mod config;
+pub mod data;
+pub mod dotenv;
+pub mod exit;
+pub mod front_matter;
+pub mod graph;
+pub mod helpers;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod init;
+pub mod links;
+pub mod logger;
+pub mod references;
+pub mod report;
+pub mod secrets;
+#[cfg(feature = "scripting")]
+pub mod scripting;
 
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    time::{Duration, Instant},
+};
+
+use handlebars::{Handlebars, RenderError};
 use log::error;
+use serde_json::Map;
 
-use crate::{cli::get_cli, config::Config};
+use crate::{
+    cli::get_cli,
+    config::{Config, Mode, RenderTarget},
+    exit::Failure,
+    graph::{Graph, GraphFormat},
+    logger::LogFormat,
+    report::Report,
+};
 
-/// Initialize the logger.
-pub fn init_logger(level: log::Level) {
-    stderrlog::new()
-        .module(module_path!())
-        .verbosity(level)
-        .init()
-        .unwrap();
+fn main() -> ExitCode {
+    let matches = match get_cli().try_get_matches() {
+        Ok(matches) => matches,
+        Err(e) => {
+            let _ = e.print();
+            if e.use_stderr() {
+                return Failure::Config.into();
+            }
+            return ExitCode::SUCCESS;
+        }
+    };
+    let log_format = matches
+        .get_one::<String>("log_format")
+        .and_then(|format| LogFormat::from_name(format))
+        .unwrap_or_default();
+    if let Some(("init", matches)) = matches.subcommand() {
+        logger::init(log::Level::Warn, log_format);
+        if !init::init(Path::new("."), matches.get_flag("force")) {
+            return Failure::Output.into();
+        }
+        return ExitCode::SUCCESS;
+    }
+    let config = match Config::try_from(matches) {
+        Ok(config) => config,
+        Err(e) => {
+            logger::init(log::Level::Error, log_format);
+            error!(code = e.code(); "{}", e);
+            return Failure::from(&e).into();
+        }
+    };
+
+    logger::init(config.log_level(), config.log_format());
+
+    let mut report = Report::new();
+    let report_path = config.report().map(Path::to_owned);
+    let result = match config.mode() {
+        Mode::Render => run(config, &mut report),
+        Mode::Validate => validate(config, &mut report),
+        Mode::Lint => lint(config, &mut report),
+        Mode::Graph(format) => graph(config, format, &mut report),
+    };
+    if let Some(path) = report_path {
+        report.finish(result);
+        if !report.write(&path) && result.is_ok() {
+            return Failure::Output.into();
+        }
+    }
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => failure.into(),
+    }
 }
 
-fn main() {
-    let config = match Config::try_from(get_cli().get_matches()) {
-        Ok(config) => config,
+/// Run the program, collecting the templates, data files, outputs and
+/// timings into the report.
+pub fn run(mut config: Config, report: &mut Report) -> Result<(), Failure> {
+    let start = Instant::now();
+    report.datafiles = config.datafiles();
+    let data = config.read_data();
+    report.timings.data = Some(start.elapsed().as_millis());
+    let registry_start = Instant::now();
+    let (registry, sources) = config
+        .new_registry_with_sources(data.as_ref())
+        .map_err(|failed| {
+            failed.log();
+            Failure::Template
+        })?;
+    report.timings.registry = Some(registry_start.elapsed().as_millis());
+    report.templates = template_names(&registry);
+    if config.print_templates() {
+        print_templates(&registry);
+        data.ok_or(Failure::Data)?;
+        return check_warnings(&config);
+    }
+    let mut data = data.ok_or(Failure::Data)?;
+    let watchdog = config.timeout().map(start_watchdog);
+    if !config.compute(&registry, &mut data) {
+        return Err(Failure::Data);
+    }
+    if config.dump_data() {
+        dump_data(&data);
+        return check_warnings(&config);
+    }
+    if config.strict_data() && !config.check_data_usage(&registry, &sources, &data) {
+        return Err(Failure::Data);
+    }
+    if config.each().is_none() {
+        config.check_references(&registry, &sources, &data);
+    }
+    check_warnings(&config)?;
+    let render_start = Instant::now();
+    let outputs = render_outputs(&mut config, &registry, &sources.files, &data, start)?;
+    drop(watchdog);
+    report.timings.render = Some(render_start.elapsed().as_millis());
+    report.outputs = outputs;
+    if config.report_unused() {
+        config.check_unused(&registry);
+    }
+    if config.report_usage() {
+        config.check_usage(&registry, &sources, &data);
+    }
+    if config.check_links() && !config.check_output_links(&report.outputs) {
+        return Err(Failure::Template);
+    }
+    check_warnings(&config)
+}
+
+/// Terminate the process with [`Failure::Timeout`] unless the returned sender
+/// is dropped within the timeout. A render can not be cancelled, as
+/// Handlebars does not check for cancellation while rendering, so the whole
+/// process is exited instead.
+fn start_watchdog(timeout: Duration) -> Sender<()> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        if let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(timeout) {
+            error!("Rendering timed out after {:?}", timeout);
+            std::process::exit(Failure::Timeout as i32);
+        }
+    });
+    sender
+}
+
+/// Render the output directory, the render targets, each item or the entry,
+/// depending on the configuration. Returns the written output files.
+fn render_outputs(
+    config: &mut Config,
+    registry: &Handlebars,
+    sources: &HashMap<String, PathBuf>,
+    data: &serde_json::Value,
+    start: Instant,
+) -> Result<Vec<PathBuf>, Failure> {
+    if config.output_dir().is_some() {
+        let targets = config.output_dir_targets(registry);
+        if targets.is_empty() {
+            error!("No template matches the output pattern");
+            return Err(Failure::Template);
+        }
+        return run_targets(config, registry, sources, data, &targets, start);
+    }
+    if !config.render().is_empty() {
+        return run_targets(config, registry, sources, data, config.render(), start);
+    }
+    if config.each().is_some() {
+        return run_each(config, registry, sources, data, start);
+    }
+    if !config.resolve_output(registry, data) {
+        return Err(Failure::Output);
+    }
+    let content = match render_entries(config, registry, data) {
+        Ok(content) => content,
         Err(e) => {
-            init_logger(log::Level::Error);
-            error!("{}", e);
-            std::process::exit(1);
+            let e = config.render_error(registry, sources, e);
+            error!(code = e.code(); "{}", e);
+            return Err(Failure::from(&e));
         }
     };
 
-    #[cfg(debug_assertions)]
-    dbg!(&config);
+    if !config.check_output(&content) {
+        return Err(Failure::Template);
+    }
+    let size = content.len();
+    if !config.write_output(content) {
+        return Err(Failure::Output);
+    }
+    print_summary(
+        config,
+        registry,
+        &config.entries().join(", "),
+        &config.output().display().to_string(),
+        size,
+        start,
+    );
+    Ok(vec![config.output().to_owned()])
+}
 
-    init_logger(config.log_level());
+/// Render the entry, or the concatenated templates joined by the separator.
+fn render_entries(
+    config: &Config,
+    registry: &Handlebars,
+    data: &serde_json::Value,
+) -> Result<String, RenderError> {
+    if config.concat().is_empty() {
+        return registry.render(config.entry(), data);
+    }
+    let outputs = config
+        .concat()
+        .iter()
+        .map(|entry| registry.render(entry, data))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(config.join_outputs(outputs))
+}
+
+/// Render each target to its output. Failures are reported at the end,
+/// unless `fail_fast` stops at the first one.
+fn run_targets(
+    config: &Config,
+    registry: &Handlebars,
+    sources: &HashMap<String, PathBuf>,
+    data: &serde_json::Value,
+    targets: &[RenderTarget],
+    start: Instant,
+) -> Result<Vec<PathBuf>, Failure> {
+    let mut failure = None;
+    let mut failed = 0;
+    let mut size = 0;
+    let mut outputs = Vec::with_capacity(targets.len());
 
-    if !run(config) {
-        std::process::exit(1);
+    for target in targets {
+        if failure.is_some() && config.fail_fast() {
+            break;
+        }
+        let content = match registry.render(&target.entry, data) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Unable to render {:?} to {:?}", target.entry, target.output);
+                let e = config.render_error(registry, sources, e);
+                error!(code = e.code(); "{}", e);
+                failure.get_or_insert(Failure::Template);
+                failed += 1;
+                continue;
+            }
+        };
+        if !config.check_output(&content) {
+            error!("Unable to render {:?} to {:?}", target.entry, target.output);
+            failure.get_or_insert(Failure::Template);
+            failed += 1;
+            continue;
+        }
+        size += content.len();
+        if config.write_output_to(&target.output, content) {
+            outputs.push(target.output.to_owned());
+        } else {
+            failure.get_or_insert(Failure::Output);
+            failed += 1;
+        }
+    }
+
+    match failure {
+        Some(failure) => {
+            error!("{} of {} render targets failed", failed, targets.len());
+            Err(failure)
+        }
+        None => {
+            let entries = format!("{} entries", targets.len());
+            let target = format!("{} files", targets.len());
+            print_summary(config, registry, &entries, &target, size, start);
+            Ok(outputs)
+        }
     }
-    std::process::exit(0);
 }
 
-/// Run the program.
-pub fn run(config: Config) -> bool {
-    let registry = config.new_registry();
-    let data = config.read_data();
+/// Render one output per item of the array selected by the `each` pointer.
+/// All items are rendered, failures are reported at the end.
+fn run_each(
+    config: &Config,
+    registry: &Handlebars,
+    sources: &HashMap<String, PathBuf>,
+    data: &serde_json::Value,
+    start: Instant,
+) -> Result<Vec<PathBuf>, Failure> {
+    let contexts = config.each_contexts(data).ok_or(Failure::Data)?;
+    let mut failure = None;
+    let mut failed = 0;
+    let mut size = 0;
+    let mut outputs = Vec::with_capacity(contexts.len());
 
-    if let (Some(registry), Some(data)) = (registry, data) {
-        let content = match registry.render("main", &data) {
+    for (index, context) in contexts.iter().enumerate() {
+        let Some(output) = config.render_output_path(registry, context) else {
+            error!("Unable to render item {}", index);
+            failure.get_or_insert(Failure::Output);
+            failed += 1;
+            continue;
+        };
+        let content = match registry.render(config.entry(), context) {
             Ok(content) => content,
             Err(e) => {
-                error!("{}", e);
-                return false;
+                error!("Unable to render item {}: {:?}", index, output);
+                let e = config.render_error(registry, sources, e);
+                error!(code = e.code(); "{}", e);
+                failure.get_or_insert(Failure::Template);
+                failed += 1;
+                continue;
             }
         };
+        if !config.check_output(&content) {
+            error!("Unable to render item {}: {:?}", index, output);
+            failure.get_or_insert(Failure::Template);
+            failed += 1;
+            continue;
+        }
+        size += content.len();
+        if config.write_output_to(&output, content) {
+            outputs.push(output);
+        } else {
+            failure.get_or_insert(Failure::Output);
+            failed += 1;
+        }
+    }
+
+    match failure {
+        Some(failure) => {
+            error!("{} of {} items failed", failed, contexts.len());
+            Err(failure)
+        }
+        None => {
+            let target = format!("{} items", contexts.len());
+            print_summary(config, registry, config.entry(), &target, size, start);
+            Ok(outputs)
+        }
+    }
+}
+
+/// Print a summary of a successful render to stderr unless quiet.
+fn print_summary(
+    config: &Config,
+    registry: &Handlebars,
+    entry: &str,
+    target: &str,
+    size: usize,
+    start: Instant,
+) {
+    if config.log_level() < log::Level::Warn {
+        return;
+    }
+    let summary = format!(
+        "Rendered {} \u{2192} {} ({}) from {} templates and {} datafiles in {}ms",
+        entry,
+        target,
+        format_size(size),
+        registry.get_templates().len(),
+        config.datafiles().len(),
+        start.elapsed().as_millis()
+    );
+    match config.log_format() {
+        LogFormat::Text => eprintln!("{}", summary),
+        LogFormat::Json => eprintln!(
+            "{}",
+            logger::json_line(log::Level::Info, &summary, Map::new())
+        ),
+    }
+}
 
-        return config.write_output(content);
+/// Print the sorted names of the registered templates to stderr.
+fn print_templates(registry: &Handlebars) {
+    for name in template_names(registry) {
+        eprintln!("{}", name);
+    }
+}
+
+/// The sorted names of the registered templates.
+fn template_names(registry: &Handlebars) -> Vec<String> {
+    let mut names = registry.get_templates().keys().cloned().collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Print the data as pretty JSON to stdout with the secrets masked.
+fn dump_data(data: &serde_json::Value) {
+    let data = secrets::redact(data);
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&data).expect("JSON is serializable")
+    );
+}
+
+/// Format a size in bytes for humans.
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// Fail if `--fail-on-warn` is set and a warning was logged.
+fn check_warnings(config: &Config) -> Result<(), Failure> {
+    let warnings = logger::warnings();
+    if config.fail_on_warn() && warnings > 0 {
+        error!("Failing on {} logged warnings", warnings);
+        return Err(Failure::Warning);
+    }
+    Ok(())
+}
+
+/// Register all templates without rendering to check that they parse.
+pub fn validate(config: Config, report: &mut Report) -> Result<(), Failure> {
+    let start = Instant::now();
+    match config.new_registry() {
+        Ok(registry) => {
+            report.timings.registry = Some(start.elapsed().as_millis());
+            report.templates = template_names(&registry);
+            if config.print_templates() {
+                print_templates(&registry);
+            }
+            println!("Validated {} templates", registry.get_templates().len());
+            check_warnings(&config)
+        }
+        Err(failed) => {
+            failed.log();
+            error!("Validation failed");
+            Err(Failure::Template)
+        }
+    }
+}
+
+/// Print the graph of the templates included by the entry templates to
+/// stdout, without reading data or rendering. Without data the includes of
+/// `include_when` are all registered.
+pub fn graph(config: Config, format: GraphFormat, report: &mut Report) -> Result<(), Failure> {
+    let start = Instant::now();
+    let (registry, sources) = config.new_registry_with_sources(None).map_err(|failed| {
+        failed.log();
+        Failure::Template
+    })?;
+    report.timings.registry = Some(start.elapsed().as_millis());
+    report.templates = template_names(&registry);
+    let graph = Graph::new(&registry, &sources.files, &config.rendered_entries(&registry));
+    print!("{}", graph.format(format));
+    check_warnings(&config)
+}
+
+/// Register all templates and report the templates not reachable from the
+/// entry templates, the missing partials and the variables missing in the
+/// data, without rendering.
+pub fn lint(config: Config, report: &mut Report) -> Result<(), Failure> {
+    let start = Instant::now();
+    report.datafiles = config.datafiles();
+    let mut data = config.read_data().ok_or(Failure::Data)?;
+    report.timings.data = Some(start.elapsed().as_millis());
+    let registry_start = Instant::now();
+    match config.new_registry_with_sources(Some(&data)) {
+        Ok((registry, sources)) => {
+            report.timings.registry = Some(registry_start.elapsed().as_millis());
+            report.templates = template_names(&registry);
+            if !config.compute(&registry, &mut data) {
+                return Err(Failure::Data);
+            }
+            let problems =
+                config.lint(&registry) + config.lint_variables(&registry, &sources, &data);
+            if problems > 0 {
+                error!("Lint found {} problems", problems);
+                return Err(Failure::Template);
+            }
+            println!("Linted {} templates", registry.get_templates().len());
+            check_warnings(&config)
+        }
+        Err(failed) => {
+            failed.log();
+            error!("Lint failed");
+            Err(Failure::Template)
+        }
     }
-    false
 }
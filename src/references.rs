@@ -0,0 +1,1075 @@
+//! Variables referenced by the registered templates.
+//!
+//! The references are collected from the parsed templates without rendering.
+//! The result is an over-approximation: a name counts as referenced if it is
+//! the first segment of any expression, helper argument or hash value, no
+//! matter in which context it is evaluated. Local variables like `@index` are
+//! skipped.
+//!
+//! The paths evaluated against the root of the data are collected more
+//! precisely, to check them against the data before rendering.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use handlebars::{
+    template::{BlockParam, DecoratorTemplate, HelperTemplate, Parameter, TemplateElement},
+    Handlebars, Path, Template,
+};
+
+/// Collect the first segments of all variables referenced by a template.
+pub fn template_names(template: &Template) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+
+    collect_template(template, &mut names);
+    names
+}
+
+fn collect_template(template: &Template, names: &mut BTreeSet<String>) {
+    for element in &template.elements {
+        collect_element(element, names);
+    }
+}
+
+fn collect_element(element: &TemplateElement, names: &mut BTreeSet<String>) {
+    match element {
+        TemplateElement::Expression(helper)
+        | TemplateElement::HtmlExpression(helper)
+        | TemplateElement::HelperBlock(helper) => collect_helper(helper, names),
+        TemplateElement::DecoratorExpression(decorator)
+        | TemplateElement::DecoratorBlock(decorator)
+        | TemplateElement::PartialExpression(decorator)
+        | TemplateElement::PartialBlock(decorator) => collect_decorator(decorator, names),
+        TemplateElement::RawString(_) | TemplateElement::Comment(_) => {}
+    }
+}
+
+fn collect_helper(helper: &HelperTemplate, names: &mut BTreeSet<String>) {
+    collect_param(&helper.name, names);
+    for param in helper.params.iter().chain(helper.hash.values()) {
+        collect_param(param, names);
+    }
+    for template in helper.template.iter().chain(&helper.inverse) {
+        collect_template(template, names);
+    }
+}
+
+/// The name of a decorator or partial is not a variable, only its arguments
+/// and block are collected.
+fn collect_decorator(decorator: &DecoratorTemplate, names: &mut BTreeSet<String>) {
+    for param in decorator.params.iter().chain(decorator.hash.values()) {
+        collect_param(param, names);
+    }
+    if let Some(template) = &decorator.template {
+        collect_template(template, names);
+    }
+}
+
+fn collect_param(param: &Parameter, names: &mut BTreeSet<String>) {
+    match param {
+        Parameter::Name(name) => insert_root(name, names),
+        Parameter::Path(Path::Relative((_, raw))) => insert_root(raw, names),
+        Parameter::Subexpression(subexpression) => {
+            collect_element(subexpression.as_element(), names)
+        }
+        Parameter::Path(Path::Local(_)) | Parameter::Literal(_) => {}
+    }
+}
+
+/// Insert the first segment of a path, skipping `this` and parent
+/// references.
+fn insert_root(raw: &str, names: &mut BTreeSet<String>) {
+    let mut path = raw;
+
+    loop {
+        let rest = ["this.", "this/", "../", "./"]
+            .iter()
+            .find_map(|prefix| path.strip_prefix(prefix));
+        match rest {
+            Some(rest) => path = rest,
+            None => break,
+        }
+    }
+    if path.starts_with('@') || path == "this" {
+        return;
+    }
+
+    let root = match path.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => path.split(['.', '/']).next().unwrap_or_default(),
+    };
+    if !root.is_empty() {
+        names.insert(root.to_owned());
+    }
+}
+
+/// Helpers whose direct parameters may be missing in strict mode.
+const LENIENT_HELPERS: [&str; 3] = ["if", "unless", "default"];
+/// Block helpers rendering their block in a different context.
+const CONTEXT_HELPERS: [&str; 2] = ["each", "with"];
+
+/// Collect the paths of the variables evaluated against the root of the data
+/// when rendering the `entries`, e.g. `person.name`. Partials called without
+/// a context are followed. Blocks of `each` and `with`, and parameters of
+/// `if`, `unless` and `default`, which may be missing in strict mode, are
+/// skipped. Expressions without arguments naming one of the registered
+/// `helpers`, like `{{debug}}`, call the helper and are skipped too.
+pub fn root_paths(
+    registry: &Handlebars,
+    helpers: &BTreeSet<String>,
+    entries: &[&str],
+) -> BTreeSet<String> {
+    root_path_locations(registry, helpers, entries)
+        .into_keys()
+        .collect()
+}
+
+/// Collect the paths like `root_paths`, each with the names of the templates
+/// and the lines referencing it.
+pub fn root_path_locations(
+    registry: &Handlebars,
+    helpers: &BTreeSet<String>,
+    entries: &[&str],
+) -> BTreeMap<String, BTreeSet<(String, usize)>> {
+    let mut walker = RootWalker {
+        registry,
+        helpers,
+        paths: BTreeMap::new(),
+        visited: BTreeSet::new(),
+        guards: Vec::new(),
+        template: String::new(),
+        line: 1,
+    };
+
+    for entry in entries {
+        walker.partial(entry);
+    }
+    walker.paths
+}
+
+struct RootWalker<'a> {
+    registry: &'a Handlebars<'a>,
+    helpers: &'a BTreeSet<String>,
+    paths: BTreeMap<String, BTreeSet<(String, usize)>>,
+    visited: BTreeSet<String>,
+    /// Paths tested by the enclosing `if` and `unless` blocks.
+    guards: Vec<String>,
+    /// The name of the template walked and the line of the element.
+    template: String,
+    line: usize,
+}
+
+impl RootWalker<'_> {
+    fn partial(&mut self, name: &str) {
+        if !self.visited.insert(name.to_owned()) {
+            return;
+        }
+        if let Some(template) = self.registry.get_template(name) {
+            let caller = std::mem::replace(&mut self.template, name.to_owned());
+            let line = self.line;
+            self.template(template);
+            self.template = caller;
+            self.line = line;
+        }
+    }
+
+    fn template(&mut self, template: &Template) {
+        for (index, element) in template.elements.iter().enumerate() {
+            if let Some(mapping) = template.mapping.get(index) {
+                self.line = mapping.0;
+            }
+            self.element(element);
+        }
+    }
+
+    fn element(&mut self, element: &TemplateElement) {
+        match element {
+            TemplateElement::Expression(helper) | TemplateElement::HtmlExpression(helper) => {
+                let is_helper = helper
+                    .name
+                    .as_name()
+                    .is_some_and(|name| self.helpers.contains(name));
+                let is_variable = helper.params.is_empty() && helper.hash.is_empty() && !is_helper;
+                if is_variable {
+                    self.param(&helper.name);
+                } else {
+                    self.helper_params(helper);
+                }
+            }
+            TemplateElement::HelperBlock(helper) => {
+                self.helper_params(helper);
+                let name = match &helper.name {
+                    Parameter::Name(name) => name.as_str(),
+                    _ => "",
+                };
+                let guard = match helper.params.first() {
+                    Some(Parameter::Path(Path::Relative((_, raw)))) => root_path(raw),
+                    _ => None,
+                };
+                if let Some(template) = &helper.template {
+                    if !CONTEXT_HELPERS.contains(&name) {
+                        self.guarded(template, guard.filter(|_| name == "if"));
+                    }
+                }
+                if let Some(inverse) = &helper.inverse {
+                    self.guarded(inverse, guard.filter(|_| name == "unless"));
+                }
+            }
+            TemplateElement::PartialExpression(partial)
+            | TemplateElement::PartialBlock(partial) => {
+                for param in partial.hash.values() {
+                    self.param(param);
+                }
+                if let Some(param) = partial.params.first() {
+                    self.param(param);
+                } else if let Parameter::Name(name) = &partial.name {
+                    self.partial(name);
+                }
+                if let Some(template) = &partial.template {
+                    self.template(template);
+                }
+            }
+            TemplateElement::DecoratorExpression(_)
+            | TemplateElement::DecoratorBlock(_)
+            | TemplateElement::RawString(_)
+            | TemplateElement::Comment(_) => {}
+        }
+    }
+
+    fn helper_params(&mut self, helper: &HelperTemplate) {
+        let lenient = matches!(&helper.name, Parameter::Name(name) if LENIENT_HELPERS.contains(&name.as_str()));
+
+        for param in helper.params.iter().chain(helper.hash.values()) {
+            match param {
+                Parameter::Subexpression(subexpression) => self.element(subexpression.as_element()),
+                _ if lenient => {}
+                param => self.param(param),
+            }
+        }
+    }
+
+    fn param(&mut self, param: &Parameter) {
+        match param {
+            Parameter::Name(name) => self.insert(name),
+            Parameter::Path(Path::Relative((_, raw))) => self.insert(raw),
+            Parameter::Subexpression(subexpression) => self.element(subexpression.as_element()),
+            Parameter::Path(Path::Local(_)) | Parameter::Literal(_) => {}
+        }
+    }
+
+    /// Walk a template rendered only if the `guard` path exists.
+    fn guarded(&mut self, template: &Template, guard: Option<&str>) {
+        match guard {
+            Some(guard) => {
+                self.guards.push(guard.to_owned());
+                self.template(template);
+                self.guards.pop();
+            }
+            None => self.template(template),
+        }
+    }
+
+    fn insert(&mut self, raw: &str) {
+        let Some(path) = root_path(raw) else {
+            return;
+        };
+        let is_guarded = self.guards.iter().any(|guard| {
+            path.strip_prefix(guard.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '/']))
+        });
+        if !is_guarded {
+            self.paths
+                .entry(path.to_owned())
+                .or_default()
+                .insert((self.template.clone(), self.line));
+        }
+    }
+}
+
+/// The path relative to the root of the data, if `raw` is evaluated in the
+/// current context and not a local variable.
+fn root_path(raw: &str) -> Option<&str> {
+    let mut path = raw;
+
+    for prefix in ["@root.", "@root/", "this.", "this/", "./"] {
+        if let Some(rest) = path.strip_prefix(prefix) {
+            path = rest;
+        }
+    }
+    if path.starts_with('@') || path.starts_with("../") || path == "this" || path.is_empty() {
+        return None;
+    }
+    Some(path)
+}
+
+/// Collect the paths of the data the `entries` may use when rendered, e.g.
+/// `person.name`, or `items.*.name` for the items of an array or object
+/// iterated by `each`. The templates are scanned statically, nothing is
+/// rendered. Variables are resolved against the context of the blocks of
+/// `each` and `with`, their block parameters and the context passed to
+/// partials, which are followed like the templates of `include`. A partial or
+/// `include` whose name is computed at render time may be any registered
+/// template, so all of them are followed. All branches are followed, so a
+/// path counts as used even if its branch is never rendered. The empty path
+/// stands for the whole data, e.g. for `{{json this}}` or `{{debug}}` at the
+/// root. Variables in blocks of other helpers are resolved against the
+/// enclosing context, in contexts that can't be resolved statically they are
+/// skipped. Expressions without arguments naming one of the registered
+/// `helpers` call the helper and use no data.
+pub fn used_paths(
+    registry: &Handlebars,
+    helpers: &BTreeSet<String>,
+    entries: &[&str],
+) -> BTreeSet<String> {
+    let mut walker = UsageWalker {
+        registry,
+        helpers,
+        paths: BTreeSet::new(),
+        visited: BTreeSet::new(),
+        partials: Vec::new(),
+        scopes: vec![Some(String::new())],
+        params: Vec::new(),
+    };
+
+    for entry in entries {
+        walker.partial(entry, Some(String::new()));
+    }
+    walker.paths
+}
+
+struct UsageWalker<'a> {
+    registry: &'a Handlebars<'a>,
+    helpers: &'a BTreeSet<String>,
+    paths: BTreeSet<String>,
+    /// The partials walked with the path of their context.
+    visited: BTreeSet<(String, Option<String>)>,
+    /// The partials being walked, to stop at recursive partials.
+    partials: Vec<String>,
+    /// The paths of the enclosing contexts, the innermost last, `None` if
+    /// unknown.
+    scopes: Vec<Option<String>>,
+    /// The block parameters of the enclosing blocks with their paths.
+    params: Vec<BTreeMap<String, Option<String>>>,
+}
+
+impl UsageWalker<'_> {
+    fn partial(&mut self, name: &str, scope: Option<String>) {
+        if self.partials.iter().any(|partial| partial == name)
+            || !self.visited.insert((name.to_owned(), scope.clone()))
+        {
+            return;
+        }
+        if let Some(template) = self.registry.get_template(name) {
+            self.partials.push(name.to_owned());
+            self.scopes.push(scope);
+            self.template(template);
+            self.scopes.pop();
+            self.partials.pop();
+        }
+    }
+
+    fn template(&mut self, template: &Template) {
+        for element in &template.elements {
+            self.element(element);
+        }
+    }
+
+    fn element(&mut self, element: &TemplateElement) {
+        match element {
+            TemplateElement::Expression(helper) | TemplateElement::HtmlExpression(helper) => {
+                if helper.params.is_empty() && helper.hash.is_empty() {
+                    match helper.name.as_name() {
+                        Some("debug") => self.scope(),
+                        Some(name) if self.helpers.contains(name) => {}
+                        _ => self.param(&helper.name),
+                    }
+                } else {
+                    self.helper(helper);
+                }
+            }
+            TemplateElement::HelperBlock(helper) => {
+                self.helper(helper);
+                let name = helper.name.as_name().unwrap_or_default();
+                let context = match helper.params.first() {
+                    Some(param) if CONTEXT_HELPERS.contains(&name) => {
+                        let path = self.resolve(param);
+                        Some(match name {
+                            "each" => path.map(|path| join(&path, "*")),
+                            _ => path,
+                        })
+                    }
+                    _ => None,
+                };
+                if let Some(template) = &helper.template {
+                    match context {
+                        Some(scope) => {
+                            let mut params = BTreeMap::new();
+                            match &helper.block_param {
+                                Some(BlockParam::Single(Parameter::Name(item))) => {
+                                    params.insert(item.to_owned(), scope.clone());
+                                }
+                                Some(BlockParam::Pair((
+                                    Parameter::Name(item),
+                                    Parameter::Name(index),
+                                ))) => {
+                                    params.insert(item.to_owned(), scope.clone());
+                                    params.insert(index.to_owned(), None);
+                                }
+                                _ => {}
+                            }
+                            self.scopes.push(scope);
+                            self.params.push(params);
+                            self.template(template);
+                            self.params.pop();
+                            self.scopes.pop();
+                        }
+                        None => self.template(template),
+                    }
+                }
+                if let Some(inverse) = &helper.inverse {
+                    self.template(inverse);
+                }
+            }
+            TemplateElement::PartialExpression(partial)
+            | TemplateElement::PartialBlock(partial) => {
+                for param in partial.hash.values() {
+                    self.param(param);
+                }
+                let scope = match partial.params.first() {
+                    Some(param) => {
+                        self.param(param);
+                        self.resolve(param)
+                    }
+                    None => self.scopes.last().cloned().flatten(),
+                };
+                match &partial.name {
+                    Parameter::Subexpression(_) => {
+                        self.param(&partial.name);
+                        self.any_partial(scope);
+                    }
+                    name => {
+                        if let Some(name) = literal_name(name) {
+                            self.partial(&name, scope);
+                        }
+                    }
+                }
+                if let Some(template) = &partial.template {
+                    self.template(template);
+                }
+            }
+            TemplateElement::DecoratorBlock(decorator) => {
+                if let Some(template) = &decorator.template {
+                    self.template(template);
+                }
+            }
+            TemplateElement::DecoratorExpression(_)
+            | TemplateElement::RawString(_)
+            | TemplateElement::Comment(_) => {}
+        }
+    }
+
+    fn helper(&mut self, helper: &HelperTemplate) {
+        for param in helper.params.iter().chain(helper.hash.values()) {
+            self.param(param);
+        }
+        let is_include = helper.name.as_name() == Some("include");
+        if let Some(name) = helper.params.first().filter(|_| is_include) {
+            let scope = self.scopes.last().cloned().flatten();
+            match literal_name(name) {
+                Some(name) => self.partial(&name, scope),
+                None => self.any_partial(scope),
+            }
+        }
+    }
+
+    /// Walk every registered template in the context of `scope`, as a
+    /// partial named at render time may be any of them.
+    fn any_partial(&mut self, scope: Option<String>) {
+        let mut names = self
+            .registry
+            .get_templates()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            self.partial(&name, scope.clone());
+        }
+    }
+
+    /// Use the whole current context, if it is known.
+    fn scope(&mut self) {
+        if let Some(Some(scope)) = self.scopes.last() {
+            self.paths.insert(scope.to_owned());
+        }
+    }
+
+    fn param(&mut self, param: &Parameter) {
+        match param {
+            Parameter::Subexpression(subexpression) => self.element(subexpression.as_element()),
+            param => {
+                if let Some(path) = self.resolve(param) {
+                    self.paths.insert(path);
+                }
+            }
+        }
+    }
+
+    /// The path of a variable relative to the root of the data, if its
+    /// context is known.
+    fn resolve(&self, param: &Parameter) -> Option<String> {
+        let raw = match param {
+            Parameter::Name(name) => name.as_str(),
+            Parameter::Path(Path::Relative((_, raw)) | Path::Local((_, _, raw))) => raw.as_str(),
+            _ => return None,
+        };
+        if let Some(rest) = raw.strip_prefix("@root") {
+            return match rest.strip_prefix(['.', '/']) {
+                Some(rest) => Some(rest.to_owned()),
+                None if rest.is_empty() => Some(String::new()),
+                None => None,
+            };
+        }
+        if raw.starts_with('@') {
+            return None;
+        }
+        let mut path = raw;
+        let mut depth = 0;
+        while let Some(rest) = path.strip_prefix("../") {
+            path = rest;
+            depth += 1;
+        }
+        for prefix in ["this.", "this/", "./"] {
+            if let Some(rest) = path.strip_prefix(prefix) {
+                path = rest;
+            }
+        }
+        if path == "this" || path == "." {
+            path = "";
+        }
+        let segments = segments(path);
+        if depth == 0 {
+            let param = segments.first().and_then(|first| {
+                self.params
+                    .iter()
+                    .rev()
+                    .find_map(|params| params.get(*first))
+            });
+            if let Some(param) = param {
+                return param
+                    .as_ref()
+                    .map(|base| join(base, &segments[1..].join(".")));
+            }
+        }
+        let index = self.scopes.len().checked_sub(depth + 1)?;
+        self.scopes[index].as_ref().map(|scope| join(scope, path))
+    }
+}
+
+/// Join two paths with a dot, either may be empty.
+fn join(base: &str, path: &str) -> String {
+    match (base.is_empty(), path.is_empty()) {
+        (true, _) => path.to_owned(),
+        (_, true) => base.to_owned(),
+        _ => format!("{}.{}", base, path),
+    }
+}
+
+/// Collect the names of the templates included when rendering the `entries`,
+/// including the entries themselves. Partials and `include` helpers are
+/// followed if their name is given literally. A template including a partial
+/// under a name computed at render time, like `{{> (lookup_file "status"
+/// state)}}`, may include any registered template, so all of them are used.
+pub fn used_templates(registry: &Handlebars, entries: &[&str]) -> BTreeSet<String> {
+    let mut used = BTreeSet::new();
+    let mut pending = entries
+        .iter()
+        .map(|entry| entry.to_string())
+        .collect::<Vec<_>>();
+
+    while let Some(name) = pending.pop() {
+        if !used.insert(name.clone()) {
+            continue;
+        }
+        if let Some(template) = registry.get_template(&name) {
+            if computes_partial(template) {
+                return registry.get_templates().keys().cloned().collect();
+            }
+            let mut calls = Vec::new();
+            collect_partials(template, &mut calls);
+            pending.extend(calls.into_iter().map(|(partial, _)| partial));
+        }
+    }
+    used
+}
+
+/// Whether the template includes a partial or a template of `include` whose
+/// name is computed at render time. Partial names are computed by a
+/// subexpression, other names like `@partial-block` are taken as written.
+fn computes_partial(template: &Template) -> bool {
+    template.elements.iter().any(|element| match element {
+        TemplateElement::Expression(helper)
+        | TemplateElement::HtmlExpression(helper)
+        | TemplateElement::HelperBlock(helper) => {
+            let is_include = matches!(&helper.name, Parameter::Name(name) if name == "include");
+            helper
+                .params
+                .first()
+                .filter(|_| is_include)
+                .is_some_and(|name| literal_name(name).is_none())
+                || helper
+                    .template
+                    .iter()
+                    .chain(&helper.inverse)
+                    .any(computes_partial)
+        }
+        TemplateElement::PartialExpression(partial) | TemplateElement::PartialBlock(partial) => {
+            matches!(partial.name, Parameter::Subexpression(_))
+                || partial.template.as_ref().is_some_and(computes_partial)
+        }
+        TemplateElement::DecoratorExpression(decorator)
+        | TemplateElement::DecoratorBlock(decorator) => {
+            decorator.template.as_ref().is_some_and(computes_partial)
+        }
+        TemplateElement::RawString(_) | TemplateElement::Comment(_) => false,
+    })
+}
+
+/// Find the shortest chain of partials and `include` helpers given literally
+/// from one of the `entries` to the template `name`. Returns the including
+/// templates with the line of the call, the entry first. The chain is empty
+/// if `name` is an entry or can't be reached.
+pub fn include_chain(registry: &Handlebars, entries: &[&str], name: &str) -> Vec<(String, usize)> {
+    let mut parents = entries
+        .iter()
+        .map(|entry| (entry.to_string(), None))
+        .collect::<BTreeMap<_, Option<(String, usize)>>>();
+    let mut pending = entries
+        .iter()
+        .map(|entry| entry.to_string())
+        .collect::<VecDeque<_>>();
+
+    while let Some(current) = pending.pop_front() {
+        if current == name {
+            break;
+        }
+        let Some(template) = registry.get_template(&current) else {
+            continue;
+        };
+        let mut calls = Vec::new();
+        collect_partials(template, &mut calls);
+        for (partial, line) in calls {
+            if !parents.contains_key(&partial) {
+                parents.insert(partial.clone(), Some((current.clone(), line)));
+                pending.push_back(partial);
+            }
+        }
+    }
+
+    let mut chain = Vec::new();
+    let mut current = name;
+    while let Some(Some((parent, line))) = parents.get(current) {
+        chain.push((parent.to_owned(), *line));
+        current = parent;
+    }
+    chain.reverse();
+    chain
+}
+
+/// Collect the names of the partials and the templates of `include` given
+/// literally in the template, including inline partials.
+pub fn partials(template: &Template) -> BTreeSet<String> {
+    let mut calls = Vec::new();
+    collect_partials(template, &mut calls);
+    calls.into_iter().map(|(name, _)| name).collect()
+}
+
+/// Collect the names of the partials and the templates of `include` given
+/// literally with the lines of the calls.
+fn collect_partials(template: &Template, calls: &mut Vec<(String, usize)>) {
+    for (index, element) in template.elements.iter().enumerate() {
+        let line = template.mapping.get(index).map_or(0, |mapping| mapping.0);
+        match element {
+            TemplateElement::Expression(helper)
+            | TemplateElement::HtmlExpression(helper)
+            | TemplateElement::HelperBlock(helper) => {
+                let is_include = matches!(&helper.name, Parameter::Name(name) if name == "include");
+                if let Some(name) = helper.params.first().filter(|_| is_include) {
+                    calls.extend(literal_name(name).map(|name| (name, line)));
+                }
+                for template in helper.template.iter().chain(&helper.inverse) {
+                    collect_partials(template, calls);
+                }
+            }
+            TemplateElement::PartialExpression(partial)
+            | TemplateElement::PartialBlock(partial) => {
+                calls.extend(literal_name(&partial.name).map(|name| (name, line)));
+                if let Some(template) = &partial.template {
+                    collect_partials(template, calls);
+                }
+            }
+            TemplateElement::DecoratorExpression(decorator)
+            | TemplateElement::DecoratorBlock(decorator) => {
+                if let Some(template) = &decorator.template {
+                    collect_partials(template, calls);
+                }
+            }
+            TemplateElement::RawString(_) | TemplateElement::Comment(_) => {}
+        }
+    }
+}
+
+/// Collect the partials referenced by the registered templates that are
+/// neither registered nor defined as inline partials by any template, as pairs
+/// of the referencing template and the partial. Partial blocks render their
+/// block if the partial is missing and are skipped, as are `@partial-block`
+/// and names computed at render time.
+pub fn missing_partials(registry: &Handlebars) -> BTreeSet<(String, String)> {
+    let mut inline = BTreeSet::new();
+    let mut references = BTreeSet::new();
+
+    for (name, template) in registry.get_templates() {
+        let mut partials = BTreeSet::new();
+        collect_references(template, &mut partials, &mut inline);
+        references.extend(
+            partials
+                .into_iter()
+                .map(|partial| (name.to_owned(), partial)),
+        );
+    }
+    references
+        .into_iter()
+        .filter(|(_, partial)| {
+            registry.get_template(partial).is_none() && !inline.contains(partial)
+        })
+        .collect()
+}
+
+fn collect_references(
+    template: &Template,
+    partials: &mut BTreeSet<String>,
+    inline: &mut BTreeSet<String>,
+) {
+    for element in &template.elements {
+        match element {
+            TemplateElement::Expression(helper)
+            | TemplateElement::HtmlExpression(helper)
+            | TemplateElement::HelperBlock(helper) => {
+                for template in helper.template.iter().chain(&helper.inverse) {
+                    collect_references(template, partials, inline);
+                }
+            }
+            TemplateElement::PartialExpression(partial) => {
+                partials.extend(literal_name(&partial.name).filter(|name| !name.starts_with('@')));
+            }
+            TemplateElement::PartialBlock(partial) => {
+                if let Some(template) = &partial.template {
+                    collect_references(template, partials, inline);
+                }
+            }
+            TemplateElement::DecoratorExpression(decorator)
+            | TemplateElement::DecoratorBlock(decorator) => {
+                let is_inline =
+                    matches!(&decorator.name, Parameter::Name(name) if name == "inline");
+                if let Some(name) = decorator.params.first().filter(|_| is_inline) {
+                    inline.extend(literal_name(name));
+                }
+                if let Some(template) = &decorator.template {
+                    collect_references(template, partials, inline);
+                }
+            }
+            TemplateElement::RawString(_) | TemplateElement::Comment(_) => {}
+        }
+    }
+}
+
+/// The name of a partial given as a name or a string literal.
+fn literal_name(param: &Parameter) -> Option<String> {
+    match param {
+        Parameter::Name(name) => Some(name.clone()),
+        Parameter::Literal(value) => value.as_str().map(str::to_owned),
+        _ => None,
+    }
+}
+
+/// Split a path like `person.name`, `items/0` or `[with space].x` into its
+/// segments.
+pub fn segments(path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut rest = path;
+
+    while !rest.is_empty() {
+        let (segment, next) = match rest.strip_prefix('[') {
+            Some(inner) => match inner.split_once(']') {
+                Some((segment, next)) => (segment, next),
+                None => (inner, ""),
+            },
+            None => match rest.find(['.', '/']) {
+                Some(index) => (&rest[..index], &rest[index..]),
+                None => (rest, ""),
+            },
+        };
+        segments.push(segment);
+        rest = next.strip_prefix(['.', '/']).unwrap_or(next);
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_template_names() {
+        let template = Template::compile(concat!(
+            "{{title}} {{person.firstName}} {{{raw}}} {{[with space].x}}\n",
+            "{{#each cities}}{{@index}} {{this}} {{../title}}{{/each}}\n",
+            "{{#if (lookup flags 0)}}{{else}}{{fallback}}{{/if}}\n",
+            "{{> part name=author}}"
+        ))
+        .unwrap();
+
+        let names = template_names(&template);
+        for name in [
+            "title",
+            "person",
+            "raw",
+            "with space",
+            "cities",
+            "flags",
+            "fallback",
+            "author",
+        ] {
+            assert!(names.contains(name), "{}", name);
+        }
+        assert!(!names.contains("part"));
+        assert!(!names.contains("firstName"));
+        assert!(!names.contains("@index"));
+    }
+
+    #[test]
+    fn collect_root_paths() {
+        let mut registry = Handlebars::new();
+        let helpers = crate::helpers::register(&mut registry)
+            .into_iter()
+            .map(str::to_owned)
+            .collect::<BTreeSet<_>>();
+        registry
+            .register_template_string(
+                "main",
+                concat!(
+                    "{{title}} {{slug heading}} {{@root.person.name}} {{this.subtitle}}\n",
+                    "{{#if optional}}{{shown}} {{optional.x}}{{/if}}{{#unless hidden}}{{else}}{{hidden}}{{/unless}}\n",
+                    "{{#each cities}}{{name}}{{else}}{{fallback}}{{/each}}\n",
+                    "{{#with author}}{{email}}{{/with}} {{default missing \"n/a\"}}\n",
+                    "{{> part}} {{> other item}} {{#if (eq status 1)}}{{/if}} {{debug}}"
+                ),
+            )
+            .unwrap();
+        registry
+            .register_template_string("part", "{{[with space].x}}")
+            .unwrap();
+        registry
+            .register_template_string("other", "{{inner}}")
+            .unwrap();
+
+        let paths = root_paths(&registry, &helpers, &["main"]);
+        let expected = [
+            "[with space].x",
+            "author",
+            "cities",
+            "fallback",
+            "heading",
+            "item",
+            "person.name",
+            "shown",
+            "status",
+            "subtitle",
+            "title",
+        ];
+        assert_eq!(
+            paths.iter().map(String::as_str).collect::<Vec<_>>(),
+            expected
+        );
+
+        let locations = root_path_locations(&registry, &helpers, &["main"]);
+        for (path, template, line) in [
+            ("title", "main", 1),
+            ("fallback", "main", 3),
+            ("author", "main", 4),
+            ("status", "main", 5),
+            ("[with space].x", "part", 1),
+        ] {
+            assert_eq!(
+                locations[path],
+                BTreeSet::from([(template.to_owned(), line)]),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn collect_used_paths() {
+        let mut registry = Handlebars::new();
+        let helpers = crate::helpers::register(&mut registry)
+            .into_iter()
+            .map(str::to_owned)
+            .collect::<BTreeSet<_>>();
+        for (name, source) in [
+            (
+                "main",
+                concat!(
+                    "{{title}} {{#each items as |item|}}{{item.name}} {{price}} {{../currency}} ",
+                    "{{@root.site.url}} {{@index}}{{/each}}\n",
+                    "{{#with author}}{{email}}{{/with}} {{> card person}} {{include \"footer\"}}\n",
+                    "{{#if draft}}{{else}}{{upper (lower tagline)}}{{/if}} {{> missing}}\n",
+                    "{{#> layout}}{{body}}{{/layout}}"
+                ),
+            ),
+            ("card", "{{name}} {{> card}}"),
+            ("layout", "{{> @partial-block}} {{len}}"),
+            ("footer", "{{copyright}}"),
+        ] {
+            registry.register_template_string(name, source).unwrap();
+        }
+
+        let paths = used_paths(&registry, &helpers, &["main"]);
+        let expected = [
+            "author",
+            "author.email",
+            "body",
+            "copyright",
+            "currency",
+            "draft",
+            "items",
+            "items.*.name",
+            "items.*.price",
+            "person",
+            "person.name",
+            "site.url",
+            "tagline",
+            "title",
+        ];
+        assert_eq!(
+            paths.iter().map(String::as_str).collect::<Vec<_>>(),
+            expected
+        );
+
+        registry
+            .register_template_string("main", "{{#each items}}{{json @root}}{{/each}}")
+            .unwrap();
+        let paths = used_paths(&registry, &helpers, &["main"]);
+        assert!(paths.contains(""));
+
+        registry
+            .register_template_string(
+                "main",
+                "{{#with page}}{{> (lookup_file \"status\" state)}}{{debug}}{{/with}}",
+            )
+            .unwrap();
+        registry
+            .register_template_string("status/done", "{{done}} {{include kind}}")
+            .unwrap();
+        let paths = used_paths(&registry, &helpers, &["main"]);
+        for path in [
+            "page",
+            "page.state",
+            "page.done",
+            "page.kind",
+            "page.copyright",
+        ] {
+            assert!(paths.contains(path), "{}", path);
+        }
+        assert!(!paths.contains(""));
+    }
+
+    #[test]
+    fn collect_used_templates() {
+        let mut registry = Handlebars::new();
+        crate::helpers::register(&mut registry);
+        for (name, source) in [
+            (
+                "main",
+                "{{#> layouts/base}}{{#*inline \"body\"}}{{> part}}{{/inline}}{{/layouts/base}}",
+            ),
+            (
+                "layouts/base",
+                "{{#if footer}}{{include \"footer\"}}{{/if}}{{> @partial-block}}",
+            ),
+            ("part", "{{> part}}{{> (lookup this \"dynamic\")}}"),
+            ("footer", ""),
+            ("dynamic", ""),
+            ("unused", "{{> other}}"),
+            ("other", ""),
+        ] {
+            registry.register_template_string(name, source).unwrap();
+        }
+
+        let used = used_templates(&registry, &["main"]);
+        for name in [
+            "main",
+            "layouts/base",
+            "part",
+            "footer",
+            "dynamic",
+            "unused",
+        ] {
+            assert!(used.contains(name), "{}", name);
+        }
+
+        registry
+            .register_template_string("part", "{{> part}}{{include \"dynamic\"}}")
+            .unwrap();
+        let used = used_templates(&registry, &["main"]);
+        for name in ["main", "layouts/base", "part", "footer", "dynamic"] {
+            assert!(used.contains(name), "{}", name);
+        }
+        for name in ["unused", "other"] {
+            assert!(!used.contains(name), "{}", name);
+        }
+
+        registry
+            .register_template_string("part", "{{include (concat \"dyn\" \"amic\")}}")
+            .unwrap();
+        assert_eq!(used_templates(&registry, &["main"]).len(), 7);
+    }
+
+    #[test]
+    fn collect_missing_partials() {
+        let mut registry = Handlebars::new();
+        for (name, source) in [
+            (
+                "main",
+                "{{#> layout}}{{#*inline \"body\"}}{{> header}}{{/inline}}{{/layout}}",
+            ),
+            (
+                "layout",
+                "{{> body}}{{> @partial-block}}{{#if draft}}{{> typo}}{{/if}}",
+            ),
+            (
+                "page",
+                "{{#> missing}}fallback{{/missing}}{{> (lookup this \"x\")}}{{> typo}}",
+            ),
+        ] {
+            registry.register_template_string(name, source).unwrap();
+        }
+
+        let missing = missing_partials(&registry);
+        let expected = [("layout", "typo"), ("main", "header"), ("page", "typo")];
+        assert_eq!(
+            missing
+                .iter()
+                .map(|(template, partial)| (template.as_str(), partial.as_str()))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn split_segments() {
+        assert_eq!(segments("person.name"), ["person", "name"]);
+        assert_eq!(segments("items/0"), ["items", "0"]);
+        assert_eq!(segments("[with space].x"), ["with space", "x"]);
+        assert_eq!(segments("a.[b.c]"), ["a", "b.c"]);
+    }
+}
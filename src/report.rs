@@ -0,0 +1,177 @@
+//! Machine-readable report of a run.
+//!
+//! With `--report` a JSON object is written after the run, also if it fails:
+//! whether it succeeded and its exit code, the registered templates, the data
+//! files read, the outputs written, the timings and the errors and warnings.
+//! The errors and warnings are collected by the logger independent of the log
+//! level and format, with secrets masked, see [`record`]. The key
+//! `schema_version` is increased whenever a key is removed or changes its
+//! meaning, new keys may be added without.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Instant,
+};
+
+use log::{error, info, Level};
+use serde::Serialize;
+
+use crate::exit::Failure;
+
+/// The version of the report schema.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The errors and warnings logged so far.
+static DIAGNOSTICS: Mutex<Vec<(Level, Diagnostic)>> = Mutex::new(Vec::new());
+
+/// Collect an error or warning for the report. Other levels are ignored.
+pub fn record(level: Level, message: String, code: Option<String>) {
+    if level > Level::Warn {
+        return;
+    }
+    let mut diagnostics = DIAGNOSTICS.lock().unwrap_or_else(|err| err.into_inner());
+    diagnostics.push((level, Diagnostic { message, code }));
+}
+
+/// An error or warning logged during the run.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub message: String,
+    /// The stable code of the error, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// The durations of the phases of the run in milliseconds. A phase that was
+/// not reached is `null`.
+#[derive(Debug, Default, Serialize)]
+pub struct Timings {
+    pub data: Option<u128>,
+    pub registry: Option<u128>,
+    pub render: Option<u128>,
+    pub total: u128,
+}
+
+/// The report of a run.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub schema_version: u32,
+    pub success: bool,
+    pub exit_code: u8,
+    /// The names of the registered templates, sorted.
+    pub templates: Vec<String>,
+    /// The data files read, with glob patterns expanded.
+    pub datafiles: Vec<PathBuf>,
+    /// The output files written.
+    pub outputs: Vec<PathBuf>,
+    pub timings: Timings,
+    pub errors: Vec<Diagnostic>,
+    pub warnings: Vec<Diagnostic>,
+    #[serde(skip)]
+    start: Instant,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            success: false,
+            exit_code: 0,
+            templates: Vec::new(),
+            datafiles: Vec::new(),
+            outputs: Vec::new(),
+            timings: Timings::default(),
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Set the outcome of the run, the total time and the errors and warnings
+    /// collected so far.
+    pub fn finish(&mut self, result: Result<(), Failure>) {
+        self.success = result.is_ok();
+        self.exit_code = result.err().map_or(0, |failure| failure as u8);
+        self.timings.total = self.start.elapsed().as_millis();
+
+        let diagnostics = DIAGNOSTICS.lock().unwrap_or_else(|err| err.into_inner());
+        for (level, diagnostic) in diagnostics.iter() {
+            match level {
+                Level::Error => self.errors.push(diagnostic.to_owned()),
+                _ => self.warnings.push(diagnostic.to_owned()),
+            }
+        }
+    }
+
+    /// Write the report as pretty JSON to the file.
+    pub fn write(&self, path: &Path) -> bool {
+        info!("Writing report: {:?}", path);
+        let result = serde_json::to_string_pretty(self)
+            .map_err(std::io::Error::from)
+            .and_then(|json| std::fs::write(path, json + "\n"));
+        if let Err(err) = result {
+            error!("Unable to write report: {:?}", path);
+            error!("{}", err);
+            return false;
+        }
+        true
+    }
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        record(Level::Info, "Reading data file".to_owned(), None);
+        record(
+            Level::Error,
+            "Missing template file".to_owned(),
+            Some("MISSING_TEMPLATE".to_owned()),
+        );
+        record(Level::Warn, "Unused template".to_owned(), None);
+
+        let mut report = Report::new();
+        report.templates = vec!["main".to_owned()];
+        report.finish(Err(Failure::Template));
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["success"], false);
+        assert_eq!(value["exit_code"], 1);
+        assert_eq!(value["templates"], serde_json::json!(["main"]));
+        assert!(value["timings"]["data"].is_null());
+        assert!(value["timings"]["total"].is_u64());
+        assert!(report.errors.contains(&Diagnostic {
+            message: "Missing template file".to_owned(),
+            code: Some("MISSING_TEMPLATE".to_owned()),
+        }));
+        assert!(report
+            .warnings
+            .iter()
+            .any(|w| w.message == "Unused template"));
+        assert!(!value.to_string().contains("Reading data file"));
+        let keys = value.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "schema_version",
+                "success",
+                "exit_code",
+                "templates",
+                "datafiles",
+                "outputs",
+                "timings",
+                "errors",
+                "warnings"
+            ]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! User-defined helpers loaded from [Rhai](https://rhai.rs/) scripts.
+//!
+//! Every public function defined in a script is registered as a Handlebars
+//! helper of the same name. The positional helper arguments are converted from
+//! JSON into Rhai values and passed to the function in order. If the helper is
+//! called with hash arguments, they are passed as an additional trailing
+//! object map. The return value of the function is converted back into JSON:
+//! strings, numbers, booleans, arrays and object maps map onto their JSON
+//! counterparts and `()` becomes `null`.
+
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, RenderErrorReason,
+    ScopedJson,
+};
+use rhai::{
+    serde::{from_dynamic, to_dynamic},
+    Dynamic, Engine, EvalAltResult, FnAccess, Scope, AST,
+};
+
+/// A helper calling a function of a compiled Rhai script.
+struct ScriptHelper {
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+    name: String,
+}
+
+impl HelperDef for ScriptHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let mut args = h
+            .params()
+            .iter()
+            .map(|param| to_dynamic(param.value()))
+            .collect::<Result<Vec<Dynamic>, _>>()
+            .map_err(|err| script_error(&self.name, err))?;
+
+        if !h.hash().is_empty() {
+            let hash = h
+                .hash()
+                .iter()
+                .map(|(key, value)| (*key, value.value()))
+                .collect::<BTreeMap<_, _>>();
+            args.push(to_dynamic(hash).map_err(|err| script_error(&self.name, err))?);
+        }
+
+        let result = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, &self.name, args)
+            .map_err(|err| script_error(&self.name, err))?;
+        let value = from_dynamic(&result).map_err(|err| script_error(&self.name, err))?;
+
+        Ok(ScopedJson::Derived(value))
+    }
+}
+
+fn script_error(name: &str, err: Box<EvalAltResult>) -> RenderError {
+    RenderErrorReason::Other(format!("Script helper \"{}\" failed: {}", name, err)).into()
+}
+
+/// Compile the Rhai script at `path` and register each of its public functions
+/// as a helper. Returns the names of the registered helpers.
+pub fn register_file(
+    registry: &mut Handlebars,
+    path: &Path,
+) -> Result<Vec<String>, Box<EvalAltResult>> {
+    let engine = Arc::new(Engine::new());
+    let ast = Arc::new(engine.compile_file(path.to_owned())?);
+    let names = ast
+        .iter_functions()
+        .filter(|func| func.access == FnAccess::Public)
+        .map(|func| func.name.to_owned())
+        .collect::<Vec<_>>();
+
+    for name in &names {
+        registry.register_helper(
+            name,
+            Box::new(ScriptHelper {
+                engine: engine.clone(),
+                ast: ast.clone(),
+                name: name.clone(),
+            }),
+        );
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn register_helpers() {
+        let mut registry = Handlebars::new();
+        let names = register_file(&mut registry, Path::new("tests/helpers/double.rhai"));
+        assert_eq!(names.unwrap(), vec!["double".to_owned()]);
+
+        let content = registry.render_template("{{double value}}", &json!({"value": 21}));
+        assert_eq!(content.unwrap(), "42");
+    }
+}